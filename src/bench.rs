@@ -0,0 +1,197 @@
+use crate::effects::{Canvas, Effect, EffectInfo};
+use crate::render::Renderer;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "count-allocs")]
+use std::alloc::{GlobalAlloc, Layout, System};
+#[cfg(feature = "count-allocs")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The system allocator, counting calls so `bench` can report allocations per frame.
+///
+/// Only built with the `count-allocs` feature, so the screensaver itself
+/// doesn't pay for two atomic adds on every allocation.
+#[cfg(feature = "count-allocs")]
+struct CountingAllocator;
+
+#[cfg(feature = "count-allocs")]
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+#[cfg(feature = "count-allocs")]
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(feature = "count-allocs")]
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[cfg(feature = "count-allocs")]
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Allocation calls and bytes allocated so far, if they are being counted.
+#[cfg(feature = "count-allocs")]
+fn allocation_counts() -> Option<(u64, u64)> {
+    Some((ALLOCATIONS.load(Ordering::Relaxed), ALLOCATED_BYTES.load(Ordering::Relaxed)))
+}
+
+#[cfg(not(feature = "count-allocs"))]
+fn allocation_counts() -> Option<(u64, u64)> {
+    None
+}
+
+/// Which effects `termsaver bench` runs and how.
+pub struct Bench {
+    pub effects: Vec<&'static EffectInfo>,
+    pub size: (usize, usize), // In terminal cells
+    pub json: bool,
+}
+
+/// Totals for one effect over all benchmarked frames.
+pub struct Measurement {
+    pub name: &'static str,
+    frames: u64,
+    update: Duration,
+    render: Duration,
+    draw: Duration,
+    bytes: u64,
+    allocations: Option<(u64, u64)>, // Calls and bytes, without the count-allocs feature None
+}
+
+/// Discards output, keeping count of how much there was.
+struct ByteCounter(u64);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Run `frames` frames of `effect`, timing the update, the effect's render
+/// into the canvas and the renderer's terminal output separately.
+pub fn measure(
+    name: &'static str,
+    mut effect: Box<dyn Effect>,
+    mut canvas: Canvas,
+    mut renderer: Renderer,
+    frames: usize,
+    dt: f32,
+) -> std::io::Result<Measurement> {
+    let mut out = ByteCounter(0);
+    let mut measurement = Measurement {
+        name,
+        frames: frames as u64,
+        update: Duration::ZERO,
+        render: Duration::ZERO,
+        draw: Duration::ZERO,
+        bytes: 0,
+        allocations: None,
+    };
+    let allocations = allocation_counts();
+
+    for _ in 0..frames {
+        let started = Instant::now();
+        effect.update(dt);
+        let updated = Instant::now();
+        canvas.clear_text();
+        effect.render(&mut canvas);
+        let rendered = Instant::now();
+        renderer.draw(&canvas, &mut out)?;
+        measurement.update += updated - started;
+        measurement.render += rendered - updated;
+        measurement.draw += rendered.elapsed();
+    }
+
+    measurement.bytes = out.0;
+    measurement.allocations = allocations.zip(allocation_counts()).map(|(before, after)| (after.0 - before.0, after.1 - before.1));
+    Ok(measurement)
+}
+
+impl Measurement {
+    fn micros_per_frame(&self, total: Duration) -> f64 {
+        total.as_secs_f64() * 1e6 / self.frames.max(1) as f64
+    }
+
+    fn per_frame(&self, total: u64) -> f64 {
+        total as f64 / self.frames.max(1) as f64
+    }
+
+    /// Allocation calls and bytes per frame, formatted with `format`, or `missing` when not counted.
+    fn allocations_per_frame(&self, format: impl Fn(f64, f64) -> String, missing: &str) -> String {
+        match self.allocations {
+            Some((calls, bytes)) => format(self.per_frame(calls), self.per_frame(bytes)),
+            None => missing.to_string(),
+        }
+    }
+}
+
+/// Print the results as a table, or as JSON for scripts comparing runs.
+pub fn report(bench: &Bench, frames: usize, results: &[Measurement]) {
+    if bench.json {
+        let entries: Vec<String> = results
+            .iter()
+            .map(|m| {
+                format!(
+                    "    {{\"effect\": \"{}\", \"update_us\": {:.2}, \"render_us\": {:.2}, \"draw_us\": {:.2}, \"bytes_per_frame\": {:.1}, {}}}",
+                    m.name,
+                    m.micros_per_frame(m.update),
+                    m.micros_per_frame(m.render),
+                    m.micros_per_frame(m.draw),
+                    m.per_frame(m.bytes),
+                    m.allocations_per_frame(
+                        |calls, bytes| format!("\"allocs_per_frame\": {:.2}, \"alloc_bytes_per_frame\": {:.1}", calls, bytes),
+                        "\"allocs_per_frame\": null, \"alloc_bytes_per_frame\": null"
+                    )
+                )
+            })
+            .collect();
+        println!("{{");
+        println!("  \"cols\": {},", bench.size.0);
+        println!("  \"rows\": {},", bench.size.1);
+        println!("  \"frames\": {},", frames);
+        println!("  \"results\": [");
+        println!("{}", entries.join(",\n"));
+        println!("  ]");
+        println!("}}");
+        return;
+    }
+
+    println!("{}x{} cells, {} frames; times and counts are per frame", bench.size.0, bench.size.1, frames);
+    if allocation_counts().is_none() {
+        println!("Allocations are counted in builds with --features count-allocs");
+    }
+    println!(
+        "{:<10} {:>11} {:>11} {:>11} {:>10} {:>8} {:>11}",
+        "effect", "update", "render", "draw", "bytes", "allocs", "alloc bytes"
+    );
+    for m in results {
+        println!(
+            "{:<10} {:>8.1} µs {:>8.1} µs {:>8.1} µs {:>10.0} {}",
+            m.name,
+            m.micros_per_frame(m.update),
+            m.micros_per_frame(m.render),
+            m.micros_per_frame(m.draw),
+            m.per_frame(m.bytes),
+            m.allocations_per_frame(|calls, bytes| format!("{:>8.1} {:>11.0}", calls, bytes), &format!("{:>8} {:>11}", "-", "-"))
+        );
+    }
+}
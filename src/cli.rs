@@ -0,0 +1,574 @@
+use crate::effects::REGISTRY;
+
+/// A command-line option as shown in the usage text and shell completions.
+pub struct CliOption {
+    pub flag: &'static str,
+    pub value: Option<&'static str>, // Placeholder for the option's argument, if it takes one
+    pub help: &'static str,
+    pub choices: &'static [&'static str],
+}
+
+pub const OPTIONS: &[CliOption] = &[
+    CliOption {
+        flag: "--bg-color",
+        value: Some("RRGGBB"),
+        help: "Set background color as hex (e.g., --bg-color 1a1b26)",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--colors",
+        value: Some("DEPTH"),
+        help: "Color output: truecolor, 256 or 16 (default: detect)",
+        choices: &["truecolor", "256", "16"],
+    },
+    CliOption {
+        flag: "--pixels",
+        value: Some("MODE"),
+        help: "Pixels per cell: half (1x2), quadrant (2x2), sextant (2x3), braille (2x4)",
+        choices: &["half", "quadrant", "sextant", "braille"],
+    },
+    CliOption {
+        flag: "--graphics",
+        value: Some("MODE"),
+        help: "Output: auto, kitty, sixel or cells (default: auto)",
+        choices: &["auto", "kitty", "sixel", "cells"],
+    },
+    CliOption {
+        flag: "--fps",
+        value: Some("N"),
+        help: "Frames drawn per second, 1 to 240 (default: 30); the simulation always runs at 60 Hz",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--quality",
+        value: Some("Q"),
+        help: "Detail level from 0.25 to 1, or auto to lower it when frames are slow (default: auto)",
+        choices: &["auto"],
+    },
+    CliOption {
+        flag: "--show-fps",
+        value: None,
+        help: "Print the achieved frame rate and frame times on exit",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--headless",
+        value: Some("WxH"),
+        help: "Render without a terminal at WxH cells and write the output to stdout",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--frames",
+        value: Some("N"),
+        help: "Number of frames to render with --headless, export or bench (default: 60)",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--duration",
+        value: Some("TIME"),
+        help: "Length of a --headless run, export or bench instead of --frames, e.g. 10s",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--size",
+        value: Some("WxH"),
+        help: "Image size in pixels for export (default: 320x180), or cells for bench (default: 200x60)",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--out",
+        value: Some("PATH"),
+        help: "Where export writes: .gif for an animation, .png or .ppm for numbered frames",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--json",
+        value: None,
+        help: "Print bench results as JSON",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--idle",
+        value: Some("TIME"),
+        help: "How long the shell sits without input before watch starts the screensaver (default: 5m)",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--lock",
+        value: None,
+        help: "Only exit after the passphrase set with termsaver passphrase is entered",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--tmux-lock",
+        value: None,
+        help: "Run as tmux's lock-command; needs a passphrase set with termsaver passphrase",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--record",
+        value: Some("PATH"),
+        help: "Save the output to PATH as an asciicast v2 recording (instead of stdout with --headless)",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--playlist",
+        value: Some("A,B,..."),
+        help: "Rotate through the given effects",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--shuffle",
+        value: None,
+        help: "Rotate in random order (through all effects without --playlist)",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--interval",
+        value: Some("TIME"),
+        help: "Time per effect in a playlist, e.g. 90s, 5m, 1h (default: 5m)",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--transition",
+        value: Some("KIND"),
+        help: "Between effects: crossfade, wipe, dissolve, burn, cut or random (default: crossfade)",
+        choices: &["crossfade", "wipe", "dissolve", "burn", "cut", "random"],
+    },
+    CliOption {
+        flag: "--transition-time",
+        value: Some("TIME"),
+        help: "Transition length, e.g. 1.5s (default: 1s)",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--overlay",
+        value: Some("SPEC"),
+        help: "Draw clock, date, message, hostname or system stats on top, e.g. clock:top-right:ffcc00:outline (repeatable)",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--message",
+        value: Some("TEXT"),
+        help: "Text for the message overlay (shown at the bottom unless placed with --overlay)",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--message-file",
+        value: Some("PATH"),
+        help: "Show the contents of PATH as the message, re-read when it changes",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--proc-root",
+        value: Some("DIR"),
+        help: "Read the system overlay's load, memory and network stats from DIR (default: /proc)",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--disk",
+        value: Some("PATH"),
+        help: "Show disk usage of the filesystem holding PATH in the system overlay (default: /)",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--control-socket",
+        value: Some("PATH"),
+        help: "Take commands from termsaver ctl on a Unix socket at PATH",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--set",
+        value: Some("EFFECT.PARAM=VALUE"),
+        help: "Set an effect parameter, e.g. --set fire.wind=2.0 (repeatable)",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--seed",
+        value: Some("N"),
+        help: "Seed the random generator so a run can be replayed",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--show-seed",
+        value: None,
+        help: "Print the seed in use on exit",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--config",
+        value: Some("PATH"),
+        help: "Read settings from PATH instead of $XDG_CONFIG_HOME/termsaver/config.toml",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--no-config",
+        value: None,
+        help: "Ignore the config file",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--list",
+        value: None,
+        help: "List the available effects and exit",
+        choices: &[],
+    },
+    CliOption {
+        flag: "--completions",
+        value: Some("SHELL"),
+        help: "Print a completion script for bash, zsh or fish and exit",
+        choices: &["bash", "zsh", "fish"],
+    },
+];
+
+/// A subcommand, given as the first argument.
+pub struct Subcommand {
+    pub name: &'static str,
+    pub usage: &'static str, // What may follow the name
+    pub help: &'static str,
+}
+
+pub const SUBCOMMANDS: &[Subcommand] = &[
+    Subcommand {
+        name: "export",
+        usage: "EFFECT --out PATH [--size WxH] [--frames N] [OPTIONS]",
+        help: "Render an effect to a GIF or to numbered PNG or PPM frames",
+    },
+    Subcommand {
+        name: "bench",
+        usage: "[EFFECT...] [--size WxH] [--frames N] [--json] [OPTIONS]",
+        help: "Time how long effects take to update and draw",
+    },
+    Subcommand {
+        name: "watch",
+        usage: "[EFFECT] [--idle TIME] [OPTIONS]",
+        help: "Run a shell and start the screensaver when it sits idle",
+    },
+    Subcommand {
+        name: "passphrase",
+        usage: "",
+        help: "Set the passphrase for --lock",
+    },
+    Subcommand {
+        name: "tmux",
+        usage: "[EFFECT] [--idle TIME]",
+        help: "Print tmux settings that run termsaver in idle panes and as the lock screen",
+    },
+    Subcommand {
+        name: "ctl",
+        usage: "[--control-socket PATH] [COMMAND [ARGS...]]",
+        help: "Send commands to a screensaver started with --control-socket",
+    },
+];
+
+/// A command-line argument: an option with its value, if it takes one, or a plain word.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Arg<'a> {
+    Flag(&'a str, Option<&'a str>),
+    Word(&'a str),
+}
+
+/// Splits a command line into `Arg`s, going by `OPTIONS` for which options take a value.
+pub struct Args<'a> {
+    args: &'a [String],
+    next: usize,
+    ctl_command: bool, // Past the command word of `ctl`, where every argument belongs to the command
+}
+
+impl<'a> Args<'a> {
+    /// `args` starts with the program name, which is skipped.
+    pub fn new(args: &'a [String]) -> Self {
+        Self {
+            args,
+            next: 1,
+            ctl_command: false,
+        }
+    }
+}
+
+impl<'a> Iterator for Args<'a> {
+    type Item = Result<Arg<'a>, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let arg = self.args.get(self.next)?.as_str();
+        self.next += 1;
+        if self.ctl_command || !arg.starts_with('-') {
+            // Once `ctl` has its command word, the rest belongs to the command, dashes and all
+            self.ctl_command |= self.next > 2 && self.args[1] == "ctl";
+            return Some(Ok(Arg::Word(arg)));
+        }
+        let Some(placeholder) = OPTIONS.iter().find(|o| o.flag == arg).and_then(|o| o.value) else {
+            return Some(Ok(Arg::Flag(arg, None)));
+        };
+        match self.args.get(self.next) {
+            Some(value) => {
+                self.next += 1;
+                Some(Ok(Arg::Flag(arg, Some(value))))
+            }
+            None => Some(Err(format!("{} requires a value: {}", arg, placeholder))),
+        }
+    }
+}
+
+/// Keys handled by the main loop for every effect.
+pub const GLOBAL_KEYS: &[(&str, &str)] = &[
+    ("q, Esc, Ctrl+C", "exit"),
+    ("n", "next effect in a playlist"),
+    ("?", "show or hide the help overlay"),
+    ("Tab", "tweak the effect's parameters"),
+];
+
+fn option_spec(option: &CliOption) -> String {
+    match option.value {
+        Some(value) => format!("{} {}", option.flag, value),
+        None => option.flag.to_string(),
+    }
+}
+
+fn option_choices(option: &CliOption) -> Vec<&'static str> {
+    if option.flag == "--playlist" {
+        REGISTRY.iter().map(|info| info.name).collect()
+    } else {
+        option.choices.to_vec()
+    }
+}
+
+pub fn print_usage() {
+    eprintln!("termsaver - Terminal screensaver with various effects");
+    eprintln!();
+    eprintln!("Usage: termsaver [EFFECT] [OPTIONS]");
+    for command in SUBCOMMANDS {
+        eprintln!("       {}", format!("termsaver {} {}", command.name, command.usage).trim_end());
+    }
+    eprintln!();
+    eprintln!("Effects:");
+    for info in REGISTRY {
+        eprintln!("  {:<9} {}", info.name, info.description);
+        let key_width = info.controls.iter().map(|(input, _)| input.len()).max().unwrap_or(0);
+        for (input, action) in info.controls {
+            eprintln!("            {:<width$}  {}", input, action, width = key_width);
+        }
+        // Defaults can be random, so only the names and ranges are listed
+        let params = info.params;
+        let specs: Vec<String> = params.iter().map(|p| format!("{}={}", p.name, p.kind)).collect();
+        let spec_width = specs.iter().map(|s| s.len()).max().unwrap_or(0);
+        for (param, spec) in params.iter().zip(&specs) {
+            eprintln!("            {:<width$}  {}", spec, param.description, width = spec_width);
+        }
+    }
+    eprintln!();
+    eprintln!("Options:");
+    for option in OPTIONS {
+        let spec = option_spec(option);
+        if spec.len() < 18 {
+            eprintln!("  {:<18} {}", spec, option.help);
+        } else {
+            eprintln!("  {}  {}", spec, option.help);
+        }
+    }
+    eprintln!();
+    let keys: Vec<String> = GLOBAL_KEYS.iter().map(|(key, action)| format!("{} = {}", key, action)).collect();
+    eprintln!("Keys: {}", keys.join(", "));
+    eprintln!();
+    eprintln!("Control commands (one per line, or as JSON: {{\"command\": \"set\", \"args\": [\"fire.wind\", 2]}}):");
+    eprintln!("  effect NAME, next, set EFFECT.PARAM VALUE, message [TEXT], pause, resume, status, quit");
+}
+
+pub fn print_list() {
+    for info in REGISTRY {
+        if info.aliases.is_empty() {
+            println!("{:<9} {}", info.name, info.description);
+        } else {
+            println!("{:<9} {} (aliases: {})", info.name, info.description, info.aliases.join(", "));
+        }
+    }
+}
+
+/// Print a completion script for `shell`. Returns false for unknown shells.
+pub fn print_completions(shell: &str) -> bool {
+    let script = match shell {
+        "bash" => bash_completions(),
+        "zsh" => zsh_completions(),
+        "fish" => fish_completions(),
+        _ => return false,
+    };
+    print!("{}", script);
+    true
+}
+
+fn effect_words() -> String {
+    let mut words = Vec::new();
+    for info in REGISTRY {
+        words.push(info.name);
+        words.extend_from_slice(info.aliases);
+    }
+    words.join(" ")
+}
+
+fn subcommand_words() -> String {
+    SUBCOMMANDS.iter().map(|command| command.name).collect::<Vec<_>>().join(" ")
+}
+
+fn bash_completions() -> String {
+    let mut script = String::new();
+    script.push_str("_termsaver() {\n");
+    script.push_str("    local cur=\"${COMP_WORDS[COMP_CWORD]}\"\n");
+    script.push_str("    local prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n");
+    script.push_str("    case \"$prev\" in\n");
+    for option in OPTIONS.iter().filter(|o| o.value.is_some()) {
+        let choices = option_choices(option);
+        if choices.is_empty() {
+            script.push_str(&format!("        {}) return ;;\n", option.flag));
+        } else {
+            script.push_str(&format!(
+                "        {}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;\n",
+                option.flag,
+                choices.join(" ")
+            ));
+        }
+    }
+    script.push_str("    esac\n");
+    let flags: Vec<&str> = OPTIONS.iter().map(|o| o.flag).collect();
+    script.push_str("    if [[ \"$cur\" == -* ]]; then\n");
+    script.push_str(&format!("        COMPREPLY=($(compgen -W \"{} --help\" -- \"$cur\"))\n", flags.join(" ")));
+    script.push_str("    elif [[ $COMP_CWORD -eq 1 ]]; then\n");
+    script.push_str(&format!(
+        "        COMPREPLY=($(compgen -W \"{} {}\" -- \"$cur\"))\n",
+        subcommand_words(),
+        effect_words()
+    ));
+    script.push_str("    else\n");
+    script.push_str(&format!("        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n", effect_words()));
+    script.push_str("    fi\n");
+    script.push_str("}\n");
+    script.push_str("complete -F _termsaver termsaver\n");
+    script
+}
+
+fn zsh_escape(text: &str) -> String {
+    text.replace('\'', "'\\''").replace('[', "\\[").replace(']', "\\]").replace(':', "\\:")
+}
+
+fn zsh_completions() -> String {
+    let mut script = String::new();
+    script.push_str("#compdef termsaver\n\n");
+    script.push_str("_arguments \\\n");
+    for option in OPTIONS {
+        let help = zsh_escape(option.help);
+        match option.value {
+            Some(value) => {
+                let choices = option_choices(option);
+                let action = if choices.is_empty() { " ".to_string() } else { format!("({})", choices.join(" ")) };
+                script.push_str(&format!("  '{}[{}]:{}:{}' \\\n", option.flag, help, value, action));
+            }
+            None => script.push_str(&format!("  '{}[{}]' \\\n", option.flag, help)),
+        }
+    }
+    script.push_str(&format!("  '1:effect or subcommand:({} {})'\n", subcommand_words(), effect_words()));
+    script
+}
+
+fn fish_completions() -> String {
+    let mut script = String::new();
+    script.push_str("complete -c termsaver -f\n");
+    for command in SUBCOMMANDS {
+        script.push_str(&format!(
+            "complete -c termsaver -n __fish_is_first_arg -a {} -d '{}'\n",
+            command.name,
+            command.help.replace('\'', "\\'")
+        ));
+    }
+    for info in REGISTRY {
+        for name in std::iter::once(&info.name).chain(info.aliases) {
+            script.push_str(&format!(
+                "complete -c termsaver -n __fish_is_first_arg -a {} -d '{}'\n",
+                name,
+                info.description.replace('\'', "\\'")
+            ));
+        }
+    }
+    for option in OPTIONS {
+        let flag = option.flag.trim_start_matches("--");
+        let help = option.help.replace('\'', "\\'");
+        if option.value.is_some() {
+            let choices = option_choices(option);
+            script.push_str(&format!(
+                "complete -c termsaver -l {} -x -a '{}' -d '{}'\n",
+                flag,
+                choices.join(" "),
+                help
+            ));
+        } else {
+            script.push_str(&format!("complete -c termsaver -l {} -d '{}'\n", flag, help));
+        }
+    }
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command_line(line: &str) -> Vec<String> {
+        std::iter::once("termsaver").chain(line.split(' ')).map(str::to_string).collect()
+    }
+
+    fn split(args: &[String]) -> Vec<Result<Arg<'_>, String>> {
+        Args::new(args).collect()
+    }
+
+    #[test]
+    fn values_are_never_taken_for_options() {
+        let args = command_line("fire --message --config --no-config --show-fps");
+        assert_eq!(
+            split(&args),
+            [
+                Ok(Arg::Word("fire")),
+                Ok(Arg::Flag("--message", Some("--config"))),
+                Ok(Arg::Flag("--no-config", None)),
+                Ok(Arg::Flag("--show-fps", None)),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_missing_value_is_an_error() {
+        let args = command_line("--show-fps --fps");
+        assert_eq!(
+            split(&args),
+            [Ok(Arg::Flag("--show-fps", None)), Err("--fps requires a value: N".to_string())]
+        );
+    }
+
+    #[test]
+    fn everything_after_the_ctl_command_belongs_to_it() {
+        let args = command_line("ctl --control-socket s message --config x");
+        assert_eq!(
+            split(&args),
+            [
+                Ok(Arg::Word("ctl")),
+                Ok(Arg::Flag("--control-socket", Some("s"))),
+                Ok(Arg::Word("message")),
+                Ok(Arg::Word("--config")),
+                Ok(Arg::Word("x")),
+            ]
+        );
+        // Only as the subcommand: an effect named ctl would be just a word
+        let args = command_line("fire ctl --no-config");
+        assert_eq!(split(&args)[2], Ok(Arg::Flag("--no-config", None)));
+    }
+
+    #[test]
+    fn completions_cover_every_subcommand_and_option() {
+        for (shell, script) in [("bash", bash_completions()), ("zsh", zsh_completions()), ("fish", fish_completions())] {
+            let words: Vec<&str> = script.split(|c: char| c.is_whitespace() || "'\"()[".contains(c)).collect();
+            for command in SUBCOMMANDS {
+                assert!(words.contains(&command.name), "{} completions lack {}", shell, command.name);
+            }
+            for option in OPTIONS {
+                let flag = if shell == "fish" { option.flag.trim_start_matches("--") } else { option.flag };
+                assert!(words.contains(&flag), "{} completions lack {}", shell, option.flag);
+            }
+        }
+    }
+}
@@ -0,0 +1,777 @@
+use crate::effects::{self, EffectInfo};
+use crate::overlay::Overlay;
+use crate::render::color::ColorDepth;
+use crate::render::graphics::Graphics;
+use crate::render::pixels::PixelMode;
+use crate::transition::TransitionKind;
+use crate::{lock, playlist, quality, EffectSetting};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A value from the config file.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    List(Vec<Value>),
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Str(_) => "a string",
+            Value::Int(_) => "an integer",
+            Value::Float(_) => "a number",
+            Value::Bool(_) => "true or false",
+            Value::List(_) => "a list",
+        }
+    }
+
+    pub fn as_str(&self) -> Result<&str, String> {
+        match self {
+            Value::Str(s) => Ok(s),
+            other => Err(format!("expected a string, found {}", other.type_name())),
+        }
+    }
+
+    pub fn as_f64(&self) -> Result<f64, String> {
+        match self {
+            Value::Int(n) => Ok(*n as f64),
+            Value::Float(n) => Ok(*n),
+            other => Err(format!("expected a number, found {}", other.type_name())),
+        }
+    }
+
+    pub fn as_bool(&self) -> Result<bool, String> {
+        match self {
+            Value::Bool(b) => Ok(*b),
+            other => Err(format!("expected true or false, found {}", other.type_name())),
+        }
+    }
+}
+
+/// A `key = value` line.
+pub struct Entry {
+    pub key: String,
+    pub value: Value,
+    pub line: usize,
+}
+
+/// A `[name]` table and the entries under it.
+pub struct Section {
+    pub name: String,
+    pub line: usize,
+    pub entries: Vec<Entry>,
+}
+
+/// A parsed config file: top-level entries followed by named sections.
+pub struct Config {
+    pub global: Vec<Entry>,
+    pub sections: Vec<Section>,
+}
+
+#[derive(Debug)]
+pub struct ConfigError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// `$XDG_CONFIG_HOME/termsaver/config.toml`, falling back to `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("termsaver").join("config.toml"))
+}
+
+/// Read and parse a config file. Errors are formatted as `path:line: message`.
+pub fn load(path: &Path) -> Result<Config, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse(&text).map_err(|e| format!("{}:{}: {}", path.display(), e.line, e.message))
+}
+
+/// What the config file sets, on top of termsaver's defaults. Flags on the
+/// command line override these.
+pub struct Settings {
+    pub effect: String,
+    pub bg_color: Option<(u8, u8, u8)>,
+    pub color_depth: Option<ColorDepth>, // None = detect
+    pub pixel_mode: Option<PixelMode>,
+    pub graphics: Option<Graphics>, // None = detect
+    pub fps: f32,
+    pub quality: Option<f32>, // None = adaptive
+    pub playlist: Option<Vec<&'static EffectInfo>>,
+    pub shuffle: bool,
+    pub interval: Duration,
+    pub transition: Option<TransitionKind>, // None = random
+    pub transition_time: f32,
+    pub idle: Duration,
+    pub overlays: Vec<Overlay>,
+    pub message: Option<String>,
+    pub message_file: Option<PathBuf>,
+    pub proc_root: PathBuf,
+    pub disk: PathBuf,
+    pub control_socket: Option<PathBuf>,
+    pub lock_hash: Option<String>, // From the [lock] section
+    pub effect_settings: Vec<EffectSetting>, // From the effect sections
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            effect: "fire".to_string(),
+            bg_color: None,
+            color_depth: None,
+            pixel_mode: None,
+            graphics: None,
+            fps: crate::DEFAULT_FPS,
+            quality: None,
+            playlist: None,
+            shuffle: false,
+            interval: Duration::from_secs(300),
+            transition: Some(TransitionKind::Crossfade),
+            transition_time: 1.0,
+            idle: Duration::from_secs(300),
+            overlays: Vec::new(),
+            message: None,
+            message_file: None,
+            proc_root: PathBuf::from("/proc"),
+            disk: PathBuf::from("/"),
+            control_socket: None,
+            lock_hash: None,
+            effect_settings: Vec::new(),
+        }
+    }
+}
+
+/// Read the config file and apply it to the defaults. Errors are formatted as `path:line: message`.
+pub fn load_settings(path: &Path) -> Result<Settings, String> {
+    let config = load(path)?;
+    settings(&config).map_err(|e| format!("{}:{}: {}", path.display(), e.line, e.message))
+}
+
+/// Apply a parsed config file to the defaults, checking every value the way the
+/// matching flag would and each effect section against its declared parameters.
+pub fn settings(config: &Config) -> Result<Settings, ConfigError> {
+    let mut settings = Settings::default();
+    for entry in &config.global {
+        apply_global(&mut settings, &entry.key, &entry.value).map_err(|message| ConfigError {
+            line: entry.line,
+            message,
+        })?;
+    }
+
+    for section in &config.sections {
+        // Not an effect: where `termsaver passphrase` keeps the passphrase for --lock
+        if section.name == "lock" {
+            for entry in &section.entries {
+                let hash = match entry.key.as_str() {
+                    "passphrase_hash" => entry
+                        .value
+                        .as_str()
+                        .and_then(|hash| lock::check_hash(hash).map(|()| hash.to_string())),
+                    other => Err(format!("unknown key '{}'", other)),
+                };
+                settings.lock_hash = Some(hash.map_err(|message| ConfigError {
+                    line: entry.line,
+                    message: format!("[lock] {}", message),
+                })?);
+            }
+            continue;
+        }
+        let Some(info) = effects::lookup(&section.name) else {
+            return Err(ConfigError {
+                line: section.line,
+                message: format!("unknown effect [{}]", section.name),
+            });
+        };
+        for entry in &section.entries {
+            let value = info
+                .find_param(&entry.key)
+                .and_then(|param| param.kind.parse_value(&entry.value).map_err(|message| format!("{} {}", entry.key, message)))
+                .map_err(|message| ConfigError {
+                    line: entry.line,
+                    message: format!("[{}] {}", section.name, message),
+                })?;
+            settings.effect_settings.push(EffectSetting {
+                effect: info.name,
+                param: entry.key.clone(),
+                value,
+            });
+        }
+    }
+    Ok(settings)
+}
+
+/// Strings, or one comma-separated string, as the list settings accept either.
+fn string_list(value: &Value) -> Result<Vec<String>, String> {
+    match value {
+        Value::List(items) => items.iter().map(|v| v.as_str().map(str::to_string)).collect(),
+        _ => Ok(value.as_str()?.split(',').map(|s| s.trim().to_string()).collect()),
+    }
+}
+
+fn apply_global(settings: &mut Settings, key: &str, value: &Value) -> Result<(), String> {
+    match key {
+        "effect" => {
+            let name = value.as_str()?;
+            effects::lookup(name).ok_or(format!("unknown effect '{}'", name))?;
+            settings.effect = name.to_string();
+        }
+        "bg_color" => {
+            let hex = value.as_str()?;
+            settings.bg_color = Some(crate::parse_hex_color(hex).ok_or(format!("invalid hex color '{}'", hex))?);
+        }
+        "fps" => {
+            let rate = value.as_f64()? as f32;
+            if !crate::FPS_RANGE.contains(&rate) {
+                return Err(format!("fps must be 1 to 240, got {}", rate));
+            }
+            settings.fps = rate;
+        }
+        "quality" => {
+            settings.quality = match value {
+                Value::Str(text) if text == "auto" => None,
+                _ => {
+                    let level = value.as_f64()? as f32;
+                    if !(quality::MIN_QUALITY..=1.0).contains(&level) {
+                        return Err(format!("quality must be auto or 0.25 to 1, got {}", level));
+                    }
+                    Some(level)
+                }
+            };
+        }
+        "colors" => {
+            let depth = value.as_str()?;
+            settings.color_depth = match depth {
+                "auto" => None,
+                _ => Some(ColorDepth::parse(depth).ok_or(format!("invalid color depth '{}'", depth))?),
+            };
+        }
+        "pixels" => {
+            let mode = value.as_str()?;
+            settings.pixel_mode = Some(PixelMode::parse(mode).ok_or(format!("invalid pixel mode '{}'", mode))?);
+        }
+        "graphics" => {
+            let mode = value.as_str()?;
+            settings.graphics = match mode {
+                "auto" => None,
+                _ => Some(Graphics::parse(mode).ok_or(format!("invalid graphics mode '{}'", mode))?),
+            };
+        }
+        "playlist" => {
+            let mut list = Vec::new();
+            for name in &string_list(value)? {
+                list.push(effects::lookup(name).ok_or(format!("unknown effect '{}' in playlist", name))?);
+            }
+            settings.playlist = Some(list);
+        }
+        "shuffle" => settings.shuffle = value.as_bool()?,
+        "overlays" => {
+            settings.overlays = string_list(value)?.iter().map(|spec| Overlay::parse(spec)).collect::<Result<_, _>>()?;
+        }
+        "message" => settings.message = Some(value.as_str()?.to_string()),
+        "message_file" => settings.message_file = Some(PathBuf::from(value.as_str()?)),
+        "proc_root" => settings.proc_root = PathBuf::from(value.as_str()?),
+        "control_socket" => settings.control_socket = Some(PathBuf::from(value.as_str()?)),
+        "disk" => settings.disk = PathBuf::from(value.as_str()?),
+        "interval" | "transition_time" | "idle" => {
+            let duration = match value {
+                Value::Str(text) => playlist::parse_interval(text).ok_or(format!("invalid duration '{}'", text))?,
+                _ => {
+                    let seconds = value.as_f64()?;
+                    if seconds <= 0.0 {
+                        return Err(format!("{} must be positive", key));
+                    }
+                    Duration::try_from_secs_f64(seconds).map_err(|_| format!("{} is too long", key))?
+                }
+            };
+            match key {
+                "interval" => settings.interval = duration,
+                "idle" => settings.idle = duration,
+                _ => settings.transition_time = duration.as_secs_f32(),
+            }
+        }
+        "transition" => {
+            let kind = value.as_str()?;
+            settings.transition = match kind {
+                "random" => None,
+                _ => Some(TransitionKind::parse(kind).ok_or(format!("invalid transition '{}'", kind))?),
+            };
+        }
+        _ => return Err(format!("unknown setting '{}'", key)),
+    }
+    Ok(())
+}
+
+/// Set `key = value` lines in a section of the file at `path`, keeping everything
+/// else (including comments) as it is. `names` are the names the section may be
+/// written under; a new `[names[0]]` section is appended if none exists. Values
+/// must already be TOML literals.
+pub fn update_section(path: &Path, names: &[&str], values: &[(&str, String)]) -> Result<(), String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+
+    let section_name = |line: &str| {
+        let content = strip_comment(line).trim();
+        content.strip_prefix('[')?.strip_suffix(']').map(|name| name.trim().to_string())
+    };
+    let header = lines.iter().position(|l| section_name(l).is_some_and(|name| names.contains(&name.as_str())));
+
+    match header {
+        Some(header) => {
+            let mut end = lines[header + 1..]
+                .iter()
+                .position(|l| section_name(l).is_some())
+                .map_or(lines.len(), |i| header + 1 + i);
+            for (key, value) in values {
+                let entry = format!("{} = {}", key, value);
+                let existing = (header + 1..end).find(|&i| {
+                    strip_comment(&lines[i]).split_once('=').is_some_and(|(k, _)| k.trim() == *key)
+                });
+                match existing {
+                    Some(i) => {
+                        let comment = &lines[i][strip_comment(&lines[i]).len()..];
+                        lines[i] = if comment.is_empty() { entry } else { format!("{} {}", entry, comment) };
+                    }
+                    None => {
+                        // After the section's last entry, not after the blank lines that follow it
+                        let mut at = end;
+                        while at > header + 1 && lines[at - 1].trim().is_empty() {
+                            at -= 1;
+                        }
+                        lines.insert(at, entry);
+                        end += 1;
+                    }
+                }
+            }
+        }
+        None => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(format!("[{}]", names[0]));
+            lines.extend(values.iter().map(|(key, value)| format!("{} = {}", key, value)));
+        }
+    }
+
+    let mut text = lines.join("\n");
+    text.push('\n');
+    // Never write a file the next start would refuse to load
+    parse(&text).map_err(|e| format!("{}: not saved, the result would not parse ({})", path.display(), e))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Quote a string as a TOML literal.
+pub fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Parse the subset of TOML termsaver uses: tables, strings, numbers, booleans
+/// and single-line arrays.
+pub fn parse(text: &str) -> Result<Config, ConfigError> {
+    let mut config = Config {
+        global: Vec::new(),
+        sections: Vec::new(),
+    };
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let error = |message: String| ConfigError { line, message };
+        let content = strip_comment(raw).trim();
+        if content.is_empty() {
+            continue;
+        }
+
+        if let Some(rest) = content.strip_prefix('[') {
+            let name = rest
+                .strip_suffix(']')
+                .ok_or_else(|| error("missing ']' after section name".to_string()))?
+                .trim();
+            if !is_bare_key(name) {
+                return Err(error(format!("invalid section name '{}'", name)));
+            }
+            if config.sections.iter().any(|s| s.name == name) {
+                return Err(error(format!("section [{}] appears twice", name)));
+            }
+            config.sections.push(Section {
+                name: name.to_string(),
+                line,
+                entries: Vec::new(),
+            });
+            continue;
+        }
+
+        let (key, value) = content
+            .split_once('=')
+            .ok_or_else(|| error(format!("expected 'key = value', found '{}'", content)))?;
+        let key = key.trim();
+        if !is_bare_key(key) {
+            return Err(error(format!("invalid key '{}'", key)));
+        }
+        let value = parse_value(value.trim()).map_err(error)?;
+
+        let entries = match config.sections.last_mut() {
+            Some(section) => &mut section.entries,
+            None => &mut config.global,
+        };
+        if entries.iter().any(|e| e.key == key) {
+            return Err(error(format!("'{}' is set twice", key)));
+        }
+        entries.push(Entry {
+            key: key.to_string(),
+            value,
+            line,
+        });
+    }
+
+    Ok(config)
+}
+
+fn is_bare_key(key: &str) -> bool {
+    !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn strip_comment(line: &str) -> &str {
+    // A '#' starts a comment unless it is inside a string
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '\\' if in_string && !escaped => {
+                escaped = true;
+                continue;
+            }
+            '"' if !escaped => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+fn parse_value(text: &str) -> Result<Value, String> {
+    if text.is_empty() {
+        return Err("missing value after '='".to_string());
+    }
+
+    if let Some(rest) = text.strip_prefix('"') {
+        let (value, len) = parse_string(rest)?;
+        if !rest[len..].trim().is_empty() {
+            return Err(format!("unexpected text after string: '{}'", rest[len..].trim()));
+        }
+        return Ok(Value::Str(value));
+    }
+
+    if let Some(rest) = text.strip_prefix('[') {
+        let inner = rest.strip_suffix(']').ok_or("arrays must be closed with ']' on the same line")?;
+        let mut items = Vec::new();
+        for item in split_list(inner)? {
+            let item = item.trim();
+            if item.is_empty() {
+                continue; // Trailing comma
+            }
+            items.push(parse_value(item)?);
+        }
+        return Ok(Value::List(items));
+    }
+
+    match text {
+        "true" => return Ok(Value::Bool(true)),
+        "false" => return Ok(Value::Bool(false)),
+        _ => {}
+    }
+
+    let number = text.replace('_', "");
+    if let Ok(n) = number.parse::<i64>() {
+        return Ok(Value::Int(n));
+    }
+    if let Ok(n) = number.parse::<f64>()
+        && n.is_finite()
+    {
+        return Ok(Value::Float(n));
+    }
+
+    Err(format!("invalid value '{}' (strings need double quotes)", text))
+}
+
+/// Parse a string body after its opening quote. Returns the value and the bytes consumed.
+fn parse_string(text: &str) -> Result<(String, usize), String> {
+    let mut value = String::new();
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, i + 1)),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, '"')) => value.push('"'),
+                Some((_, '\\')) => value.push('\\'),
+                Some((_, other)) => return Err(format!("unknown escape '\\{}' in string", other)),
+                None => break,
+            },
+            _ => value.push(c),
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+fn split_list(text: &str) -> Result<Vec<&str>, String> {
+    let mut items = Vec::new();
+    let mut start = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '\\' if in_string && !escaped => {
+                escaped = true;
+                continue;
+            }
+            '"' if !escaped => in_string = !in_string,
+            '[' if !in_string => return Err("nested arrays are not supported".to_string()),
+            ',' if !in_string => {
+                items.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        escaped = false;
+    }
+    items.push(&text[start..]);
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::ParamValue;
+
+    fn parse_error(text: &str) -> (usize, String) {
+        match parse(text) {
+            Ok(_) => panic!("parsed: {:?}", text),
+            Err(e) => (e.line, e.message),
+        }
+    }
+
+    fn settings_error(text: &str) -> (usize, String) {
+        match settings(&parse(text).unwrap()) {
+            Ok(_) => panic!("accepted: {:?}", text),
+            Err(e) => (e.line, e.message),
+        }
+    }
+
+    fn value(text: &str) -> Value {
+        let config = parse(&format!("key = {}", text)).unwrap();
+        config.global[0].value.clone()
+    }
+
+    /// A scratch file, removed on drop.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, text: &str) -> Self {
+            let path = env::temp_dir().join(format!("termsaver-config-{}-{}.toml", std::process::id(), name));
+            fs::write(&path, text).unwrap();
+            Self(path)
+        }
+
+        fn read(&self) -> String {
+            fs::read_to_string(&self.0).unwrap()
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn parses_tables_and_entries_with_their_lines() {
+        let config = parse("# settings\nfps = 30\n\n[fire]\nwind = -1.5\n[gol] # board\nrules = \"B3/S23\"\n").unwrap();
+        assert_eq!(config.global.len(), 1);
+        assert_eq!((config.global[0].key.as_str(), config.global[0].line), ("fps", 2));
+        assert_eq!(config.global[0].value, Value::Int(30));
+        let sections: Vec<(&str, usize, usize)> =
+            config.sections.iter().map(|s| (s.name.as_str(), s.line, s.entries.len())).collect();
+        assert_eq!(sections, [("fire", 4, 1), ("gol", 6, 1)]);
+        assert_eq!(config.sections[0].entries[0].value, Value::Float(-1.5));
+        assert_eq!(config.sections[1].entries[0].line, 7);
+    }
+
+    #[test]
+    fn reports_syntax_errors_at_their_line() {
+        assert_eq!(parse_error("fps = 30\n\n[fire\n"), (3, "missing ']' after section name".to_string()));
+        assert_eq!(parse_error("[two words]"), (1, "invalid section name 'two words'".to_string()));
+        assert_eq!(parse_error("# just\nwords"), (2, "expected 'key = value', found 'words'".to_string()));
+        assert_eq!(parse_error("bad key = 1"), (1, "invalid key 'bad key'".to_string()));
+        assert_eq!(parse_error("\n\nfps ="), (3, "missing value after '='".to_string()));
+        assert_eq!(parse_error("a = 1\nmessage = \"open"), (2, "unterminated string".to_string()));
+        assert_eq!(parse_error("message = \"\\q\""), (1, "unknown escape '\\q' in string".to_string()));
+        assert_eq!(parse_error("message = \"a\" b"), (1, "unexpected text after string: 'b'".to_string()));
+        assert_eq!(parse_error("\nlist = [1, 2"), (2, "arrays must be closed with ']' on the same line".to_string()));
+        assert_eq!(parse_error("list = [[1]]"), (1, "nested arrays are not supported".to_string()));
+    }
+
+    #[test]
+    fn rejects_bad_numbers_and_bare_words() {
+        for text in ["1.2.3", "12abc", "inf", "nan", "fire"] {
+            assert_eq!(
+                parse_error(&format!("fps = {}", text)),
+                (1, format!("invalid value '{}' (strings need double quotes)", text))
+            );
+        }
+        assert_eq!(parse_error("a = 1\n\n\nb = 0x10"), (4, "invalid value '0x10' (strings need double quotes)".to_string()));
+    }
+
+    #[test]
+    fn rejects_duplicate_keys_and_tables() {
+        assert_eq!(parse_error("fps = 30\nfps = 60"), (2, "'fps' is set twice".to_string()));
+        assert_eq!(parse_error("[fire]\nwind = 1\n\nwind = 2"), (4, "'wind' is set twice".to_string()));
+        assert_eq!(parse_error("[fire]\n[gol]\n[fire]"), (3, "section [fire] appears twice".to_string()));
+        // The same key in different tables is fine
+        assert!(parse("wind = 1\n[fire]\nwind = 1\n[plasma]\nwind = 2").is_ok());
+    }
+
+    #[test]
+    fn parses_values() {
+        assert_eq!(value("1_000"), Value::Int(1000));
+        assert_eq!(value("-2.5"), Value::Float(-2.5));
+        assert_eq!(value("1e3"), Value::Float(1000.0));
+        assert_eq!(value("true"), Value::Bool(true));
+        assert_eq!(value("\"tab\\there\\nquote\\\" back\\\\\""), Value::Str("tab\there\nquote\" back\\".to_string()));
+        assert_eq!(value("\"\""), Value::Str(String::new()));
+    }
+
+    #[test]
+    fn a_hash_inside_a_string_is_not_a_comment() {
+        assert_eq!(value("\"ticket #42\" # the comment"), Value::Str("ticket #42".to_string()));
+        assert_eq!(value("\"say \\\"#1\\\"\" # \"quoted\""), Value::Str("say \"#1\"".to_string()));
+        assert_eq!(value("[\"#a\", \"b#\"] # list"), Value::List(vec![Value::Str("#a".into()), Value::Str("b#".into())]));
+    }
+
+    #[test]
+    fn parses_single_line_arrays() {
+        assert_eq!(
+            value("[\"fire\", \"a,b\", 3, true,]"),
+            Value::List(vec![Value::Str("fire".into()), Value::Str("a,b".into()), Value::Int(3), Value::Bool(true)])
+        );
+        assert_eq!(value("[]"), Value::List(Vec::new()));
+        assert_eq!(value("[ \"x\" ]"), Value::List(vec![Value::Str("x".into())]));
+    }
+
+    #[test]
+    fn quoted_strings_parse_back_unchanged() {
+        let text = "a \"quote\", a \\ backslash,\ta tab\nand # a hash";
+        assert_eq!(value(&quote(text)), Value::Str(text.to_string()));
+    }
+
+    #[test]
+    fn applies_settings_over_the_defaults() {
+        let settings = settings(&parse("fps = 20\nplaylist = \"fire, gol\"\nquality = \"auto\"\n[fire]\nwind = 1.5\n").unwrap()).unwrap();
+        assert_eq!(settings.fps, 20.0);
+        assert_eq!(settings.playlist.unwrap().iter().map(|info| info.name).collect::<Vec<_>>(), ["fire", "gol"]);
+        assert_eq!(settings.quality, None);
+        assert_eq!(settings.effect, "fire");
+        let tweaks: Vec<(&str, &str, ParamValue)> =
+            settings.effect_settings.iter().map(|s| (s.effect, s.param.as_str(), s.value.clone())).collect();
+        assert_eq!(tweaks, [("fire", "wind", ParamValue::Float(1.5))]);
+    }
+
+    #[test]
+    fn reports_unknown_sections_and_bad_settings_at_their_line() {
+        assert_eq!(settings_error("fps = 30\n\n[nosuch]\n"), (3, "unknown effect [nosuch]".to_string()));
+        assert_eq!(settings_error("colour = 1"), (1, "unknown setting 'colour'".to_string()));
+        assert_eq!(settings_error("\nfps = 500"), (2, "fps must be 1 to 240, got 500".to_string()));
+        assert_eq!(settings_error("playlist = [\"fire\", \"ice\"]"), (1, "unknown effect 'ice' in playlist".to_string()));
+        assert_eq!(settings_error("[fire]\nwind = 9"), (2, "[fire] wind must be between -3 and 3, got 9".to_string()));
+        assert_eq!(
+            settings_error("[fire]\n\nbogus = 1"),
+            (3, "[fire] unknown parameter 'bogus' (expected one of wind, height, palette)".to_string())
+        );
+        assert_eq!(settings_error("[aurora]\nspeed = 1"), (2, "[aurora] this effect has no parameters".to_string()));
+        assert_eq!(settings_error("[lock]\nhash = \"x\""), (2, "[lock] unknown key 'hash'".to_string()));
+    }
+
+    #[test]
+    fn updating_a_section_keeps_everything_else_byte_for_byte() {
+        let before = "\
+# termsaver settings
+fps = 30  # smooth enough
+message = \"[fire] # not a section\"
+
+[fire]
+# Flames
+wind = 0.5   # breezy
+height = 1.0
+
+[plasma] # globe
+tendrils = 4
+";
+        let file = TempFile::new("update", before);
+        update_section(&file.0, &["fire"], &[("wind", "1.5".to_string()), ("palette", quote("blue"))]).unwrap();
+        assert_eq!(
+            file.read(),
+            "\
+# termsaver settings
+fps = 30  # smooth enough
+message = \"[fire] # not a section\"
+
+[fire]
+# Flames
+wind = 1.5 # breezy
+height = 1.0
+palette = \"blue\"
+
+[plasma] # globe
+tendrils = 4
+"
+        );
+
+        // A section under another of its names is updated in place, a missing one appended
+        update_section(&file.0, &["lava", "plasma"], &[("tendrils", "8".to_string())]).unwrap();
+        update_section(&file.0, &["lock"], &[("passphrase_hash", quote("hash"))]).unwrap();
+        let after = file.read();
+        assert!(after.starts_with(&before[..before.find("wind").unwrap()]));
+        assert!(after.ends_with("[plasma] # globe\ntendrils = 8\n\n[lock]\npassphrase_hash = \"hash\"\n"), "{}", after);
+    }
+
+    #[test]
+    fn refuses_to_save_what_would_not_parse() {
+        let file = TempFile::new("refuse", "[fire]\nwind = 1\n");
+        let error = update_section(&file.0, &["fire"], &[("wind", "\"unterminated".to_string())]).unwrap_err();
+        assert!(error.contains("would not parse"), "{}", error);
+        assert_eq!(file.read(), "[fire]\nwind = 1\n");
+    }
+}
@@ -0,0 +1,540 @@
+use crate::effects::{self, EffectInfo};
+use crate::record::json_string;
+use std::fs;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const MAX_LINE: usize = 64 * 1024; // Clients sending longer lines are dropped
+const MAX_LINES_PER_POLL: usize = 16; // From each client; the rest wait for the next frame
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Something to do to the running screensaver, sent over the control socket.
+pub enum Command {
+    Effect(&'static EffectInfo),
+    Next,
+    Set(String), // EFFECT.PARAM=VALUE, checked like --set
+    Message(Option<String>), // None clears it
+    Pause,
+    Resume,
+    Status,
+    Quit,
+}
+
+impl Command {
+    /// Parse a line such as `effect aurora`, `set fire.wind 2`,
+    /// `message "Deploy in progress"` or `pause`, or the same as JSON:
+    /// `{"command": "set", "args": ["fire.wind", 2]}`.
+    fn parse(line: &str) -> Result<Self, String> {
+        let (name, args) = if line.starts_with('{') {
+            parse_json_command(line)?
+        } else {
+            let mut words = split_words(line)?;
+            if words.is_empty() {
+                return Err("empty command".to_string());
+            }
+            let name = words.remove(0);
+            (name, words)
+        };
+
+        let arity = |count: usize| {
+            if args.len() == count {
+                Ok(())
+            } else {
+                Err(format!("{} takes {} argument{}", name, count, if count == 1 { "" } else { "s" }))
+            }
+        };
+        match name.as_str() {
+            "effect" => {
+                arity(1)?;
+                effects::lookup(&args[0]).map(Command::Effect).ok_or(format!("unknown effect '{}'", args[0]))
+            }
+            "next" => arity(0).map(|()| Command::Next),
+            "set" => match &args[..] {
+                [setting] if setting.contains('=') => Ok(Command::Set(setting.clone())),
+                [target, value] => Ok(Command::Set(format!("{}={}", target, value))),
+                _ => Err("set takes EFFECT.PARAM VALUE".to_string()),
+            },
+            // Unquoted words are joined back up, so `message Deploy in progress` works too
+            "message" => Ok(Command::Message((!args.is_empty()).then(|| args.join(" ")))),
+            "pause" => arity(0).map(|()| Command::Pause),
+            "resume" => arity(0).map(|()| Command::Resume),
+            "status" => arity(0).map(|()| Command::Status),
+            "quit" => arity(0).map(|()| Command::Quit),
+            other => Err(format!(
+                "unknown command '{}', expected effect, next, set, message, pause, resume, status or quit",
+                other
+            )),
+        }
+    }
+}
+
+/// Split a line into words, keeping double-quoted text together.
+fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let Some(&first) = chars.peek() else {
+            return Ok(words);
+        };
+        let mut word = String::new();
+        if first == '"' {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some('n') => word.push('\n'),
+                        Some(c) => word.push(c),
+                        None => return Err("unfinished escape".to_string()),
+                    },
+                    Some(c) => word.push(c),
+                    None => return Err("missing closing quote".to_string()),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                word.push(c);
+            }
+        }
+        words.push(word);
+    }
+}
+
+/// Quote words for `split_words`, so `termsaver ctl` can pass its arguments on as one line.
+/// A lone JSON command is passed on as it is.
+pub fn join_words(words: &[String]) -> String {
+    if let [word] = words
+        && word.trim_start().starts_with('{')
+    {
+        return word.replace('\n', " ");
+    }
+    let quoted: Vec<String> = words
+        .iter()
+        .map(|word| {
+            if !word.is_empty() && !word.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\') {
+                return word.clone();
+            }
+            let escaped = word.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+            format!("\"{}\"", escaped)
+        })
+        .collect();
+    quoted.join(" ")
+}
+
+/// The JSON values a command can contain.
+enum Json {
+    Text(String), // Strings, and numbers and booleans as written
+    List(Vec<Json>),
+    Object(Vec<(String, Json)>),
+    Null,
+}
+
+/// Pull `command` and `args` out of a JSON object.
+fn parse_json_command(line: &str) -> Result<(String, Vec<String>), String> {
+    let mut parser = JsonParser { text: line.as_bytes(), at: 0 };
+    let value = parser.value()?;
+    parser.skip_space();
+    if parser.at < parser.text.len() {
+        return Err("unexpected text after the JSON object".to_string());
+    }
+    let Json::Object(fields) = value else {
+        return Err("expected a JSON object".to_string());
+    };
+    let field = |name: &str| fields.iter().find(|(key, _)| key == name).map(|(_, value)| value);
+    let Some(Json::Text(name)) = field("command") else {
+        return Err("expected a \"command\" string".to_string());
+    };
+    let args = match field("args") {
+        None | Some(Json::Null) => Vec::new(),
+        Some(Json::List(items)) => items
+            .iter()
+            .map(|item| match item {
+                Json::Text(text) => Ok(text.clone()),
+                _ => Err("\"args\" can only hold strings, numbers and booleans".to_string()),
+            })
+            .collect::<Result<_, _>>()?,
+        Some(_) => return Err("expected \"args\" to be a list".to_string()),
+    };
+    Ok((name.clone(), args))
+}
+
+struct JsonParser<'a> {
+    text: &'a [u8],
+    at: usize,
+}
+
+impl JsonParser<'_> {
+    fn skip_space(&mut self) {
+        while self.text.get(self.at).is_some_and(|b| b.is_ascii_whitespace()) {
+            self.at += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        self.skip_space();
+        if self.text.get(self.at) != Some(&byte) {
+            return Err(format!("invalid JSON: expected '{}' at offset {}", byte as char, self.at));
+        }
+        self.at += 1;
+        Ok(())
+    }
+
+    /// Parse a comma-separated sequence up to `close`, calling `item` for each element.
+    fn sequence(&mut self, close: u8, mut item: impl FnMut(&mut Self) -> Result<(), String>) -> Result<(), String> {
+        self.skip_space();
+        if self.text.get(self.at) == Some(&close) {
+            self.at += 1;
+            return Ok(());
+        }
+        loop {
+            item(self)?;
+            self.skip_space();
+            match self.text.get(self.at) {
+                Some(b',') => self.at += 1,
+                Some(&b) if b == close => {
+                    self.at += 1;
+                    return Ok(());
+                }
+                _ => return Err(format!("invalid JSON: expected ',' or '{}' at offset {}", close as char, self.at)),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_space();
+        match self.text.get(self.at) {
+            Some(b'{') => {
+                self.at += 1;
+                let mut fields = Vec::new();
+                self.sequence(b'}', |parser| {
+                    parser.skip_space();
+                    let key = parser.string()?;
+                    parser.expect(b':')?;
+                    fields.push((key, parser.value()?));
+                    Ok(())
+                })?;
+                Ok(Json::Object(fields))
+            }
+            Some(b'[') => {
+                self.at += 1;
+                let mut items = Vec::new();
+                self.sequence(b']', |parser| {
+                    items.push(parser.value()?);
+                    Ok(())
+                })?;
+                Ok(Json::List(items))
+            }
+            Some(b'"') => self.string().map(Json::Text),
+            Some(_) => {
+                // Numbers, true, false and null, kept as written
+                let start = self.at;
+                while self.text.get(self.at).is_some_and(|b| b.is_ascii_alphanumeric() || b"+-.".contains(b)) {
+                    self.at += 1;
+                }
+                match std::str::from_utf8(&self.text[start..self.at]).unwrap_or("") {
+                    "" => Err(format!("invalid JSON at offset {}", start)),
+                    "null" => Ok(Json::Null),
+                    word => Ok(Json::Text(word.to_string())),
+                }
+            }
+            None => Err("invalid JSON: unexpected end".to_string()),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.text.get(self.at) != Some(&b'"') {
+            return Err(format!("invalid JSON: expected a string at offset {}", self.at));
+        }
+        self.at += 1;
+        let mut bytes = Vec::new();
+        loop {
+            let Some(&byte) = self.text.get(self.at) else {
+                return Err("invalid JSON: unfinished string".to_string());
+            };
+            self.at += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escape = self.text.get(self.at).copied();
+                    self.at += 1;
+                    let c = match escape {
+                        Some(b'n') => '\n',
+                        Some(b't') => '\t',
+                        Some(b'r') => '\r',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'u') => {
+                            let hex = self.text.get(self.at..self.at + 4).and_then(|hex| std::str::from_utf8(hex).ok());
+                            let code = hex.and_then(|hex| u32::from_str_radix(hex, 16).ok()).ok_or("invalid JSON: bad \\u escape")?;
+                            self.at += 4;
+                            // Surrogate pairs are rare in commands; they become U+FFFD
+                            char::from_u32(code).unwrap_or('\u{fffd}')
+                        }
+                        Some(other) => other as char,
+                        None => return Err("invalid JSON: unfinished escape".to_string()),
+                    };
+                    bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                _ => bytes.push(byte),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| "invalid JSON: not UTF-8".to_string())
+    }
+}
+
+/// A command waiting for the main loop, and the connection to answer on.
+pub struct Request {
+    pub command: Result<Command, String>,
+    json: bool,
+    stream: UnixStream,
+}
+
+impl Request {
+    /// Answer `ok`, `ok TEXT` or `error: MESSAGE`, or the JSON equivalent if the command was JSON.
+    pub fn respond(mut self, result: Result<String, String>) {
+        let reply = match (self.json, result) {
+            (false, Ok(text)) if text.is_empty() => "ok".to_string(),
+            (false, Ok(text)) => format!("ok {}", text),
+            (false, Err(message)) => format!("error: {}", message),
+            (true, Ok(text)) if text.is_empty() => "{\"ok\": true}".to_string(),
+            (true, Ok(text)) => format!("{{\"ok\": true, \"result\": {}}}", json_string(&text)),
+            (true, Err(message)) => format!("{{\"ok\": false, \"error\": {}}}", json_string(&message)),
+        };
+        // A client that went away or stopped reading just misses its reply
+        let _ = writeln!(self.stream, "{}", reply);
+    }
+}
+
+struct Client {
+    stream: UnixStream,
+    buffer: Vec<u8>,
+    closed: bool, // The client has finished sending; what is buffered is all there is
+}
+
+impl Client {
+    /// Move up to `limit` complete lines out of the buffer, blank ones included,
+    /// and return how many were taken.
+    fn take_lines(&mut self, requests: &mut Vec<Request>, limit: usize) -> usize {
+        let mut taken = 0;
+        while taken < limit
+            && let Some(end) = self.buffer.iter().position(|&b| b == b'\n')
+        {
+            taken += 1;
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            // Replies go out on a second handle, after this client may have been dropped
+            let Ok(stream) = self.stream.try_clone() else {
+                continue;
+            };
+            requests.push(Request {
+                command: Command::parse(line),
+                json: line.starts_with('{'),
+                stream,
+            });
+        }
+        taken
+    }
+}
+
+/// Listens on a Unix socket for commands, one per line, without ever blocking the frame loop.
+pub struct ControlServer {
+    path: PathBuf,
+    listener: UnixListener,
+    clients: Vec<Client>,
+}
+
+impl ControlServer {
+    /// Listen at `path`, replacing a socket left behind by a screensaver that is no longer running.
+    /// Only the owner can connect.
+    pub fn bind(path: &Path) -> io::Result<Self> {
+        if let Ok(metadata) = fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                return Err(io::Error::new(ErrorKind::AlreadyExists, "a file that is not a socket is in the way"));
+            }
+            if UnixStream::connect(path).is_ok() {
+                return Err(io::Error::new(ErrorKind::AddrInUse, "another termsaver is listening there"));
+            }
+            fs::remove_file(path)?;
+        }
+        // Created without group or other permissions, so nobody else can connect even briefly
+        // SAFETY: umask only swaps the process's file creation mask
+        let umask = unsafe { libc::umask(0o177) };
+        let listener = UnixListener::bind(path);
+        unsafe { libc::umask(umask) };
+        let listener = listener?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            listener,
+            clients: Vec::new(),
+        })
+    }
+
+    /// Accept new connections and collect the commands that have arrived, in order.
+    pub fn poll(&mut self) -> Vec<Request> {
+        while let Ok((stream, _)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                self.clients.push(Client {
+                    stream,
+                    buffer: Vec::new(),
+                    closed: false,
+                });
+            }
+        }
+
+        let mut requests = Vec::new();
+        self.clients.retain_mut(|client| {
+            // Reading stops at a limit on lines, so a client that never stops sending can't hold up the frame
+            let mut chunk = [0u8; 4096];
+            let mut taken = 0;
+            loop {
+                taken += client.take_lines(&mut requests, MAX_LINES_PER_POLL - taken);
+                if taken == MAX_LINES_PER_POLL {
+                    return true;
+                }
+                if client.closed || client.buffer.len() > MAX_LINE {
+                    return false;
+                }
+                match client.stream.read(&mut chunk) {
+                    Ok(0) => {
+                        // The last line may lack a newline once the client has finished sending
+                        client.buffer.push(b'\n');
+                        client.closed = true;
+                    }
+                    Ok(count) => client.buffer.extend_from_slice(&chunk[..count]),
+                    Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                    Err(err) => return err.kind() == ErrorKind::WouldBlock,
+                }
+            }
+        });
+        requests
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Send command lines to the screensaver listening at `path`, printing what
+/// comes back. Returns false if any command failed.
+pub fn send(path: &Path, commands: &[String]) -> io::Result<bool> {
+    let mut stream = UnixStream::connect(path)?;
+    stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+    for command in commands {
+        writeln!(stream, "{}", command)?;
+    }
+    stream.shutdown(Shutdown::Write)?;
+
+    let mut all_ok = true;
+    for reply in BufReader::new(stream).lines() {
+        let reply = reply?;
+        if let Some(message) = reply.strip_prefix("error: ") {
+            eprintln!("{}", message);
+            all_ok = false;
+        } else if let Some(text) = reply.strip_prefix("ok ") {
+            println!("{}", text);
+        } else if reply != "ok" {
+            // JSON replies to JSON commands are passed on as they are
+            all_ok &= !reply.contains("\"ok\": false");
+            println!("{}", reply);
+        }
+    }
+    Ok(all_ok)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn socket_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("termsaver-control-{}-{}.sock", std::process::id(), name))
+    }
+
+    /// Poll until `count` requests have come in, or give up after a second.
+    fn poll_for(server: &mut ControlServer, count: usize) -> Vec<Request> {
+        let mut requests = Vec::new();
+        for _ in 0..100 {
+            requests.extend(server.poll());
+            if requests.len() >= count {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        requests
+    }
+
+    #[test]
+    fn passes_a_lone_json_argument_through() {
+        let json = r#"{"command": "message", "args": ["hi there"]}"#;
+        assert_eq!(join_words(&[json.to_string()]), json);
+        assert!(matches!(Command::parse(&join_words(&[json.to_string()])), Ok(Command::Message(Some(text))) if text == "hi there"));
+        let words = ["message".to_string(), "hi \"you\"".to_string()];
+        assert_eq!(join_words(&words), r#"message "hi \"you\"""#);
+        assert!(matches!(Command::parse(&join_words(&words)), Ok(Command::Message(Some(text))) if text == "hi \"you\""));
+    }
+
+    #[test]
+    fn only_the_owner_can_connect() {
+        let path = socket_path("mode");
+        let server = ControlServer::bind(&path).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        drop(server);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn reads_commands_and_drops_clients_with_overlong_lines() {
+        let path = socket_path("poll");
+        let mut server = ControlServer::bind(&path).unwrap();
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        client.write_all(b"pause\n\nstatus").unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+        let requests = poll_for(&mut server, 2);
+        assert!(matches!(requests[0].command, Ok(Command::Pause)));
+        assert!(matches!(requests[1].command, Ok(Command::Status)));
+
+        // Far more than a line's worth without a newline: the client is cut off, not buffered
+        let mut flood = UnixStream::connect(&path).unwrap();
+        let written = std::thread::spawn(move || {
+            let chunk = [b'x'; 4096];
+            (0..64).all(|_| flood.write_all(&chunk).is_ok())
+        });
+        for _ in 0..100 {
+            assert!(server.poll().is_empty());
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(server.clients.is_empty());
+        assert!(!written.join().unwrap());
+    }
+
+    #[test]
+    fn limits_the_lines_taken_from_a_client_each_poll() {
+        let path = socket_path("limit");
+        let mut server = ControlServer::bind(&path).unwrap();
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        client.write_all(&b"\n".repeat(40)).unwrap();
+        client.write_all(&b"status\n".repeat(40)).unwrap();
+        client.write_all(b"quit").unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+
+        // Blank lines count toward the limit, so a flood of them can't stall a frame either
+        let counts: Vec<usize> = (0..8).map(|_| server.poll().len()).collect();
+        assert_eq!(counts, [0, 0, 8, 16, 16, 1, 0, 0]);
+        assert!(server.clients.is_empty());
+    }
+}
@@ -1,7 +1,7 @@
-use super::Effect;
+use super::{Canvas, Effect, EffectInfo};
 use crossterm::event::Event;
+use fastrand::Rng;
 use noise::{NoiseFn, Perlin};
-use std::io::{BufWriter, Stdout, Write};
 
 const AURORA_COLORS: [(u8, u8, u8); 5] = [
     (30, 255, 120),   // Bright green
@@ -16,8 +16,8 @@ pub struct AuroraEffect {
     height: usize,
     time: f32,
     noise: Perlin,
-    output_buf: Vec<u8>,
     curtains: Vec<AuroraCurtain>,
+    block: usize, // Columns sharing one set of noise samples; above 1 at reduced quality
 }
 
 struct AuroraCurtain {
@@ -31,37 +31,46 @@ struct AuroraCurtain {
 }
 
 impl AuroraCurtain {
-    fn new(color_idx: usize, base_y_ratio: f32) -> Self {
+    fn new(rng: &mut Rng, color_idx: usize, base_y_ratio: f32) -> Self {
         Self {
             base_y: base_y_ratio,
             color_idx,
-            wave_offset: fastrand::f32() * 100.0,
-            wave_speed: 0.3 + fastrand::f32() * 0.4,
-            wave_amplitude: 8.0 + fastrand::f32() * 12.0,
-            intensity: 0.35 + fastrand::f32() * 0.25,  // Reduced from 0.6-1.0 to 0.35-0.6
-            height_scale: 0.3 + fastrand::f32() * 0.5,
+            wave_offset: rng.f32() * 100.0,
+            wave_speed: 0.3 + rng.f32() * 0.4,
+            wave_amplitude: 8.0 + rng.f32() * 12.0,
+            intensity: 0.35 + rng.f32() * 0.25,  // Reduced from 0.6-1.0 to 0.35-0.6
+            height_scale: 0.3 + rng.f32() * 0.5,
         }
     }
 }
 
+pub const INFO: EffectInfo = EffectInfo {
+    name: "aurora",
+    aliases: &[],
+    description: "Aurora borealis with smooth wave-like patterns",
+    controls: &[],
+    params: &[],
+    create: super::construct::<AuroraEffect>,
+};
+
 impl Effect for AuroraEffect {
-    fn new(width: usize, height: usize) -> Self {
+    fn new(width: usize, height: usize, mut rng: Rng) -> Self {
         // Create multiple aurora curtains at different heights
         let curtains = vec![
-            AuroraCurtain::new(0, 0.2),  // Green curtain
-            AuroraCurtain::new(1, 0.3),  // Cyan curtain
-            AuroraCurtain::new(2, 0.25), // Blue curtain
-            AuroraCurtain::new(3, 0.35), // Purple curtain
-            AuroraCurtain::new(4, 0.28), // Magenta curtain
+            AuroraCurtain::new(&mut rng, 0, 0.2),  // Green curtain
+            AuroraCurtain::new(&mut rng, 1, 0.3),  // Cyan curtain
+            AuroraCurtain::new(&mut rng, 2, 0.25), // Blue curtain
+            AuroraCurtain::new(&mut rng, 3, 0.35), // Purple curtain
+            AuroraCurtain::new(&mut rng, 4, 0.28), // Magenta curtain
         ];
 
         Self {
             width,
             height,
             time: 0.0,
-            noise: Perlin::new(fastrand::u32(..)),
-            output_buf: Vec::with_capacity(width * height * 25),
+            noise: Perlin::new(rng.u32(..)),
             curtains,
+            block: 1,
         }
     }
 
@@ -73,10 +82,7 @@ impl Effect for AuroraEffect {
         }
     }
 
-    fn render(&mut self, stdout: &mut BufWriter<Stdout>) -> std::io::Result<()> {
-        self.output_buf.clear();
-        self.output_buf.extend_from_slice(b"\x1b[H");
-
+    fn render(&mut self, canvas: &mut Canvas) {
         let bg_color = crate::get_bg_color();
         // Initialize with background color to avoid artifacts
         let bg_float = (bg_color.0 as f32, bg_color.1 as f32, bg_color.2 as f32);
@@ -86,23 +92,28 @@ impl Effect for AuroraEffect {
         for curtain in &self.curtains {
             let base_color = AURORA_COLORS[curtain.color_idx];
             let curtain_base_y = curtain.base_y * self.height as f32;
+            let mut samples = (0.0, 0.0, 0.0);
 
             for x in 0..self.width {
-                // Use Perlin noise for smooth horizontal wave
-                // Add curtain index to noise coordinates to avoid banding between curtains
-                let noise_x = x as f64 * 0.015;  // Slightly coarser for smoother waves
-                let noise_t = (self.time * curtain.wave_speed + curtain.wave_offset) as f64;
-                let noise_z = curtain.color_idx as f64 * 10.0;  // Separate each curtain in noise space
+                if x % self.block == 0 {
+                    // Use Perlin noise for smooth horizontal wave
+                    // Add curtain index to noise coordinates to avoid banding between curtains
+                    let noise_x = x as f64 * 0.015;  // Slightly coarser for smoother waves
+                    let noise_t = (self.time * curtain.wave_speed + curtain.wave_offset) as f64;
+                    let noise_z = curtain.color_idx as f64 * 10.0;  // Separate each curtain in noise space
 
-                let wave_y = self.noise.get([noise_x, noise_t, noise_z]) as f32;
-                let wave_offset = wave_y * curtain.wave_amplitude;
+                    let wave_y = self.noise.get([noise_x, noise_t, noise_z]) as f32;
 
-                // Second noise layer for vertical undulation (makes curtains taller/shorter)
-                let vertical_noise = self.noise.get([noise_x * 0.5, noise_t * 0.7, noise_z + 100.0]) as f32;
-                let height_variation = 1.0 + vertical_noise * 0.4;  // Reduced variation
+                    // Second noise layer for vertical undulation (makes curtains taller/shorter)
+                    let vertical_noise = self.noise.get([noise_x * 0.5, noise_t * 0.7, noise_z + 100.0]) as f32;
 
-                // Third noise layer for intensity variation
-                let intensity_noise = self.noise.get([noise_x * 0.3, noise_t * 0.5, noise_z + 200.0]) as f32;
+                    // Third noise layer for intensity variation
+                    let intensity_noise = self.noise.get([noise_x * 0.3, noise_t * 0.5, noise_z + 200.0]) as f32;
+                    samples = (wave_y, vertical_noise, intensity_noise);
+                }
+                let (wave_y, vertical_noise, intensity_noise) = samples;
+                let wave_offset = wave_y * curtain.wave_amplitude;
+                let height_variation = 1.0 + vertical_noise * 0.4;  // Reduced variation
                 let intensity_mod = 0.75 + (intensity_noise * 0.5 + 0.5) * 0.25;
 
                 // Skip this entire column if intensity is too low
@@ -166,55 +177,19 @@ impl Effect for AuroraEffect {
         // Add stars twinkling in the background
         self.add_stars(&mut frame_buffer);
 
-        // Convert frame buffer to colors and render
-        let mut prev_top_color: (u8, u8, u8) = (255, 255, 255);
-        let mut prev_bot_color: (u8, u8, u8) = (255, 255, 255);
-
-        for y in (0..self.height).step_by(2) {
-            for x in 0..self.width {
-                let top_idx = y * self.width + x;
-                let bot_idx = if y + 1 < self.height {
-                    (y + 1) * self.width + x
-                } else {
-                    top_idx
-                };
-
-                let top_color = self.blend_with_background(frame_buffer[top_idx], bg_color);
-                let bot_color = self.blend_with_background(frame_buffer[bot_idx], bg_color);
-
-                if top_color != prev_top_color {
-                    write!(
-                        self.output_buf,
-                        "\x1b[48;2;{};{};{}m",
-                        top_color.0, top_color.1, top_color.2
-                    )?;
-                    prev_top_color = top_color;
-                }
-                if bot_color != prev_bot_color {
-                    write!(
-                        self.output_buf,
-                        "\x1b[38;2;{};{};{}m",
-                        bot_color.0, bot_color.1, bot_color.2
-                    )?;
-                    prev_bot_color = bot_color;
-                }
-
-                self.output_buf.extend_from_slice("▄".as_bytes());
-            }
-            self.output_buf.extend_from_slice(b"\x1b[0m");
-            prev_top_color = (255, 255, 255);
-            prev_bot_color = (255, 255, 255);
-            if y + 2 < self.height {
-                self.output_buf.extend_from_slice(b"\r\n");
-            }
-        }
+        canvas.copy_from_f32(&frame_buffer, self.width);
+    }
 
-        stdout.write_all(&self.output_buf)?;
-        stdout.flush()?;
-        Ok(())
+    fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
     }
 
     fn handle_event(&mut self, _event: &Event) {}
+
+    fn set_quality(&mut self, quality: f32) {
+        self.block = (1.0 / quality).round().max(1.0) as usize;
+    }
 }
 
 impl AuroraEffect {
@@ -269,13 +244,4 @@ impl AuroraEffect {
             }
         }
     }
-
-    fn blend_with_background(&self, color: (f32, f32, f32), _bg: (u8, u8, u8)) -> (u8, u8, u8) {
-        // Clamp color values (background is already included in frame buffer)
-        (
-            color.0.round().clamp(0.0, 255.0) as u8,
-            color.1.round().clamp(0.0, 255.0) as u8,
-            color.2.round().clamp(0.0, 255.0) as u8,
-        )
-    }
 }
@@ -1,9 +1,10 @@
-use super::Effect;
+use super::{Canvas, Effect, EffectInfo, ParamInfo, ParamKind, ParamValue};
 use crossterm::event::Event;
-use std::io::{BufWriter, Stdout, Write};
+use fastrand::Rng;
 
 const DEEP_WATER: (u8, u8, u8) = (2, 8, 20);
 const FISH_GLOW: (u8, u8, u8) = (40, 150, 255);
+const TRAIL_LENGTH: usize = 8;
 
 struct Fish {
     x: f32,
@@ -24,14 +25,14 @@ struct Fish {
 }
 
 impl Fish {
-    fn new(x: f32, y: f32, size: f32) -> Self {
-        let base_brightness = 0.5 + fastrand::f32() * 0.5;
-        let blink_type = fastrand::u8(0..3);
-        let independence = if fastrand::f32() < 0.3 {
+    fn new(rng: &mut Rng, x: f32, y: f32, size: f32) -> Self {
+        let base_brightness = 0.5 + rng.f32() * 0.5;
+        let blink_type = rng.u8(0..3);
+        let independence = if rng.f32() < 0.3 {
             // 30% of fish are more independent
-            0.5 + fastrand::f32() * 0.5
+            0.5 + rng.f32() * 0.5
         } else {
-            fastrand::f32() * 0.3
+            rng.f32() * 0.3
         };
 
         Self {
@@ -45,15 +46,16 @@ impl Fish {
             base_brightness,
             brightness: base_brightness,
             target_brightness: base_brightness,
-            blink_phase: fastrand::f32() * std::f32::consts::PI * 2.0,
-            blink_speed: 0.5 + fastrand::f32() * 2.5,
+            blink_phase: rng.f32() * std::f32::consts::PI * 2.0,
+            blink_speed: 0.5 + rng.f32() * 2.5,
             blink_type,
             independence,
             trail: Vec::new(),
         }
     }
 
-    fn update(&mut self, dt: f32, others: &[Fish], width: usize, height: usize, current_x: f32, current_y: f32, _time: f32) {
+    #[allow(clippy::too_many_arguments)]
+    fn update(&mut self, rng: &mut Rng, dt: f32, others: &[Fish], width: usize, height: usize, current_x: f32, current_y: f32, _time: f32) {
         // Update blink phase
         self.blink_phase += dt * self.blink_speed;
         if self.blink_phase > std::f32::consts::PI * 2.0 {
@@ -130,9 +132,9 @@ impl Fish {
 
         // More frequent random darting for independent fish
         let dart_chance = 0.02 + self.independence * 0.06;
-        if fastrand::f32() < dart_chance {
-            self.target_vx = (fastrand::f32() - 0.5) * 80.0;
-            self.target_vy = (fastrand::f32() - 0.5) * 60.0;
+        if rng.f32() < dart_chance {
+            self.target_vx = (rng.f32() - 0.5) * 80.0;
+            self.target_vy = (rng.f32() - 0.5) * 60.0;
         }
 
         // Apply independence - reduce schooling forces
@@ -173,7 +175,7 @@ impl Fish {
 
         // Update trail
         self.trail.push((self.x, self.y, 1.0));
-        if self.trail.len() > 8 {
+        if self.trail.len() > TRAIL_LENGTH {
             self.trail.remove(0);
         }
 
@@ -189,28 +191,39 @@ pub struct BioluminescenceEffect {
     height: usize,
     time: f32,
     fish: Vec<Fish>,
-    output_buf: Vec<u8>,
+    trail_points: usize, // Newest trail points drawn per fish; fewer at reduced quality
+    rng: Rng,
 }
 
-impl Effect for BioluminescenceEffect {
-    fn new(width: usize, height: usize) -> Self {
-        let mut fish = Vec::new();
-
-        // Spawn large swarm of fish
-        for _ in 0..70 {
-            let x = fastrand::f32() * width as f32;
-            let y = fastrand::f32() * height as f32;
-            let size = 1.0 + fastrand::f32() * 1.5;
-            fish.push(Fish::new(x, y, size));
-        }
+pub const INFO: EffectInfo = EffectInfo {
+    name: "biolum",
+    aliases: &["bioluminescence"],
+    description: "Deep sea bioluminescence with jellyfish and schooling fish",
+    controls: &[],
+    params: &[
+        ParamInfo {
+            name: "fish",
+            description: "Size of the school",
+            kind: ParamKind::Int { min: 0, max: 300 },
+        },
+    ],
+    create: super::construct::<BioluminescenceEffect>,
+};
 
-        Self {
+impl Effect for BioluminescenceEffect {
+    fn new(width: usize, height: usize, rng: Rng) -> Self {
+        let mut effect = Self {
             width,
             height,
             time: 0.0,
-            fish,
-            output_buf: Vec::with_capacity(width * height * 25),
-        }
+            fish: Vec::new(),
+            trail_points: TRAIL_LENGTH,
+            rng,
+        };
+
+        // Spawn large swarm of fish
+        effect.set_fish_count(70);
+        effect
     }
 
     fn update(&mut self, dt: f32) {
@@ -244,14 +257,11 @@ impl Effect for BioluminescenceEffect {
         }).collect();
 
         for i in 0..self.fish.len() {
-            self.fish[i].update(dt, &fish_clone, self.width, self.height, current_x, current_y, self.time);
+            self.fish[i].update(&mut self.rng, dt, &fish_clone, self.width, self.height, current_x, current_y, self.time);
         }
     }
 
-    fn render(&mut self, stdout: &mut BufWriter<Stdout>) -> std::io::Result<()> {
-        self.output_buf.clear();
-        self.output_buf.extend_from_slice(b"\x1b[H");
-
+    fn render(&mut self, canvas: &mut Canvas) {
         let bg_color = crate::get_bg_color();
         let water_color = if bg_color == (0, 0, 0) {
             DEEP_WATER
@@ -264,7 +274,8 @@ impl Effect for BioluminescenceEffect {
 
         // Render fish trails
         for fish in &self.fish {
-            for (tx, ty, intensity) in &fish.trail {
+            let oldest = fish.trail.len().saturating_sub(self.trail_points);
+            for (tx, ty, intensity) in &fish.trail[oldest..] {
                 if *intensity > 0.1 {
                     self.add_glow(&mut frame_buffer, *tx, *ty, 2.0, *intensity * fish.brightness, FISH_GLOW);
                 }
@@ -276,66 +287,58 @@ impl Effect for BioluminescenceEffect {
             self.add_glow(&mut frame_buffer, fish.x, fish.y, 2.5, fish.brightness, FISH_GLOW);
         }
 
-        // Convert to output
-        let mut prev_top_color: (u8, u8, u8) = (255, 255, 255);
-        let mut prev_bot_color: (u8, u8, u8) = (255, 255, 255);
-
-        for y in (0..self.height).step_by(2) {
-            for x in 0..self.width {
-                let top_idx = y * self.width + x;
-                let bot_idx = if y + 1 < self.height {
-                    (y + 1) * self.width + x
-                } else {
-                    top_idx
-                };
-
-                let top_color = (
-                    frame_buffer[top_idx].0.round().clamp(0.0, 255.0) as u8,
-                    frame_buffer[top_idx].1.round().clamp(0.0, 255.0) as u8,
-                    frame_buffer[top_idx].2.round().clamp(0.0, 255.0) as u8,
-                );
-                let bot_color = (
-                    frame_buffer[bot_idx].0.round().clamp(0.0, 255.0) as u8,
-                    frame_buffer[bot_idx].1.round().clamp(0.0, 255.0) as u8,
-                    frame_buffer[bot_idx].2.round().clamp(0.0, 255.0) as u8,
-                );
-
-                if top_color != prev_top_color {
-                    write!(
-                        self.output_buf,
-                        "\x1b[48;2;{};{};{}m",
-                        top_color.0, top_color.1, top_color.2
-                    )?;
-                    prev_top_color = top_color;
-                }
-                if bot_color != prev_bot_color {
-                    write!(
-                        self.output_buf,
-                        "\x1b[38;2;{};{};{}m",
-                        bot_color.0, bot_color.1, bot_color.2
-                    )?;
-                    prev_bot_color = bot_color;
-                }
+        canvas.copy_from_f32(&frame_buffer, self.width);
+    }
 
-                self.output_buf.extend_from_slice("▄".as_bytes());
-            }
-            self.output_buf.extend_from_slice(b"\x1b[0m");
-            prev_top_color = (255, 255, 255);
-            prev_bot_color = (255, 255, 255);
-            if y + 2 < self.height {
-                self.output_buf.extend_from_slice(b"\r\n");
+    fn resize(&mut self, width: usize, height: usize) {
+        let sx = width as f32 / self.width as f32;
+        let sy = height as f32 / self.height as f32;
+        for fish in &mut self.fish {
+            fish.x *= sx;
+            fish.y *= sy;
+            for (tx, ty, _) in &mut fish.trail {
+                *tx *= sx;
+                *ty *= sy;
             }
         }
-
-        stdout.write_all(&self.output_buf)?;
-        stdout.flush()?;
-        Ok(())
+        self.width = width;
+        self.height = height;
     }
 
     fn handle_event(&mut self, _event: &Event) {}
+
+    fn set_quality(&mut self, quality: f32) {
+        self.trail_points = (TRAIL_LENGTH as f32 * quality).round() as usize;
+    }
+
+    fn param(&self, name: &str) -> Option<ParamValue> {
+        Some(match name {
+            "fish" => ParamValue::Int(self.fish.len() as i64),
+            _ => return None,
+        })
+    }
+
+    fn set_param(&mut self, name: &str, value: &ParamValue) -> Result<(), String> {
+        match name {
+            "fish" => self.set_fish_count(value.as_int() as usize),
+            _ => return Err(format!("unknown parameter '{}'", name)),
+        }
+        Ok(())
+    }
 }
 
 impl BioluminescenceEffect {
+    /// Spawn fish at random spots or drop the newest ones to reach `count`.
+    fn set_fish_count(&mut self, count: usize) {
+        self.fish.truncate(count);
+        while self.fish.len() < count {
+            let x = self.rng.f32() * self.width as f32;
+            let y = self.rng.f32() * self.height as f32;
+            let size = 1.0 + self.rng.f32() * 1.5;
+            self.fish.push(Fish::new(&mut self.rng, x, y, size));
+        }
+    }
+
     fn add_glow(&self, buffer: &mut [(f32, f32, f32)], x: f32, y: f32, radius: f32, intensity: f32, color: (u8, u8, u8)) {
         let x_min = (x - radius).max(0.0) as usize;
         let x_max = (x + radius).min(self.width as f32 - 1.0) as usize;
@@ -1,6 +1,6 @@
-use super::Effect;
+use super::{Canvas, Effect, EffectInfo};
 use crossterm::event::Event;
-use std::io::{BufWriter, Stdout, Write};
+use fastrand::Rng;
 
 // Ultra-fast noise implementation - much faster than Perlin
 struct FastNoise {
@@ -11,8 +11,8 @@ impl FastNoise {
     fn new(seed: u32) -> Self {
         let mut perm = [0u8; 512];
         // Initialize permutation table with seed
-        for i in 0..256 {
-            perm[i] = i as u8;
+        for (i, p) in perm.iter_mut().take(256).enumerate() {
+            *p = i as u8;
         }
 
         // Fisher-Yates shuffle with seed
@@ -107,30 +107,31 @@ pub struct CloudEffect {
     noise3: FastNoise,
     noise4: FastNoise,
     layers: Vec<CloudLayer>,
-    output_buf: Vec<u8>,
+    block: usize,       // Pixels per noise sample along each axis; above 1 at reduced quality
+    fine_octave: bool,  // Whether to sample the finest noise octave
 }
 
 impl CloudLayer {
-    fn new(cloud_type: CloudType, altitude: f32) -> Self {
+    fn new(rng: &mut Rng, cloud_type: CloudType, altitude: f32) -> Self {
         let (speed, density, scale) = match cloud_type {
             CloudType::Cumulus => (
-                0.5 + fastrand::f32() * 0.3,
-                0.7 + fastrand::f32() * 0.25, // Higher density - more visible
+                0.5 + rng.f32() * 0.3,
+                0.7 + rng.f32() * 0.25, // Higher density - more visible
                 0.015,
             ),
             CloudType::Cirrus => (
-                1.2 + fastrand::f32() * 0.5,
-                0.15 + fastrand::f32() * 0.1, // Lower density - less overwhelming
+                1.2 + rng.f32() * 0.5,
+                0.15 + rng.f32() * 0.1, // Lower density - less overwhelming
                 0.025,
             ),
             CloudType::Stratus => (
-                0.3 + fastrand::f32() * 0.2,
-                0.5 + fastrand::f32() * 0.2,
+                0.3 + rng.f32() * 0.2,
+                0.5 + rng.f32() * 0.2,
                 0.008,
             ),
             CloudType::Cumulonimbus => (
-                0.4 + fastrand::f32() * 0.2,
-                0.85 + fastrand::f32() * 0.15, // Very high density - dramatic and visible
+                0.4 + rng.f32() * 0.2,
+                0.85 + rng.f32() * 0.15, // Very high density - dramatic and visible
                 0.012,
             ),
         };
@@ -140,59 +141,69 @@ impl CloudLayer {
             altitude,
             speed,
             density,
-            offset_x: fastrand::f32() * 1000.0,
-            offset_y: fastrand::f32() * 1000.0,
+            offset_x: rng.f32() * 1000.0,
+            offset_y: rng.f32() * 1000.0,
             scale,
         }
     }
 }
 
+pub const INFO: EffectInfo = EffectInfo {
+    name: "clouds",
+    aliases: &[],
+    description: "Realistic clouds with multiple types and volumetric shading",
+    controls: &[],
+    params: &[],
+    create: super::construct::<CloudEffect>,
+};
+
 impl Effect for CloudEffect {
-    fn new(width: usize, height: usize) -> Self {
+    fn new(width: usize, height: usize, mut rng: Rng) -> Self {
         let mut layers = Vec::new();
 
         // Rebalanced for more variety - less cirrus, more cumulus and storms
 
         // Just one or two cirrus layers (not overwhelming)
-        if fastrand::f32() > 0.3 {
-            layers.push(CloudLayer::new(CloudType::Cirrus, 0.88));
+        if rng.f32() > 0.3 {
+            layers.push(CloudLayer::new(&mut rng, CloudType::Cirrus, 0.88));
         }
 
         // LOTS of cumulus clouds (main attraction)
-        layers.push(CloudLayer::new(CloudType::Cumulus, 0.72));
-        layers.push(CloudLayer::new(CloudType::Cumulus, 0.67));
-        layers.push(CloudLayer::new(CloudType::Cumulus, 0.62));
-        layers.push(CloudLayer::new(CloudType::Cumulus, 0.57));
-        layers.push(CloudLayer::new(CloudType::Cumulus, 0.53));
-        layers.push(CloudLayer::new(CloudType::Cumulus, 0.49));
-        layers.push(CloudLayer::new(CloudType::Cumulus, 0.45));
+        layers.push(CloudLayer::new(&mut rng, CloudType::Cumulus, 0.72));
+        layers.push(CloudLayer::new(&mut rng, CloudType::Cumulus, 0.67));
+        layers.push(CloudLayer::new(&mut rng, CloudType::Cumulus, 0.62));
+        layers.push(CloudLayer::new(&mut rng, CloudType::Cumulus, 0.57));
+        layers.push(CloudLayer::new(&mut rng, CloudType::Cumulus, 0.53));
+        layers.push(CloudLayer::new(&mut rng, CloudType::Cumulus, 0.49));
+        layers.push(CloudLayer::new(&mut rng, CloudType::Cumulus, 0.45));
 
         // Stratus for variety
-        if fastrand::f32() > 0.5 {
-            layers.push(CloudLayer::new(CloudType::Stratus, 0.40));
+        if rng.f32() > 0.5 {
+            layers.push(CloudLayer::new(&mut rng, CloudType::Stratus, 0.40));
         }
 
         // Storm clouds are common and dramatic - towering vertically
-        if fastrand::f32() > 0.2 {
-            layers.push(CloudLayer::new(CloudType::Cumulonimbus, 0.60));
+        if rng.f32() > 0.2 {
+            layers.push(CloudLayer::new(&mut rng, CloudType::Cumulonimbus, 0.60));
         }
-        if fastrand::f32() > 0.4 {
-            layers.push(CloudLayer::new(CloudType::Cumulonimbus, 0.54));
+        if rng.f32() > 0.4 {
+            layers.push(CloudLayer::new(&mut rng, CloudType::Cumulonimbus, 0.54));
         }
-        if fastrand::f32() > 0.6 {
-            layers.push(CloudLayer::new(CloudType::Cumulonimbus, 0.48));
+        if rng.f32() > 0.6 {
+            layers.push(CloudLayer::new(&mut rng, CloudType::Cumulonimbus, 0.48));
         }
 
         Self {
             width,
             height,
             time: 0.0,
-            noise1: FastNoise::new(fastrand::u32(..)),
-            noise2: FastNoise::new(fastrand::u32(..)),
-            noise3: FastNoise::new(fastrand::u32(..)),
-            noise4: FastNoise::new(fastrand::u32(..)),
+            noise1: FastNoise::new(rng.u32(..)),
+            noise2: FastNoise::new(rng.u32(..)),
+            noise3: FastNoise::new(rng.u32(..)),
+            noise4: FastNoise::new(rng.u32(..)),
             layers,
-            output_buf: Vec::with_capacity(width * height * 25),
+            block: 1,
+            fine_octave: true,
         }
     }
 
@@ -210,10 +221,7 @@ impl Effect for CloudEffect {
         }
     }
 
-    fn render(&mut self, stdout: &mut BufWriter<Stdout>) -> std::io::Result<()> {
-        self.output_buf.clear();
-        self.output_buf.extend_from_slice(b"\x1b[H");
-
+    fn render(&mut self, canvas: &mut Canvas) {
         let bg_color = crate::get_bg_color();
 
         // Sky gradient - light blue at top, lighter at horizon
@@ -251,63 +259,20 @@ impl Effect for CloudEffect {
             self.render_cloud_layer(layer, &mut frame_buffer);
         }
 
-        // Convert to output
-        let mut prev_top_color: (u8, u8, u8) = (255, 255, 255);
-        let mut prev_bot_color: (u8, u8, u8) = (255, 255, 255);
-
-        for y in (0..self.height).step_by(2) {
-            for x in 0..self.width {
-                let top_idx = y * self.width + x;
-                let bot_idx = if y + 1 < self.height {
-                    (y + 1) * self.width + x
-                } else {
-                    top_idx
-                };
-
-                let top_color = (
-                    frame_buffer[top_idx].0.round().clamp(0.0, 255.0) as u8,
-                    frame_buffer[top_idx].1.round().clamp(0.0, 255.0) as u8,
-                    frame_buffer[top_idx].2.round().clamp(0.0, 255.0) as u8,
-                );
-                let bot_color = (
-                    frame_buffer[bot_idx].0.round().clamp(0.0, 255.0) as u8,
-                    frame_buffer[bot_idx].1.round().clamp(0.0, 255.0) as u8,
-                    frame_buffer[bot_idx].2.round().clamp(0.0, 255.0) as u8,
-                );
-
-                if top_color != prev_top_color {
-                    write!(
-                        self.output_buf,
-                        "\x1b[48;2;{};{};{}m",
-                        top_color.0, top_color.1, top_color.2
-                    )?;
-                    prev_top_color = top_color;
-                }
-                if bot_color != prev_bot_color {
-                    write!(
-                        self.output_buf,
-                        "\x1b[38;2;{};{};{}m",
-                        bot_color.0, bot_color.1, bot_color.2
-                    )?;
-                    prev_bot_color = bot_color;
-                }
-
-                self.output_buf.extend_from_slice("▄".as_bytes());
-            }
-            self.output_buf.extend_from_slice(b"\x1b[0m");
-            prev_top_color = (255, 255, 255);
-            prev_bot_color = (255, 255, 255);
-            if y + 2 < self.height {
-                self.output_buf.extend_from_slice(b"\r\n");
-            }
-        }
+        canvas.copy_from_f32(&frame_buffer, self.width);
+    }
 
-        stdout.write_all(&self.output_buf)?;
-        stdout.flush()?;
-        Ok(())
+    fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
     }
 
     fn handle_event(&mut self, _event: &Event) {}
+
+    fn set_quality(&mut self, quality: f32) {
+        self.block = (1.0 / quality).round().max(1.0) as usize;
+        self.fine_octave = quality > 0.75;
+    }
 }
 
 impl CloudEffect {
@@ -315,14 +280,13 @@ impl CloudEffect {
         // Cumulonimbus clouds need different vertical treatment
         let is_vertical = matches!(layer.cloud_type, CloudType::Cumulonimbus);
 
-        for y in 0..self.height {
+        let block = self.block;
+        for y in (0..self.height).step_by(block) {
             let distance = y as f32 / self.height as f32;
             let perspective_scale = 0.3 + distance * 2.0;
             let scale = layer.scale * perspective_scale;
 
-            for x in 0..self.width {
-                let idx = y * self.width + x;
-
+            for x in (0..self.width).step_by(block) {
                 let nx = (x as f64 * scale as f64) + layer.offset_x as f64;
                 // Cumulonimbus uses much more vertical sampling for towering effect
                 let vertical_scale = if is_vertical { 1.8 } else { 0.3 };
@@ -340,7 +304,11 @@ impl CloudEffect {
                 let n1 = self.noise1.get(warped_nx * 0.25, warped_ny * 0.25 + alt_offset);
                 let n2 = self.noise2.get(warped_nx * 0.7, warped_ny * 0.7 + alt_offset);
                 let n3 = self.noise3.get(warped_nx * 2.2, warped_ny * 2.2 + alt_offset);
-                let n4 = self.noise4.get(warped_nx * 6.5, warped_ny * 6.5 + alt_offset);
+                let n4 = if self.fine_octave {
+                    self.noise4.get(warped_nx * 6.5, warped_ny * 6.5 + alt_offset)
+                } else {
+                    0.0
+                };
 
                 // Highly detailed cloud value using 4 octaves
                 let cloud_value = match layer.cloud_type {
@@ -424,17 +392,23 @@ impl CloudEffect {
                     let brightness = base_brightness * vertical_shade.clamp(0.45, 1.05);
                     let atmo_fade = distance * 0.22;
 
-                    let sky_r = buffer[idx].0;
-                    let sky_g = buffer[idx].1;
-                    let sky_b = buffer[idx].2;
-
-                    let cloud_r = brightness * (1.0 - atmo_fade) + sky_r * atmo_fade;
-                    let cloud_g = brightness * (1.0 - atmo_fade) + sky_g * atmo_fade;
-                    let cloud_b = brightness * (1.0 - atmo_fade) + sky_b * atmo_fade;
-
-                    buffer[idx].0 = buffer[idx].0 * (1.0 - density) + cloud_r * density;
-                    buffer[idx].1 = buffer[idx].1 * (1.0 - density) + cloud_g * density;
-                    buffer[idx].2 = buffer[idx].2 * (1.0 - density) + cloud_b * density;
+                    // One sample covers a block of pixels at reduced quality
+                    for py in y..(y + block).min(self.height) {
+                        for px in x..(x + block).min(self.width) {
+                            let idx = py * self.width + px;
+                            let sky_r = buffer[idx].0;
+                            let sky_g = buffer[idx].1;
+                            let sky_b = buffer[idx].2;
+
+                            let cloud_r = brightness * (1.0 - atmo_fade) + sky_r * atmo_fade;
+                            let cloud_g = brightness * (1.0 - atmo_fade) + sky_g * atmo_fade;
+                            let cloud_b = brightness * (1.0 - atmo_fade) + sky_b * atmo_fade;
+
+                            buffer[idx].0 = buffer[idx].0 * (1.0 - density) + cloud_r * density;
+                            buffer[idx].1 = buffer[idx].1 * (1.0 - density) + cloud_g * density;
+                            buffer[idx].2 = buffer[idx].2 * (1.0 - density) + cloud_b * density;
+                        }
+                    }
                 }
             }
         }
@@ -1,8 +1,8 @@
-use super::Effect;
+use super::{Canvas, Effect, EffectInfo, ParamInfo, ParamKind, ParamValue};
+use fastrand::Rng;
 use noise::{NoiseFn, Perlin};
-use std::io::{BufWriter, Stdout, Write};
 
-const PALETTE: [(u8, u8, u8); 37] = [
+pub const PALETTE: [(u8, u8, u8); 37] = [
     (0x07, 0x07, 0x07), (0x1F, 0x07, 0x07), (0x2F, 0x0F, 0x07), (0x47, 0x0F, 0x07),
     (0x57, 0x17, 0x07), (0x67, 0x1F, 0x07), (0x77, 0x1F, 0x07), (0x8F, 0x27, 0x07),
     (0x9F, 0x2F, 0x07), (0xAF, 0x3F, 0x07), (0xBF, 0x47, 0x07), (0xC7, 0x47, 0x07),
@@ -15,6 +15,42 @@ const PALETTE: [(u8, u8, u8); 37] = [
     (0xFF, 0xFF, 0xFF),
 ];
 
+/// Recolors the palette by swapping channels, keeping its brightness ramp.
+#[derive(Clone, Copy)]
+enum Tint {
+    Classic,
+    Blue,
+    Green,
+    Purple,
+}
+
+impl Tint {
+    const NAMES: &'static [&'static str] = &["classic", "blue", "green", "purple"];
+
+    fn from_name(name: &str) -> Option<Tint> {
+        match name {
+            "classic" => Some(Tint::Classic),
+            "blue" => Some(Tint::Blue),
+            "green" => Some(Tint::Green),
+            "purple" => Some(Tint::Purple),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+
+    fn apply(self, (r, g, b): (u8, u8, u8)) -> (u8, u8, u8) {
+        match self {
+            Tint::Classic => (r, g, b),
+            Tint::Blue => (b, g, r),
+            Tint::Green => (g, r, b),
+            Tint::Purple => (r, b, g),
+        }
+    }
+}
+
 struct Spark {
     x: f32,
     y: f32,
@@ -33,15 +69,44 @@ pub struct FireEffect {
     turb_perlin: Perlin,
     time: f32,
     wind: f32,
+    wind_bias: f32, // Steady wind added to the gusts, negative blows left
     height_cache: Vec<f32>,
-    output_buf: Vec<u8>,
     decay_scale: f32,
+    flame_height: f32, // Multiplier on how high flames reach
+    tint: Tint,
+    turbulence_stride: usize, // Turbulence is sampled at one pixel in this many, more apart at reduced quality
+    rng: Rng,
 }
 
+pub const INFO: EffectInfo = EffectInfo {
+    name: "fire",
+    aliases: &[],
+    description: "Fire screensaver (default)",
+    controls: &[],
+    params: &[
+        ParamInfo {
+            name: "wind",
+            description: "Steady wind, negative blows left",
+            kind: ParamKind::Float { min: -3.0, max: 3.0, step: 0.1 },
+        },
+        ParamInfo {
+            name: "height",
+            description: "How high the flames reach",
+            kind: ParamKind::Float { min: 0.5, max: 2.0, step: 0.1 },
+        },
+        ParamInfo {
+            name: "palette",
+            description: "Flame colors",
+            kind: ParamKind::Choice(Tint::NAMES),
+        },
+    ],
+    create: super::construct::<FireEffect>,
+};
+
 impl Effect for FireEffect {
-    fn new(width: usize, height: usize) -> Self {
-        let perlin = Perlin::new(fastrand::u32(0..1000));
-        let turb_perlin = Perlin::new(fastrand::u32(0..1000));
+    fn new(width: usize, height: usize, mut rng: Rng) -> Self {
+        let perlin = Perlin::new(rng.u32(0..1000));
+        let turb_perlin = Perlin::new(rng.u32(0..1000));
 
         // Scale decay based on terminal height (56 rows * 2 = 112 is baseline)
         // Taller terminals = less decay = flames reach higher
@@ -56,9 +121,13 @@ impl Effect for FireEffect {
             turb_perlin,
             time: 0.0,
             wind: 0.0,
+            wind_bias: 0.0,
             height_cache: vec![0.0; width],
-            output_buf: Vec::with_capacity(width * height * 25),
             decay_scale,
+            flame_height: 1.0,
+            tint: Tint::Classic,
+            turbulence_stride: 2,
+            rng,
         }
     }
 
@@ -72,7 +141,8 @@ impl Effect for FireEffect {
         // Update wind
         self.wind = (self.time * 0.7).sin() * 1.5
             + (self.time * 1.3).sin() * 0.8
-            + (self.time * 2.1).sin() * 0.4;
+            + (self.time * 2.1).sin() * 0.4
+            + self.wind_bias;
 
         // Cache height noise per column (only changes with time)
         for x in 0..self.width {
@@ -84,24 +154,24 @@ impl Effect for FireEffect {
         let base_row = self.height - 1;
         for x in 0..self.width {
             let noise_val = self.perlin.get([x as f64 * 0.05, self.time as f64 * 0.8]) as f32;
-            let fuel = 28.0 + noise_val * 6.0 + fastrand::f32() * 3.0;
+            let fuel = 28.0 + noise_val * 6.0 + self.rng.f32() * 3.0;
             self.buffer[base_row * self.width + x] = fuel;
         }
 
         self.spread_fire();
 
         // Spawn sparks
-        if fastrand::f32() < 0.2 {
-            let x = fastrand::usize(0..self.width) as f32;
+        if self.rng.f32() < 0.2 {
+            let x = self.rng.usize(0..self.width) as f32;
             let intensity = self.buffer[(self.height - 2) * self.width + x as usize];
             if intensity > 25.0 {
                 self.sparks.push(Spark {
                     x,
                     y: (self.height - 2) as f32,
-                    vx: fastrand::f32() - 0.5 + self.wind * 0.2,
-                    vy: -(fastrand::f32() * 1.5 + 1.0),
+                    vx: self.rng.f32() - 0.5 + self.wind * 0.2,
+                    vy: -(self.rng.f32() * 1.5 + 1.0),
                     life: 1.0,
-                    brightness: fastrand::u8(18..24),
+                    brightness: self.rng.u8(18..24),
                 });
             }
         }
@@ -113,70 +183,72 @@ impl Effect for FireEffect {
             spark.x += spark.vx + wind * 0.1;
             spark.y += spark.vy;
             spark.vy += 0.05;
-            spark.vx += fastrand::f32() * 0.3 - 0.15;
+            spark.vx += self.rng.f32() * 0.3 - 0.15;
             spark.life -= dt * 0.8;
 
             spark.life > 0.0 && spark.x >= 0.0 && spark.x < w && spark.y >= 0.0
         });
     }
 
-    fn render(&mut self, stdout: &mut BufWriter<Stdout>) -> std::io::Result<()> {
-        self.output_buf.clear();
-        self.output_buf.extend_from_slice(b"\x1b[H"); // Move to home
-
+    fn render(&mut self, canvas: &mut Canvas) {
         let bg_color = crate::get_bg_color();
-        let mut prev_top: (u8, u8, u8) = (255, 255, 255);
-        let mut prev_bot: (u8, u8, u8) = (255, 255, 255);
 
-        for y in (0..self.height).step_by(2) {
+        for y in 0..self.height {
             for x in 0..self.width {
-                let mut top_intensity = self.buffer[y * self.width + x];
-                let mut bot_intensity = if y + 1 < self.height {
-                    self.buffer[(y + 1) * self.width + x]
-                } else {
-                    36.0
-                };
-
-                // Check sparks
-                for spark in &self.sparks {
-                    let sy = spark.y as usize;
-                    let sx = spark.x as usize;
-                    if sx == x {
-                        if sy == y {
-                            top_intensity = top_intensity.max(spark.brightness as f32 * spark.life);
-                        } else if sy == y + 1 {
-                            bot_intensity = bot_intensity.max(spark.brightness as f32 * spark.life);
-                        }
-                    }
-                }
-
-                let top_idx = (top_intensity as usize).min(36);
-                let bot_idx = (bot_intensity as usize).min(36);
-
-                let top = Self::blend_with_bg(PALETTE[top_idx], bg_color, top_idx);
-                let bot = Self::blend_with_bg(PALETTE[bot_idx], bg_color, bot_idx);
-
-                // Only emit color codes if changed
-                if top != prev_top {
-                    write!(self.output_buf, "\x1b[48;2;{};{};{}m", top.0, top.1, top.2)?;
-                    prev_top = top;
-                }
-                if bot != prev_bot {
-                    write!(self.output_buf, "\x1b[38;2;{};{};{}m", bot.0, bot.1, bot.2)?;
-                    prev_bot = bot;
-                }
-                self.output_buf.extend_from_slice("▄".as_bytes());
+                let idx = (self.buffer[y * self.width + x] as usize).min(36);
+                canvas.set(x, y, Self::blend_with_bg(self.tint.apply(PALETTE[idx]), bg_color, idx));
             }
-            self.output_buf.extend_from_slice(b"\x1b[0m");
-            prev_top = (255, 255, 255);
-            prev_bot = (255, 255, 255);
-            if y + 2 < self.height {
-                self.output_buf.extend_from_slice(b"\r\n");
+        }
+
+        // Sparks glow on top of the flames where they are brighter
+        for spark in &self.sparks {
+            let x = spark.x as usize;
+            let y = spark.y as usize;
+            if x >= self.width || y >= self.height {
+                continue;
             }
+            let intensity = self.buffer[y * self.width + x].max(spark.brightness as f32 * spark.life);
+            let idx = (intensity as usize).min(36);
+            canvas.set(x, y, Self::blend_with_bg(self.tint.apply(PALETTE[idx]), bg_color, idx));
+        }
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.buffer = super::resample(&self.buffer, self.width, self.height, width, height);
+        let sx = width as f32 / self.width as f32;
+        let sy = height as f32 / self.height as f32;
+        for spark in &mut self.sparks {
+            spark.x *= sx;
+            spark.y *= sy;
         }
+        self.width = width;
+        self.height = height;
+        self.height_cache = vec![0.0; width];
+        self.decay_scale = 112.0 / height as f32;
+    }
+
+    fn set_quality(&mut self, quality: f32) {
+        self.turbulence_stride = (2.0 / quality).round().max(2.0) as usize;
+    }
 
-        stdout.write_all(&self.output_buf)?;
-        stdout.flush()?;
+    fn param(&self, name: &str) -> Option<ParamValue> {
+        Some(match name {
+            "wind" => ParamValue::Float(self.wind_bias),
+            "height" => ParamValue::Float(self.flame_height),
+            "palette" => ParamValue::Text(self.tint.name().to_string()),
+            _ => return None,
+        })
+    }
+
+    fn set_param(&mut self, name: &str, value: &ParamValue) -> Result<(), String> {
+        match name {
+            "wind" => self.wind_bias = value.as_f32(),
+            "height" => self.flame_height = value.as_f32(),
+            "palette" => {
+                self.tint = Tint::from_name(value.as_str()).ok_or_else(|| format!("unknown palette '{}'", value))?
+            }
+            _ => return Err(format!("unknown parameter '{}'", name)),
+        }
         Ok(())
     }
 }
@@ -208,7 +280,7 @@ impl FireEffect {
                 let intensity = self.buffer[src];
 
                 // Simplified turbulence - sample less often
-                let turb = if (x + y) % 2 == 0 {
+                let turb = if (x + y) % self.turbulence_stride == 0 {
                     self.turb_perlin.get([
                         x as f64 * 0.03,
                         y as f64 * 0.03,
@@ -218,14 +290,14 @@ impl FireEffect {
                     0.0
                 };
 
-                let drift = wind * 0.4 + turb * 2.0 + fastrand::f32() * 2.0 - 1.0;
+                let drift = wind * 0.4 + turb * 2.0 + self.rng.f32() * 2.0 - 1.0;
                 let dst_x = (x as f32 + drift).clamp(0.0, (width - 1) as f32) as usize;
                 let dst = (y - 1) * width + dst_x;
 
                 let height_factor = self.height_cache[x];
                 let heat_decay = 1.0 + intensity * 0.03;
-                let base_decay = fastrand::f32() * 1.2 + 0.3;
-                let decay = base_decay * height_factor * heat_decay * self.decay_scale;
+                let base_decay = self.rng.f32() * 1.2 + 0.3;
+                let decay = base_decay * height_factor * heat_decay * self.decay_scale / self.flame_height;
 
                 self.buffer[dst] = (self.buffer[src] - decay).max(0.0);
             }
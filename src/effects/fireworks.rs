@@ -1,5 +1,5 @@
-use super::Effect;
-use std::io::{BufWriter, Stdout, Write};
+use super::{Canvas, Effect, EffectInfo, ParamInfo, ParamKind, ParamValue};
+use fastrand::Rng;
 
 // Realistic firework colors based on chemical compounds
 const COLORS: [(u8, u8, u8); 10] = [
@@ -65,8 +65,8 @@ struct Rocket {
 }
 
 impl ExplosionType {
-    fn random() -> Self {
-        match fastrand::usize(0..13) {
+    fn random(rng: &mut Rng) -> Self {
+        match rng.usize(0..13) {
             0 => ExplosionType::Sphere,
             1 => ExplosionType::Ring,
             2 => ExplosionType::Willow,
@@ -91,11 +91,28 @@ pub struct FireworksEffect {
     particles: Vec<Particle>,
     time: f32,
     next_launch: f32,
-    output_buf: Vec<u8>,
+    launch_rate: f32, // Multiplier on how often rockets go up
+    quality: f32,
+    rng: Rng,
 }
 
+pub const INFO: EffectInfo = EffectInfo {
+    name: "fireworks",
+    aliases: &[],
+    description: "Colorful fireworks display",
+    controls: &[],
+    params: &[
+        ParamInfo {
+            name: "launch_rate",
+            description: "How often rockets go up",
+            kind: ParamKind::Float { min: 0.1, max: 10.0, step: 0.1 },
+        },
+    ],
+    create: super::construct::<FireworksEffect>,
+};
+
 impl Effect for FireworksEffect {
-    fn new(width: usize, height: usize) -> Self {
+    fn new(width: usize, height: usize, rng: Rng) -> Self {
         Self {
             width,
             height,
@@ -103,7 +120,9 @@ impl Effect for FireworksEffect {
             particles: Vec::new(),
             time: 0.0,
             next_launch: 0.5,
-            output_buf: Vec::with_capacity(width * height * 25),
+            launch_rate: 1.0,
+            quality: 1.0,
+            rng,
         }
     }
 
@@ -117,22 +136,22 @@ impl Effect for FireworksEffect {
 
         // Launch new rockets
         if self.time >= self.next_launch {
-            let x = fastrand::usize(self.width / 4..self.width * 3 / 4) as f32;
-            let target_y = fastrand::usize(self.height / 6..self.height * 2 / 5) as f32;
-            let color = COLORS[fastrand::usize(0..COLORS.len())];
+            let x = self.rng.usize(self.width / 4..self.width * 3 / 4) as f32;
+            let target_y = self.rng.usize(self.height / 6..self.height * 2 / 5) as f32;
+            let color = COLORS[self.rng.usize(0..COLORS.len())];
 
             // Random angle from -5 to 5 degrees
-            let angle_degrees = -5.0 + fastrand::f32() * 10.0;
+            let angle_degrees = -5.0 + self.rng.f32() * 10.0;
             let angle_radians = angle_degrees * std::f32::consts::PI / 180.0;
 
             // Randomize launch velocity - ensure minimum to reach decent height
-            let speed = 60.0 + fastrand::f32() * 70.0; // 60 to 130
+            let speed = 60.0 + self.rng.f32() * 70.0; // 60 to 130
             let vx = speed * angle_radians.sin();
             let vy = -speed * angle_radians.cos(); // Negative because up is negative y
 
-            let explosion_type = ExplosionType::random();
+            let explosion_type = ExplosionType::random(&mut self.rng);
             let burst_count = if matches!(explosion_type, ExplosionType::MultiBurst) {
-                2 + fastrand::usize(0..2) // 2 or 3 bursts
+                2 + self.rng.usize(0..2) // 2 or 3 bursts
             } else {
                 1
             };
@@ -148,7 +167,7 @@ impl Effect for FireworksEffect {
                 burst_count,
             });
 
-            self.next_launch = self.time + 0.3 + fastrand::f32() * 0.8;
+            self.next_launch = self.time + (0.3 + self.rng.f32() * 0.8) / self.launch_rate;
         }
 
         // Update rockets and collect explosions
@@ -200,9 +219,9 @@ impl Effect for FireworksEffect {
                 particle.spark_timer -= dt;
                 if particle.spark_timer <= 0.0 {
                     // Emit 2-4 golden sparks
-                    let spark_count = 2 + fastrand::usize(0..3);
+                    let spark_count = 2 + self.rng.usize(0..3);
                     sparks_to_emit.push((particle.x, particle.y, particle.vx, particle.vy, spark_count));
-                    particle.spark_timer = 0.03 + fastrand::f32() * 0.03; // Emit every 0.03-0.06s
+                    particle.spark_timer = 0.03 + self.rng.f32() * 0.03; // Emit every 0.03-0.06s
                 }
             }
 
@@ -221,10 +240,10 @@ impl Effect for FireworksEffect {
 
         // Create crossette secondary explosions (as golden sparks)
         for (x, y, vx, vy, _color) in crossette_explosions {
-            let count = 15 + fastrand::usize(0..10);
+            let count = self.burst(15, 10);
             for _ in 0..count {
-                let angle = fastrand::f32() * std::f32::consts::PI * 2.0;
-                let speed = fastrand::f32() * 25.0;
+                let angle = self.rng.f32() * std::f32::consts::PI * 2.0;
+                let speed = self.rng.f32() * 25.0;
 
                 self.particles.push(Particle {
                     x,
@@ -232,7 +251,7 @@ impl Effect for FireworksEffect {
                     vx: angle.cos() * speed + vx * 0.3, // Inherit some velocity
                     vy: angle.sin() * speed + vy * 0.3,
                     life: 1.0,
-                    max_life: 0.4 + fastrand::f32() * 0.2, // Short-lived sparks (0.4-0.6)
+                    max_life: 0.4 + self.rng.f32() * 0.2, // Short-lived sparks (0.4-0.6)
                     color: SPARK_COLOR, // Golden sparks
                     color_end: None,
                     strobe_phase: 0.0,
@@ -240,16 +259,16 @@ impl Effect for FireworksEffect {
                     trail_length: 0,
                     emits_sparks: false,
                     spark_timer: 0.0,
-                    opacity: 0.6 + fastrand::f32() * 0.4, // Random opacity 0.6-1.0
+                    opacity: 0.6 + self.rng.f32() * 0.4, // Random opacity 0.6-1.0
                 });
             }
         }
 
         // Create golden sparks from willowtail particles
         for (x, y, vx, vy, count) in sparks_to_emit {
-            for _ in 0..count {
-                let angle = fastrand::f32() * std::f32::consts::PI * 2.0;
-                let speed = 3.0 + fastrand::f32() * 8.0;
+            for _ in 0..self.scaled(count) {
+                let angle = self.rng.f32() * std::f32::consts::PI * 2.0;
+                let speed = 3.0 + self.rng.f32() * 8.0;
 
                 self.particles.push(Particle {
                     x,
@@ -257,7 +276,7 @@ impl Effect for FireworksEffect {
                     vx: angle.cos() * speed + vx * 0.5, // Inherit half the velocity
                     vy: angle.sin() * speed + vy * 0.5,
                     life: 1.0,
-                    max_life: 0.3 + fastrand::f32() * 0.3, // Short-lived sparks
+                    max_life: 0.3 + self.rng.f32() * 0.3, // Short-lived sparks
                     color: SPARK_COLOR, // Bright golden color
                     color_end: None,
                     strobe_phase: 0.0,
@@ -265,16 +284,13 @@ impl Effect for FireworksEffect {
                     trail_length: 1, // Small trail
                     emits_sparks: false,
                     spark_timer: 0.0,
-                    opacity: 0.6 + fastrand::f32() * 0.4, // Random opacity 0.6-1.0
+                    opacity: 0.6 + self.rng.f32() * 0.4, // Random opacity 0.6-1.0
                 });
             }
         }
     }
 
-    fn render(&mut self, stdout: &mut BufWriter<Stdout>) -> std::io::Result<()> {
-        self.output_buf.clear();
-        self.output_buf.extend_from_slice(b"\x1b[H");
-
+    fn render(&mut self, canvas: &mut Canvas) {
         let bg_color = crate::get_bg_color();
         let mut glow_buffer = vec![(0.0f32, bg_color); self.width * self.height];
 
@@ -476,79 +492,74 @@ impl Effect for FireworksEffect {
             }
         }
 
-        let mut prev_top_color: (u8, u8, u8) = (255, 255, 255);
-        let mut prev_bot_color: (u8, u8, u8) = (255, 255, 255);
-
-        // Render using half-blocks
-        for y in (0..self.height).step_by(2) {
+        for y in 0..self.height {
             for x in 0..self.width {
-                let top_idx = y * self.width + x;
-                let bot_idx = if y + 1 < self.height {
-                    (y + 1) * self.width + x
-                } else {
-                    top_idx
-                };
-
-                let (top_intensity, top_base_color) = glow_buffer[top_idx];
-                let (bot_intensity, bot_base_color) = glow_buffer[bot_idx];
+                let (intensity, base_color) = glow_buffer[y * self.width + x];
 
                 // Blend particle color with background based on intensity
-                let top_color = if top_intensity > 0.05 {
-                    let blend = (top_intensity / 3.0).min(1.0); // Normalize intensity
+                let color = if intensity > 0.05 {
+                    let blend = (intensity / 3.0).min(1.0); // Normalize intensity
                     (
-                        (bg_color.0 as f32 * (1.0 - blend) + top_base_color.0 as f32 * blend) as u8,
-                        (bg_color.1 as f32 * (1.0 - blend) + top_base_color.1 as f32 * blend) as u8,
-                        (bg_color.2 as f32 * (1.0 - blend) + top_base_color.2 as f32 * blend) as u8,
+                        (bg_color.0 as f32 * (1.0 - blend) + base_color.0 as f32 * blend) as u8,
+                        (bg_color.1 as f32 * (1.0 - blend) + base_color.1 as f32 * blend) as u8,
+                        (bg_color.2 as f32 * (1.0 - blend) + base_color.2 as f32 * blend) as u8,
                     )
                 } else {
                     bg_color
                 };
+                canvas.set(x, y, color);
+            }
+        }
+    }
 
-                let bot_color = if bot_intensity > 0.05 {
-                    let blend = (bot_intensity / 3.0).min(1.0); // Normalize intensity
-                    (
-                        (bg_color.0 as f32 * (1.0 - blend) + bot_base_color.0 as f32 * blend) as u8,
-                        (bg_color.1 as f32 * (1.0 - blend) + bot_base_color.1 as f32 * blend) as u8,
-                        (bg_color.2 as f32 * (1.0 - blend) + bot_base_color.2 as f32 * blend) as u8,
-                    )
-                } else {
-                    bg_color
-                };
+    fn resize(&mut self, width: usize, height: usize) {
+        let sx = width as f32 / self.width as f32;
+        let sy = height as f32 / self.height as f32;
+        for rocket in &mut self.rockets {
+            rocket.x *= sx;
+            rocket.y *= sy;
+            rocket.target_y *= sy;
+        }
+        for particle in &mut self.particles {
+            particle.x *= sx;
+            particle.y *= sy;
+        }
+        self.width = width;
+        self.height = height;
+    }
 
-                if top_color != prev_top_color {
-                    write!(
-                        self.output_buf,
-                        "\x1b[48;2;{};{};{}m",
-                        top_color.0, top_color.1, top_color.2
-                    )?;
-                    prev_top_color = top_color;
-                }
-                if bot_color != prev_bot_color {
-                    write!(
-                        self.output_buf,
-                        "\x1b[38;2;{};{};{}m",
-                        bot_color.0, bot_color.1, bot_color.2
-                    )?;
-                    prev_bot_color = bot_color;
-                }
+    fn set_quality(&mut self, quality: f32) {
+        self.quality = quality;
+    }
 
-                self.output_buf.extend_from_slice("▄".as_bytes());
-            }
-            self.output_buf.extend_from_slice(b"\x1b[0m");
-            prev_top_color = (255, 255, 255);
-            prev_bot_color = (255, 255, 255);
-            if y + 2 < self.height {
-                self.output_buf.extend_from_slice(b"\r\n");
-            }
-        }
+    fn param(&self, name: &str) -> Option<ParamValue> {
+        Some(match name {
+            "launch_rate" => ParamValue::Float(self.launch_rate),
+            _ => return None,
+        })
+    }
 
-        stdout.write_all(&self.output_buf)?;
-        stdout.flush()?;
+    fn set_param(&mut self, name: &str, value: &ParamValue) -> Result<(), String> {
+        match name {
+            "launch_rate" => self.launch_rate = value.as_f32(),
+            _ => return Err(format!("unknown parameter '{}'", name)),
+        }
         Ok(())
     }
 }
 
 impl FireworksEffect {
+    /// How many particles a burst gets at the current quality: fewer, but never none.
+    fn scaled(&self, count: usize) -> usize {
+        ((count as f32 * self.quality).round() as usize).max(1)
+    }
+
+    /// A random burst size, `base` plus fewer than `spread` more, scaled like `scaled`.
+    fn burst(&mut self, base: usize, spread: usize) -> usize {
+        let count = base + self.rng.usize(0..spread);
+        self.scaled(count)
+    }
+
     fn create_explosion(&mut self, rocket: &Rocket) {
         match rocket.explosion_type {
             ExplosionType::Sphere => self.create_sphere_explosion(rocket),
@@ -568,10 +579,10 @@ impl FireworksEffect {
     }
 
     fn create_sphere_explosion(&mut self, rocket: &Rocket) {
-        let particle_count = 80 + fastrand::usize(0..40);
+        let particle_count = self.burst(80, 40);
         for _ in 0..particle_count {
-            let angle = fastrand::f32() * std::f32::consts::PI * 2.0;
-            let speed = fastrand::f32() * 55.0;
+            let angle = self.rng.f32() * std::f32::consts::PI * 2.0;
+            let speed = self.rng.f32() * 55.0;
 
             self.particles.push(Particle {
                 x: rocket.x,
@@ -579,7 +590,7 @@ impl FireworksEffect {
                 vx: angle.cos() * speed + rocket.vx,
                 vy: angle.sin() * speed + rocket.vy,
                 life: 1.0,
-                max_life: 1.0 + fastrand::f32() * 0.5,
+                max_life: 1.0 + self.rng.f32() * 0.5,
                 color: rocket.color,
                 color_end: None,
                 strobe_phase: 0.0,
@@ -593,19 +604,19 @@ impl FireworksEffect {
     }
 
     fn create_ring_explosion(&mut self, rocket: &Rocket) {
-        let particle_count = 60 + fastrand::usize(0..30);
+        let particle_count = self.burst(60, 30);
         for i in 0..particle_count {
             let angle = (i as f32 / particle_count as f32) * std::f32::consts::PI * 2.0;
-            let speed = 35.0 + fastrand::f32() * 15.0;
+            let speed = 35.0 + self.rng.f32() * 15.0;
 
             // Ring is horizontal, so vx and vy form the ring, vz would be minimal
             self.particles.push(Particle {
                 x: rocket.x,
                 y: rocket.y,
                 vx: angle.cos() * speed + rocket.vx,
-                vy: fastrand::f32() * 5.0 - 2.5 + rocket.vy, // Minimal vertical spread
+                vy: self.rng.f32() * 5.0 - 2.5 + rocket.vy, // Minimal vertical spread
                 life: 1.0,
-                max_life: 1.2 + fastrand::f32() * 0.3,
+                max_life: 1.2 + self.rng.f32() * 0.3,
                 color: rocket.color,
                 color_end: None,
                 strobe_phase: 0.0,
@@ -619,10 +630,10 @@ impl FireworksEffect {
     }
 
     fn create_willow_explosion(&mut self, rocket: &Rocket) {
-        let particle_count = 100 + fastrand::usize(0..50);
+        let particle_count = self.burst(100, 50);
         for _ in 0..particle_count {
-            let angle = fastrand::f32() * std::f32::consts::PI * 2.0;
-            let speed = fastrand::f32() * 40.0;
+            let angle = self.rng.f32() * std::f32::consts::PI * 2.0;
+            let speed = self.rng.f32() * 40.0;
 
             // Bias towards downward motion
             let vx = angle.cos() * speed;
@@ -634,7 +645,7 @@ impl FireworksEffect {
                 vx: vx + rocket.vx,
                 vy: vy + rocket.vy,
                 life: 1.0,
-                max_life: 1.5 + fastrand::f32() * 0.8, // Longer life for willow
+                max_life: 1.5 + self.rng.f32() * 0.8, // Longer life for willow
                 color: rocket.color,
                 color_end: None,
                 strobe_phase: 0.0,
@@ -648,10 +659,10 @@ impl FireworksEffect {
     }
 
     fn create_crossette_explosion(&mut self, rocket: &Rocket) {
-        let particle_count = 40 + fastrand::usize(0..20);
+        let particle_count = self.burst(40, 20);
         for _ in 0..particle_count {
-            let angle = fastrand::f32() * std::f32::consts::PI * 2.0;
-            let speed = 20.0 + fastrand::f32() * 30.0;
+            let angle = self.rng.f32() * std::f32::consts::PI * 2.0;
+            let speed = 20.0 + self.rng.f32() * 30.0;
 
             self.particles.push(Particle {
                 x: rocket.x,
@@ -659,11 +670,11 @@ impl FireworksEffect {
                 vx: angle.cos() * speed + rocket.vx,
                 vy: angle.sin() * speed + rocket.vy,
                 life: 1.0,
-                max_life: 1.0 + fastrand::f32() * 0.5,
+                max_life: 1.0 + self.rng.f32() * 0.5,
                 color: rocket.color,
                 color_end: None,
                 strobe_phase: 0.0,
-                crossette_time: Some(0.2 + fastrand::f32() * 0.3), // Explode again after 0.2-0.5s
+                crossette_time: Some(0.2 + self.rng.f32() * 0.3), // Explode again after 0.2-0.5s
                 trail_length: 0,
                 emits_sparks: false,
                 spark_timer: 0.0,
@@ -672,11 +683,12 @@ impl FireworksEffect {
         }
     }
 
+    #[allow(clippy::approx_constant)] // The phase has always been drawn from 0..6.28, not 0..TAU
     fn create_strobe_explosion(&mut self, rocket: &Rocket) {
-        let particle_count = 80 + fastrand::usize(0..40);
+        let particle_count = self.burst(80, 40);
         for _ in 0..particle_count {
-            let angle = fastrand::f32() * std::f32::consts::PI * 2.0;
-            let speed = fastrand::f32() * 50.0;
+            let angle = self.rng.f32() * std::f32::consts::PI * 2.0;
+            let speed = self.rng.f32() * 50.0;
 
             self.particles.push(Particle {
                 x: rocket.x,
@@ -684,10 +696,10 @@ impl FireworksEffect {
                 vx: angle.cos() * speed + rocket.vx,
                 vy: angle.sin() * speed + rocket.vy,
                 life: 1.0,
-                max_life: 1.0 + fastrand::f32() * 0.5,
+                max_life: 1.0 + self.rng.f32() * 0.5,
                 color: rocket.color,
                 color_end: None,
-                strobe_phase: fastrand::f32() * 6.28, // Random starting phase
+                strobe_phase: self.rng.f32() * 6.28, // Random starting phase
                 crossette_time: None,
                 trail_length: 0,
                 emits_sparks: false,
@@ -699,10 +711,10 @@ impl FireworksEffect {
 
     fn create_multiburst_explosion(&mut self, rocket: &Rocket) {
         // Create smaller first burst
-        let particle_count = 30 + fastrand::usize(0..20);
+        let particle_count = self.burst(30, 20);
         for _ in 0..particle_count {
-            let angle = fastrand::f32() * std::f32::consts::PI * 2.0;
-            let speed = 15.0 + fastrand::f32() * 20.0;
+            let angle = self.rng.f32() * std::f32::consts::PI * 2.0;
+            let speed = 15.0 + self.rng.f32() * 20.0;
 
             self.particles.push(Particle {
                 x: rocket.x,
@@ -726,10 +738,10 @@ impl FireworksEffect {
         for burst in 1..rocket.burst_count {
             let delay = burst as f32 * 0.15;
             // Create particles that will appear later (simulated by short max_life that increases)
-            let particle_count = 40 + fastrand::usize(0..30);
+            let particle_count = self.burst(40, 30);
             for _ in 0..particle_count {
-                let angle = fastrand::f32() * std::f32::consts::PI * 2.0;
-                let speed = fastrand::f32() * 45.0;
+                let angle = self.rng.f32() * std::f32::consts::PI * 2.0;
+                let speed = self.rng.f32() * 45.0;
 
                 self.particles.push(Particle {
                     x: rocket.x,
@@ -752,16 +764,16 @@ impl FireworksEffect {
     }
 
     fn create_colorshift_explosion(&mut self, rocket: &Rocket) {
-        let particle_count = 80 + fastrand::usize(0..40);
+        let particle_count = self.burst(80, 40);
         // Pick a second color different from the rocket color
         let end_color = loop {
-            let c = COLORS[fastrand::usize(0..COLORS.len())];
+            let c = COLORS[self.rng.usize(0..COLORS.len())];
             if c != rocket.color { break c; }
         };
 
         for _ in 0..particle_count {
-            let angle = fastrand::f32() * std::f32::consts::PI * 2.0;
-            let speed = fastrand::f32() * 50.0;
+            let angle = self.rng.f32() * std::f32::consts::PI * 2.0;
+            let speed = self.rng.f32() * 50.0;
 
             self.particles.push(Particle {
                 x: rocket.x,
@@ -769,7 +781,7 @@ impl FireworksEffect {
                 vx: angle.cos() * speed + rocket.vx,
                 vy: angle.sin() * speed + rocket.vy,
                 life: 1.0,
-                max_life: 1.2 + fastrand::f32() * 0.5,
+                max_life: 1.2 + self.rng.f32() * 0.5,
                 color: rocket.color,
                 color_end: Some(end_color),
                 strobe_phase: 0.0,
@@ -783,7 +795,7 @@ impl FireworksEffect {
     }
 
     fn create_spiral_explosion(&mut self, rocket: &Rocket) {
-        let particle_count = 60;
+        let particle_count = self.scaled(60);
         let spiral_turns = 3.0;
 
         for i in 0..particle_count {
@@ -797,7 +809,7 @@ impl FireworksEffect {
                 vx: angle.cos() * speed + rocket.vx,
                 vy: angle.sin() * speed + rocket.vy,
                 life: 1.0,
-                max_life: 1.0 + fastrand::f32() * 0.5,
+                max_life: 1.0 + self.rng.f32() * 0.5,
                 color: rocket.color,
                 color_end: None,
                 strobe_phase: 0.0,
@@ -811,7 +823,7 @@ impl FireworksEffect {
     }
 
     fn create_heart_explosion(&mut self, rocket: &Rocket) {
-        let particle_count = 80;
+        let particle_count = self.scaled(80);
 
         for i in 0..particle_count {
             let t = (i as f32 / particle_count as f32) * std::f32::consts::PI * 2.0;
@@ -827,7 +839,7 @@ impl FireworksEffect {
                 vx: x_shape * scale + rocket.vx,
                 vy: y_shape * scale + rocket.vy,
                 life: 1.0,
-                max_life: 1.3 + fastrand::f32() * 0.4,
+                max_life: 1.3 + self.rng.f32() * 0.4,
                 color: rocket.color,
                 color_end: None,
                 strobe_phase: 0.0,
@@ -842,7 +854,7 @@ impl FireworksEffect {
 
     fn create_star_explosion(&mut self, rocket: &Rocket) {
         let points = 5;
-        let particles_per_point = 15;
+        let particles_per_point = self.scaled(15);
 
         for p in 0..points {
             let base_angle = (p as f32 / points as f32) * std::f32::consts::PI * 2.0;
@@ -859,7 +871,7 @@ impl FireworksEffect {
                     vx: angle.cos() * radius + rocket.vx,
                     vy: angle.sin() * radius + rocket.vy,
                     life: 1.0,
-                    max_life: 1.2 + fastrand::f32() * 0.3,
+                    max_life: 1.2 + self.rng.f32() * 0.3,
                     color: rocket.color,
                     color_end: None,
                     strobe_phase: 0.0,
@@ -874,11 +886,11 @@ impl FireworksEffect {
     }
 
     fn create_chrysanthemum_explosion(&mut self, rocket: &Rocket) {
-        let particle_count = 120 + fastrand::usize(0..60);
+        let particle_count = self.burst(120, 60);
 
         for _ in 0..particle_count {
-            let angle = fastrand::f32() * std::f32::consts::PI * 2.0;
-            let speed = fastrand::f32() * 45.0;
+            let angle = self.rng.f32() * std::f32::consts::PI * 2.0;
+            let speed = self.rng.f32() * 45.0;
 
             self.particles.push(Particle {
                 x: rocket.x,
@@ -886,7 +898,7 @@ impl FireworksEffect {
                 vx: angle.cos() * speed + rocket.vx,
                 vy: angle.sin() * speed + rocket.vy,
                 life: 1.0,
-                max_life: 1.8 + fastrand::f32() * 0.7, // Very long life
+                max_life: 1.8 + self.rng.f32() * 0.7, // Very long life
                 color: rocket.color,
                 color_end: None,
                 strobe_phase: 0.0,
@@ -901,10 +913,10 @@ impl FireworksEffect {
 
     fn create_double_explosion(&mut self, rocket: &Rocket) {
         // Inner fast burst
-        let inner_count = 40 + fastrand::usize(0..20);
+        let inner_count = self.burst(40, 20);
         for _ in 0..inner_count {
-            let angle = fastrand::f32() * std::f32::consts::PI * 2.0;
-            let speed = 40.0 + fastrand::f32() * 25.0;
+            let angle = self.rng.f32() * std::f32::consts::PI * 2.0;
+            let speed = 40.0 + self.rng.f32() * 25.0;
 
             self.particles.push(Particle {
                 x: rocket.x,
@@ -912,7 +924,7 @@ impl FireworksEffect {
                 vx: angle.cos() * speed + rocket.vx,
                 vy: angle.sin() * speed + rocket.vy,
                 life: 1.0,
-                max_life: 0.8 + fastrand::f32() * 0.3,
+                max_life: 0.8 + self.rng.f32() * 0.3,
                 color: rocket.color,
                 color_end: None,
                 strobe_phase: 0.0,
@@ -925,10 +937,10 @@ impl FireworksEffect {
         }
 
         // Outer slow burst
-        let outer_count = 60 + fastrand::usize(0..30);
+        let outer_count = self.burst(60, 30);
         for _ in 0..outer_count {
-            let angle = fastrand::f32() * std::f32::consts::PI * 2.0;
-            let speed = 10.0 + fastrand::f32() * 20.0;
+            let angle = self.rng.f32() * std::f32::consts::PI * 2.0;
+            let speed = 10.0 + self.rng.f32() * 20.0;
 
             self.particles.push(Particle {
                 x: rocket.x,
@@ -936,7 +948,7 @@ impl FireworksEffect {
                 vx: angle.cos() * speed + rocket.vx,
                 vy: angle.sin() * speed + rocket.vy,
                 life: 1.0,
-                max_life: 1.3 + fastrand::f32() * 0.5,
+                max_life: 1.3 + self.rng.f32() * 0.5,
                 color: rocket.color,
                 color_end: None,
                 strobe_phase: 0.0,
@@ -950,11 +962,11 @@ impl FireworksEffect {
     }
 
     fn create_willowtail_explosion(&mut self, rocket: &Rocket) {
-        let particle_count = 40 + fastrand::usize(0..20);
+        let particle_count = self.burst(40, 20);
 
         for _ in 0..particle_count {
-            let angle = fastrand::f32() * std::f32::consts::PI * 2.0;
-            let speed = 10.0 + fastrand::f32() * 25.0;
+            let angle = self.rng.f32() * std::f32::consts::PI * 2.0;
+            let speed = 10.0 + self.rng.f32() * 25.0;
 
             // Spread in a circle
             let vx = angle.cos() * speed;
@@ -966,7 +978,7 @@ impl FireworksEffect {
                 vx: vx + rocket.vx,
                 vy: vy + rocket.vy,
                 life: 1.0,
-                max_life: 2.0 + fastrand::f32() * 1.0, // Long life for willowtail
+                max_life: 2.0 + self.rng.f32() * 1.0, // Long life for willowtail
                 color: rocket.color,
                 color_end: None,
                 strobe_phase: 0.0,
@@ -1,13 +1,12 @@
-use super::Effect;
+use super::{Canvas, Effect, EffectInfo, ParamInfo, ParamKind, ParamValue};
 use crossterm::event::{Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind};
-use std::io::{BufWriter, Stdout, Write};
+use fastrand::Rng;
 
 pub struct GameOfLifeEffect {
     width: usize,
     height: usize,
     cells: Vec<u8>,      // Cell states (0 = dead, 1 = alive, 2+ = dying)
     next_cells: Vec<u8>,
-    output_buf: Vec<u8>,
     survival_rules: Vec<u8>, // Neighbor counts that keep a cell alive
     birth_rules: Vec<u8>,    // Neighbor counts that birth a new cell
     update_counter: f32,
@@ -17,32 +16,65 @@ pub struct GameOfLifeEffect {
     alive_color: (u8, u8, u8),
     color_transition: f32,
     num_states: u8, // Total number of states (2-10)
+    pace: f32, // Fraction of the generations to step; below 1 at reduced quality
+    rng: Rng,
 }
 
+pub const INFO: EffectInfo = EffectInfo {
+    name: "gol",
+    aliases: &["life", "gameoflife"],
+    description: "Conway's Game of Life with randomized multi-state rules",
+    controls: &[
+        ("0-8", "toggle a survival count"),
+        (")!@#$%^&*", "toggle a birth count"),
+        ("+/-", "more or fewer states"),
+        ("r", "random rules"),
+        ("click", "cycle a cell"),
+        ("right click", "reset the board"),
+    ],
+    params: &[
+        ParamInfo {
+            name: "interval",
+            description: "Seconds between generations",
+            kind: ParamKind::Float { min: 0.01, max: 2.0, step: 0.01 },
+        },
+        ParamInfo {
+            name: "rules",
+            description: "Birth/survival rules, e.g. B3/S23",
+            kind: ParamKind::Text(check_rules),
+        },
+        ParamInfo {
+            name: "states",
+            description: "Number of cell states",
+            kind: ParamKind::Int { min: 2, max: 20 },
+        },
+    ],
+    create: super::construct::<GameOfLifeEffect>,
+};
+
 impl Effect for GameOfLifeEffect {
-    fn new(width: usize, height: usize) -> Self {
+    fn new(width: usize, height: usize, mut rng: Rng) -> Self {
         let cell_count = width * height;
-        let num_states = 2 + fastrand::u8(0..9); // Random 2-10 states
+        let num_states = 2 + rng.u8(0..9); // Random 2-10 states
         let mut cells = vec![0u8; cell_count];
 
         // Random initial state (15% alive at max state)
         for cell in cells.iter_mut() {
-            if fastrand::f32() < 0.15 {
+            if rng.f32() < 0.15 {
                 *cell = num_states - 1;
             }
         }
 
-        let (survival_rules, birth_rules) = Self::random_rules();
+        let (survival_rules, birth_rules) = Self::random_rules(&mut rng);
 
-        let current_color = Self::random_color();
-        let target_color = Self::random_color();
+        let current_color = Self::random_color(&mut rng);
+        let target_color = Self::random_color(&mut rng);
 
         Self {
             width,
             height,
             cells,
             next_cells: vec![0u8; cell_count],
-            output_buf: Vec::with_capacity(width * height * 25),
             survival_rules,
             birth_rules,
             update_counter: 0.0,
@@ -52,6 +84,8 @@ impl Effect for GameOfLifeEffect {
             alive_color: current_color,
             color_transition: 0.0,
             num_states,
+            pace: 1.0,
+            rng,
         }
     }
 
@@ -64,7 +98,7 @@ impl Effect for GameOfLifeEffect {
         if self.color_transition >= 1.0 {
             // Reached target color, pick a new target
             self.current_color = self.target_color;
-            self.target_color = Self::random_color();
+            self.target_color = Self::random_color(&mut self.rng);
             self.color_transition = 0.0;
         }
 
@@ -75,69 +109,56 @@ impl Effect for GameOfLifeEffect {
         let b = (self.current_color.2 as f32 * (1.0 - t) + self.target_color.2 as f32 * t) as u8;
         self.alive_color = (r, g, b);
 
-        if self.update_counter >= self.update_interval {
+        if self.update_counter >= self.update_interval / self.pace {
             self.update_counter = 0.0;
             self.step_generation();
         }
     }
 
-    fn render(&mut self, stdout: &mut BufWriter<Stdout>) -> std::io::Result<()> {
-        self.output_buf.clear();
-        self.output_buf.extend_from_slice(b"\x1b[H"); // Move to home
-
+    fn render(&mut self, canvas: &mut Canvas) {
         let bg_color = crate::get_bg_color();
 
-        // Build rule string
-        let survival_str: String = self.survival_rules.iter()
-            .map(|n| n.to_string())
-            .collect::<Vec<_>>()
-            .join("");
-        let birth_str: String = self.birth_rules.iter()
-            .map(|n| n.to_string())
-            .collect::<Vec<_>>()
-            .join("");
-        let rule_text = format!("B{}/S{}/{}", birth_str, survival_str, self.num_states);
-
-        let mut prev_top: (u8, u8, u8) = (255, 255, 255);
-        let mut prev_bot: (u8, u8, u8) = (255, 255, 255);
-
-        for y in (0..self.height).step_by(2) {
+        for y in 0..self.height {
             for x in 0..self.width {
-                let top_state = self.cells[y * self.width + x];
-                let bot_state = if y + 1 < self.height {
-                    self.cells[(y + 1) * self.width + x]
-                } else {
-                    0
-                };
+                let state = self.cells[y * self.width + x];
+                canvas.set(x, y, self.state_to_color(state, bg_color));
+            }
+        }
 
-                let top = self.state_to_color(top_state, bg_color);
-                let bot = self.state_to_color(bot_state, bg_color);
+        let rule_text = format!(" {}/{} ", self.rules_text(), self.num_states);
 
-                // Only emit color codes if changed
-                if top != prev_top {
-                    write!(self.output_buf, "\x1b[48;2;{};{};{}m", top.0, top.1, top.2)?;
-                    prev_top = top;
-                }
-                if bot != prev_bot {
-                    write!(self.output_buf, "\x1b[38;2;{};{};{}m", bot.0, bot.1, bot.2)?;
-                    prev_bot = bot;
-                }
-                self.output_buf.extend_from_slice("▄".as_bytes());
+        // Draw rule text in top left (overlay)
+        canvas.put_text(0, 0, &rule_text, (255, 255, 255), Some((0, 0, 0)), false);
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        // Stretching would break up the patterns, so keep the board centered
+        // and crop it or pad it with dead cells instead
+        let offset_x = (width as isize - self.width as isize) / 2;
+        let offset_y = (height as isize - self.height as isize) / 2;
+        let mut cells = vec![0u8; width * height];
+        for y in 0..height {
+            let src_y = y as isize - offset_y;
+            if src_y < 0 || src_y >= self.height as isize {
+                continue;
             }
-            self.output_buf.extend_from_slice(b"\x1b[0m");
-            prev_top = (255, 255, 255);
-            prev_bot = (255, 255, 255);
-            if y + 2 < self.height {
-                self.output_buf.extend_from_slice(b"\r\n");
+            for x in 0..width {
+                let src_x = x as isize - offset_x;
+                if src_x >= 0 && src_x < self.width as isize {
+                    cells[y * width + x] = self.cells[src_y as usize * self.width + src_x as usize];
+                }
             }
         }
+        self.cells = cells;
+        self.next_cells = vec![0u8; width * height];
+        self.width = width;
+        self.height = height;
+    }
 
-        // Draw rule text in top left (overlay)
-        write!(self.output_buf, "\x1b[1;1H\x1b[38;2;255;255;255m\x1b[48;2;0;0;0m {rule_text} \x1b[0m")?;
-
-        stdout.write_all(&self.output_buf)?;
-        stdout.flush()?;
-        Ok(())
+    fn set_quality(&mut self, quality: f32) {
+        // Every cell matters to the next generation, so rather than sample the
+        // board more coarsely, step it less often
+        self.pace = quality;
     }
 
     fn handle_event(&mut self, event: &Event) {
@@ -146,8 +167,7 @@ impl Effect for GameOfLifeEffect {
                 match mouse_event.kind {
                     MouseEventKind::Down(MouseButton::Left) => {
                         // Toggle cell at mouse position
-                        let x = mouse_event.column as usize;
-                        let y = mouse_event.row as usize * 2; // Account for half-block rendering
+                        let (x, y) = super::cell_to_pixel(mouse_event.column, mouse_event.row);
 
                         if x < self.width && y < self.height {
                             let idx = y * self.width + x;
@@ -168,20 +188,23 @@ impl Effect for GameOfLifeEffect {
                 // 'r' to randomize rules
                 match key_event.code {
                     KeyCode::Char('r') => {
-                        let (survival, birth) = Self::random_rules();
+                        let (survival, birth) = Self::random_rules(&mut self.rng);
                         self.survival_rules = survival;
                         self.birth_rules = birth;
                     }
+                    // Guards would let the key fall through to later arms at the limit
+                    #[allow(clippy::collapsible_match)]
                     KeyCode::Char('+') | KeyCode::Char('=') => {
                         // Increase state count
                         if self.num_states < 20 {
-                            self.num_states += 1;
+                            self.set_states(self.num_states + 1);
                         }
                     }
+                    #[allow(clippy::collapsible_match)]
                     KeyCode::Char('-') | KeyCode::Char('_') => {
                         // Decrease state count
                         if self.num_states > 2 {
-                            self.num_states -= 1;
+                            self.set_states(self.num_states - 1);
                         }
                     }
                     KeyCode::Char(c @ '0'..='8') => {
@@ -235,19 +258,88 @@ impl Effect for GameOfLifeEffect {
             _ => {}
         }
     }
+
+    fn param(&self, name: &str) -> Option<ParamValue> {
+        Some(match name {
+            "interval" => ParamValue::Float(self.update_interval),
+            "rules" => ParamValue::Text(self.rules_text()),
+            "states" => ParamValue::Int(self.num_states as i64),
+            _ => return None,
+        })
+    }
+
+    fn set_param(&mut self, name: &str, value: &ParamValue) -> Result<(), String> {
+        match name {
+            "interval" => self.update_interval = value.as_f32(),
+            "rules" => {
+                let (birth, survival) = Self::parse_rules(value.as_str())?;
+                self.birth_rules = birth;
+                self.survival_rules = survival;
+            }
+            "states" => self.set_states(value.as_int() as u8),
+            _ => return Err(format!("unknown parameter '{}'", name)),
+        }
+        Ok(())
+    }
+}
+
+/// Check rules without a board, for `--set` and the config file.
+fn check_rules(text: &str) -> Result<(), String> {
+    GameOfLifeEffect::parse_rules(text).map(|_| ())
 }
 
 impl GameOfLifeEffect {
-    fn random_rules() -> (Vec<u8>, Vec<u8>) {
+    /// Current rules in B/S notation, e.g. "B3/S23".
+    fn rules_text(&self) -> String {
+        let birth_str: String = self.birth_rules.iter().map(|n| n.to_string()).collect();
+        let survival_str: String = self.survival_rules.iter().map(|n| n.to_string()).collect();
+        format!("B{}/S{}", birth_str, survival_str)
+    }
+
+    /// Parse rules in B/S notation, e.g. "B3/S23". Returns (birth, survival).
+    fn parse_rules(text: &str) -> Result<(Vec<u8>, Vec<u8>), String> {
+        let mut birth = Vec::new();
+        let mut survival = Vec::new();
+        let mut seen = (false, false);
+
+        for part in text.split('/') {
+            let mut chars = part.chars();
+            let target = match chars.next().map(|c| c.to_ascii_uppercase()) {
+                Some('B') => {
+                    seen.0 = true;
+                    &mut birth
+                }
+                Some('S') => {
+                    seen.1 = true;
+                    &mut survival
+                }
+                _ => return Err(format!("invalid rules '{}' (expected something like B3/S23)", text)),
+            };
+            for c in chars {
+                match c.to_digit(10) {
+                    Some(n) if n <= 8 && !target.contains(&(n as u8)) => target.push(n as u8),
+                    _ => return Err(format!("invalid neighbor count '{}' in rules '{}'", c, text)),
+                }
+            }
+            target.sort();
+        }
+
+        if !(seen.0 && seen.1) {
+            return Err(format!("invalid rules '{}' (expected something like B3/S23)", text));
+        }
+        Ok((birth, survival))
+    }
+
+    fn random_rules(rng: &mut Rng) -> (Vec<u8>, Vec<u8>) {
         // Generate completely random rules
         let mut survival = Vec::new();
         let mut birth = Vec::new();
 
         for i in 0..9 {
-            if fastrand::f32() < 0.3 {
+            if rng.f32() < 0.3 {
                 survival.push(i);
             }
-            if fastrand::f32() < 0.3 {
+            if rng.f32() < 0.3 {
                 birth.push(i);
             }
         }
@@ -262,10 +354,10 @@ impl GameOfLifeEffect {
         (survival, birth)
     }
 
-    fn random_color() -> (u8, u8, u8) {
-        let hue = fastrand::f32();
-        let s = 0.6 + fastrand::f32() * 0.4;
-        let v = 0.7 + fastrand::f32() * 0.3;
+    fn random_color(rng: &mut Rng) -> (u8, u8, u8) {
+        let hue = rng.f32();
+        let s = 0.6 + rng.f32() * 0.4;
+        let v = 0.7 + rng.f32() * 0.3;
 
         let h = hue * 6.0;
         let c = v * s;
@@ -293,6 +385,23 @@ impl GameOfLifeEffect {
         )
     }
 
+    /// Change the number of states. Live cells stay alive and dying cells keep
+    /// the same fraction of their decay left, so nothing comes back to life.
+    fn set_states(&mut self, num_states: u8) {
+        let (old_max, new_max) = (self.num_states as u32 - 1, num_states as u32 - 1);
+        for cell in self.cells.iter_mut().filter(|c| **c != 0) {
+            let state = *cell as u32;
+            *cell = if state >= old_max {
+                new_max
+            } else if new_max < 2 {
+                0 // Two states leave no room for dying
+            } else {
+                ((state * new_max + old_max / 2) / old_max).clamp(1, new_max - 1)
+            } as u8;
+        }
+        self.num_states = num_states;
+    }
+
     fn count_neighbors(&self, x: usize, y: usize) -> u8 {
         let mut count = 0;
         let max_state = self.num_states - 1;
@@ -359,20 +468,20 @@ impl GameOfLifeEffect {
 
     fn reset(&mut self) {
         // Randomize rules and state count first
-        let (survival, birth) = Self::random_rules();
+        let (survival, birth) = Self::random_rules(&mut self.rng);
         self.survival_rules = survival;
         self.birth_rules = birth;
-        self.num_states = 2 + fastrand::u8(0..9); // Random 2-10 states
+        self.num_states = 2 + self.rng.u8(0..9); // Random 2-10 states
 
         // Randomize cells again (spawn at max state)
         let max_state = self.num_states - 1;
         for cell in self.cells.iter_mut() {
-            *cell = if fastrand::f32() < 0.15 { max_state } else { 0 };
+            *cell = if self.rng.f32() < 0.15 { max_state } else { 0 };
         }
 
         // Reset color transition
         self.current_color = self.alive_color;
-        self.target_color = Self::random_color();
+        self.target_color = Self::random_color(&mut self.rng);
         self.color_transition = 0.0;
     }
 
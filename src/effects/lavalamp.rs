@@ -1,5 +1,5 @@
-use super::Effect;
-use std::io::{BufWriter, Stdout, Write};
+use super::{Canvas, Effect, EffectInfo, ParamInfo, ParamKind, ParamValue};
+use fastrand::Rng;
 
 struct Blob {
     x: f32,
@@ -13,46 +13,65 @@ pub struct LavaLampEffect {
     width: usize,
     height: usize,
     blobs: Vec<Blob>,
+    max_blobs: usize,
     field: Vec<f32>,
     time: f32,
-    output_buf: Vec<u8>,
     current_color: (u8, u8, u8),
     target_color: (u8, u8, u8),
     lava_color: (u8, u8, u8), // Interpolated display color
     color_transition: f32,
+    block: usize, // Pixels per field sample along each axis; above 1 at reduced quality
+    rng: Rng,
 }
 
+pub const INFO: EffectInfo = EffectInfo {
+    name: "lavalamp",
+    aliases: &["lava"],
+    description: "Smooth metaball lava lamp animation",
+    controls: &[],
+    params: &[
+        ParamInfo {
+            name: "max_blobs",
+            description: "Most blobs in the lamp at once",
+            kind: ParamKind::Int { min: 1, max: 60 },
+        },
+    ],
+    create: super::construct::<LavaLampEffect>,
+};
+
 impl Effect for LavaLampEffect {
-    fn new(width: usize, height: usize) -> Self {
+    fn new(width: usize, height: usize, mut rng: Rng) -> Self {
         // Create 8-12 blobs starting at the bottom
-        let blob_count = 8 + fastrand::usize(0..5);
+        let blob_count = 8 + rng.usize(0..5);
         let mut blobs = Vec::with_capacity(blob_count);
 
         for _i in 0..blob_count {
-            let radius = 6.0 + fastrand::f32() * 10.0; // Radius 6-16 (can bond together)
+            let radius = 6.0 + rng.f32() * 10.0; // Radius 6-16 (can bond together)
             blobs.push(Blob {
-                x: fastrand::f32() * width as f32, // Spawn randomly across full width
-                y: height as f32 + radius * 2.0 - fastrand::f32() * 40.0, // Start below/at bottom
+                x: rng.f32() * width as f32, // Spawn randomly across full width
+                y: height as f32 + radius * 2.0 - rng.f32() * 40.0, // Start below/at bottom
                 vy: 0.0,
                 radius,
-                temperature: 0.8 + fastrand::f32() * 0.2, // Start hot
+                temperature: 0.8 + rng.f32() * 0.2, // Start hot
             });
         }
 
-        let current_color = Self::random_lava_color();
-        let target_color = Self::random_lava_color();
+        let current_color = Self::random_lava_color(&mut rng);
+        let target_color = Self::random_lava_color(&mut rng);
 
         Self {
             width,
             height,
             blobs,
+            max_blobs: 25,
             field: vec![0.0; width * height],
             time: 0.0,
-            output_buf: Vec::with_capacity(width * height * 25),
             current_color,
             target_color,
             lava_color: current_color,
             color_transition: 0.0,
+            block: 1,
+            rng,
         }
     }
 
@@ -69,7 +88,7 @@ impl Effect for LavaLampEffect {
         if self.color_transition >= 1.0 {
             // Reached target color, pick a new target
             self.current_color = self.target_color;
-            self.target_color = Self::random_lava_color();
+            self.target_color = Self::random_lava_color(&mut self.rng);
             self.color_transition = 0.0;
         }
 
@@ -84,11 +103,11 @@ impl Effect for LavaLampEffect {
         let width = self.width as f32;
 
         // Spawn new blobs below the screen periodically
-        // Limit to max 25 blobs
-        if fastrand::f32() < 0.05 && self.blobs.len() < 25 {
-            let radius = 6.0 + fastrand::f32() * 10.0; // Radius 6-16
+        // Limit to max_blobs
+        if self.rng.f32() < 0.05 && self.blobs.len() < self.max_blobs {
+            let radius = 6.0 + self.rng.f32() * 10.0; // Radius 6-16
             self.blobs.push(Blob {
-                x: fastrand::f32() * width, // Spawn across full width
+                x: self.rng.f32() * width, // Spawn across full width
                 y: height + radius * 2.0 + 10.0, // Bigger blobs spawn further below
                 vy: 0.0,
                 radius,
@@ -146,9 +165,9 @@ impl Effect for LavaLampEffect {
         });
 
         // Calculate metaball field (simple and fast)
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let idx = y * self.width + x;
+        let block = self.block;
+        for y in (0..self.height).step_by(block) {
+            for x in (0..self.width).step_by(block) {
                 let mut field_value = 0.0;
 
                 // Sum influence from all blobs
@@ -173,65 +192,73 @@ impl Effect for LavaLampEffect {
                     }
                 }
 
-                self.field[idx] = field_value;
+                // One sample covers a block of pixels at reduced quality
+                for py in y..(y + block).min(self.height) {
+                    for px in x..(x + block).min(self.width) {
+                        self.field[py * self.width + px] = field_value;
+                    }
+                }
             }
         }
     }
 
-    fn render(&mut self, stdout: &mut BufWriter<Stdout>) -> std::io::Result<()> {
-        self.output_buf.clear();
-        self.output_buf.extend_from_slice(b"\x1b[H"); // Move to home
-
+    fn render(&mut self, canvas: &mut Canvas) {
         let bg_color = crate::get_bg_color();
-        let mut prev_top: (u8, u8, u8) = (255, 255, 255);
-        let mut prev_bot: (u8, u8, u8) = (255, 255, 255);
 
-        for y in (0..self.height).step_by(2) {
+        for y in 0..self.height {
             for x in 0..self.width {
-                let top_field = self.field[y * self.width + x];
-                let bot_field = if y + 1 < self.height {
-                    self.field[(y + 1) * self.width + x]
-                } else {
-                    0.0
-                };
-
                 // Map field value to color (threshold at 1.0)
-                let top = self.field_to_color(top_field, bg_color);
-                let bot = self.field_to_color(bot_field, bg_color);
-
-                // Only emit color codes if changed
-                if top != prev_top {
-                    write!(self.output_buf, "\x1b[48;2;{};{};{}m", top.0, top.1, top.2)?;
-                    prev_top = top;
-                }
-                if bot != prev_bot {
-                    write!(self.output_buf, "\x1b[38;2;{};{};{}m", bot.0, bot.1, bot.2)?;
-                    prev_bot = bot;
-                }
-                self.output_buf.extend_from_slice("▄".as_bytes());
-            }
-            self.output_buf.extend_from_slice(b"\x1b[0m");
-            prev_top = (255, 255, 255);
-            prev_bot = (255, 255, 255);
-            if y + 2 < self.height {
-                self.output_buf.extend_from_slice(b"\r\n");
+                let field = self.field[y * self.width + x];
+                canvas.set(x, y, self.field_to_color(field, bg_color));
             }
         }
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        // Blobs keep their size; only where they are in the lamp scales
+        let sx = width as f32 / self.width as f32;
+        let sy = height as f32 / self.height as f32;
+        for blob in &mut self.blobs {
+            blob.x *= sx;
+            blob.y *= sy;
+        }
+        // The field is rebuilt on the next update, but a frame may be drawn before that
+        self.field = super::resample(&self.field, self.width, self.height, width, height);
+        self.width = width;
+        self.height = height;
+    }
 
-        stdout.write_all(&self.output_buf)?;
-        stdout.flush()?;
+    fn set_quality(&mut self, quality: f32) {
+        self.block = (1.0 / quality).round().max(1.0) as usize;
+    }
+
+    fn param(&self, name: &str) -> Option<ParamValue> {
+        Some(match name {
+            "max_blobs" => ParamValue::Int(self.max_blobs as i64),
+            _ => return None,
+        })
+    }
+
+    fn set_param(&mut self, name: &str, value: &ParamValue) -> Result<(), String> {
+        match name {
+            "max_blobs" => {
+                self.max_blobs = value.as_int() as usize;
+                self.blobs.truncate(self.max_blobs);
+            }
+            _ => return Err(format!("unknown parameter '{}'", name)),
+        }
         Ok(())
     }
 }
 
 impl LavaLampEffect {
-    fn random_lava_color() -> (u8, u8, u8) {
+    fn random_lava_color(rng: &mut Rng) -> (u8, u8, u8) {
         // Generate vibrant lava lamp colors
-        let hue = fastrand::f32(); // 0.0 to 1.0
+        let hue = rng.f32(); // 0.0 to 1.0
 
         // Convert HSV to RGB (S=0.7-1.0, V=0.8-1.0 for vibrant colors)
-        let s = 0.7 + fastrand::f32() * 0.3;
-        let v = 0.8 + fastrand::f32() * 0.2;
+        let s = 0.7 + rng.f32() * 0.3;
+        let v = 0.8 + rng.f32() * 0.2;
 
         let h = hue * 6.0;
         let c = v * s;
@@ -1,5 +1,7 @@
+use crate::config::Value;
 use crossterm::event::Event;
-use std::io::{BufWriter, Stdout};
+use fastrand::Rng;
+use std::fmt;
 
 pub mod fire;
 pub mod thunder;
@@ -13,10 +15,398 @@ pub mod bioluminescence;
 pub mod star;
 
 pub trait Effect {
-    fn new(width: usize, height: usize) -> Self
+    /// Create the effect. It draws every random number from `rng`, so the
+    /// same seed always plays out the same way.
+    fn new(width: usize, height: usize, rng: Rng) -> Self
     where
         Self: Sized;
     fn update(&mut self, dt: f32);
-    fn render(&mut self, stdout: &mut BufWriter<Stdout>) -> std::io::Result<()>;
+    fn render(&mut self, canvas: &mut Canvas);
+
+    /// The canvas changed size. Keep the running state, rescaled to fit.
+    fn resize(&mut self, width: usize, height: usize);
+
     fn handle_event(&mut self, _event: &Event) {}
+
+    /// Hint from the main loop about how much work to do per frame: 1.0 is full
+    /// detail, lower values (down to 0.25) ask for cheaper rendering.
+    fn set_quality(&mut self, quality: f32);
+
+    /// Current value of a parameter declared in the effect's `EffectInfo`.
+    fn param(&self, _name: &str) -> Option<ParamValue> {
+        None
+    }
+
+    /// Change a parameter. Use `apply_param`, which checks the value against
+    /// the parameter's kind and range before calling this.
+    fn set_param(&mut self, name: &str, _value: &ParamValue) -> Result<(), String> {
+        Err(format!("unknown parameter '{}'", name))
+    }
+}
+
+/// The type and allowed values of a parameter.
+#[derive(Clone, Copy, Debug)]
+pub enum ParamKind {
+    Float { min: f32, max: f32, step: f32 }, // Step is the tweak panel's arrow key increment
+    Int { min: i64, max: i64 },
+    Choice(&'static [&'static str]),
+    Text(fn(&str) -> Result<(), String>), // Free-form, checked by the given function (e.g. gol rules)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamValue {
+    Float(f32),
+    Int(i64),
+    Text(String), // Also used for choices
+}
+
+/// A named, typed parameter an effect exposes for tuning, as declared in the registry.
+pub struct ParamInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub kind: ParamKind,
+}
+
+/// A parameter with its current value in a running effect.
+pub struct Param {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub kind: ParamKind,
+    pub value: ParamValue,
+}
+
+impl fmt::Display for ParamValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParamValue::Float(v) => write!(f, "{}", v),
+            ParamValue::Int(v) => write!(f, "{}", v),
+            ParamValue::Text(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+impl Param {
+    /// The value as shown to the user, with floats rounded to the step's precision.
+    pub fn value_text(&self) -> String {
+        match (self.kind, &self.value) {
+            (ParamKind::Float { step, .. }, ParamValue::Float(v)) => {
+                let decimals = format!("{}", step).split_once('.').map_or(0, |(_, frac)| frac.len());
+                format!("{:.*}", decimals, v)
+            }
+            (_, value) => value.to_string(),
+        }
+    }
+}
+
+impl ParamValue {
+    pub fn as_f32(&self) -> f32 {
+        match self {
+            ParamValue::Float(v) => *v,
+            ParamValue::Int(v) => *v as f32,
+            ParamValue::Text(_) => 0.0,
+        }
+    }
+
+    pub fn as_int(&self) -> i64 {
+        match self {
+            ParamValue::Int(v) => *v,
+            other => other.as_f32() as i64,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            ParamValue::Text(v) => v,
+            _ => "",
+        }
+    }
+}
+
+impl fmt::Display for ParamKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParamKind::Float { min, max, .. } => write!(f, "{}..{}", min, max),
+            ParamKind::Int { min, max } => write!(f, "{}..{}", min, max),
+            ParamKind::Choice(choices) => write!(f, "{}", choices.join("|")),
+            ParamKind::Text(_) => write!(f, "text"),
+        }
+    }
+}
+
+impl ParamKind {
+    /// Parse a value typed on the command line, e.g. the `2.0` in `--set fire.wind=2.0`.
+    pub fn parse_text(self, text: &str) -> Result<ParamValue, String> {
+        let value = match self {
+            ParamKind::Float { .. } => {
+                ParamValue::Float(text.parse().map_err(|_| format!("expected a number, got '{}'", text))?)
+            }
+            ParamKind::Int { .. } => {
+                ParamValue::Int(text.parse().map_err(|_| format!("expected a whole number, got '{}'", text))?)
+            }
+            ParamKind::Choice(_) | ParamKind::Text(_) => ParamValue::Text(text.to_string()),
+        };
+        self.check(value)
+    }
+
+    /// Convert a value from the config file.
+    pub fn parse_value(self, value: &Value) -> Result<ParamValue, String> {
+        let value = match self {
+            ParamKind::Float { .. } => ParamValue::Float(value.as_f64()? as f32),
+            ParamKind::Int { .. } => match value {
+                Value::Int(n) => ParamValue::Int(*n),
+                other => return Err(format!("expected a whole number, found {}", other.type_name())),
+            },
+            ParamKind::Choice(_) | ParamKind::Text(_) => ParamValue::Text(value.as_str()?.to_string()),
+        };
+        self.check(value)
+    }
+
+    fn check(self, value: ParamValue) -> Result<ParamValue, String> {
+        match (self, &value) {
+            (ParamKind::Float { min, max, .. }, ParamValue::Float(v)) if !(min..=max).contains(v) => {
+                Err(format!("must be between {} and {}, got {}", min, max, v))
+            }
+            (ParamKind::Int { min, max }, ParamValue::Int(v)) if !(min..=max).contains(v) => {
+                Err(format!("must be between {} and {}, got {}", min, max, v))
+            }
+            (ParamKind::Choice(choices), ParamValue::Text(v)) if !choices.contains(&v.as_str()) => {
+                Err(format!("must be one of {}, got '{}'", choices.join(", "), v))
+            }
+            (ParamKind::Text(check), ParamValue::Text(v)) => check(v).map(|()| value),
+            _ => Ok(value),
+        }
+    }
+}
+
+/// Check a value against the parameter's kind and range, then set it on
+/// `effect`, which must be the effect `info` describes.
+pub fn apply_param(info: &EffectInfo, effect: &mut dyn Effect, name: &str, value: ParamValue) -> Result<(), String> {
+    let value = info.find_param(name)?.kind.check(value)?;
+    effect.set_param(name, &value)
+}
+
+/// Creates a boxed effect for the given pixel dimensions, drawing from the given generator.
+pub type Constructor = fn(usize, usize, Rng) -> Box<dyn Effect>;
+
+fn construct<E: Effect + 'static>(width: usize, height: usize, rng: Rng) -> Box<dyn Effect> {
+    let (width, height) = effect_size(width, height);
+    Box::new(E::new(width, height, rng))
+}
+
+/// Smallest size in pixels effects are created or resized to. On a smaller
+/// canvas they show their top-left corner; thunder needs this much room to
+/// keep bolts away from the edges.
+const MIN_EFFECT_SIZE: (usize, usize) = (32, 16);
+
+/// The size to give an effect for a canvas of `width` x `height` pixels.
+pub fn effect_size(width: usize, height: usize) -> (usize, usize) {
+    (width.max(MIN_EFFECT_SIZE.0), height.max(MIN_EFFECT_SIZE.1))
+}
+
+/// Stretch a row-major grid to a new size, picking the nearest old cell for each new one.
+fn resample<T: Copy>(grid: &[T], width: usize, height: usize, new_width: usize, new_height: usize) -> Vec<T> {
+    let mut resampled = Vec::with_capacity(new_width * new_height);
+    for y in 0..new_height {
+        let src_y = (y * height / new_height).min(height - 1);
+        for x in 0..new_width {
+            let src_x = (x * width / new_width).min(width - 1);
+            resampled.push(grid[src_y * width + src_x]);
+        }
+    }
+    resampled
+}
+
+/// Everything the command line and help screens need to know about an effect.
+pub struct EffectInfo {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub description: &'static str,
+    pub controls: &'static [(&'static str, &'static str)], // (input, what it does)
+    pub params: &'static [ParamInfo],
+    pub create: Constructor,
+}
+
+impl EffectInfo {
+    /// Find one of the effect's parameters by name.
+    pub fn find_param(&self, name: &str) -> Result<&'static ParamInfo, String> {
+        let params = self.params;
+        match params.iter().find(|p| p.name == name) {
+            Some(param) => Ok(param),
+            None if params.is_empty() => Err("this effect has no parameters".to_string()),
+            None => {
+                let names: Vec<&str> = params.iter().map(|p| p.name).collect();
+                Err(format!("unknown parameter '{}' (expected one of {})", name, names.join(", ")))
+            }
+        }
+    }
+
+    /// The parameters with their current values in `effect`, which must be this effect.
+    pub fn params(&self, effect: &dyn Effect) -> Vec<Param> {
+        self.params
+            .iter()
+            .filter_map(|param| {
+                Some(Param {
+                    name: param.name,
+                    description: param.description,
+                    kind: param.kind,
+                    value: effect.param(param.name)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Every effect, in the order they are listed in the usage text.
+pub const REGISTRY: &[EffectInfo] = &[
+    fire::INFO,
+    thunder::INFO,
+    plasma::INFO,
+    fireworks::INFO,
+    lavalamp::INFO,
+    aurora::INFO,
+    clouds::INFO,
+    bioluminescence::INFO,
+    star::INFO,
+    gameoflife::INFO,
+];
+
+/// Find an effect by name or alias.
+pub fn lookup(name: &str) -> Option<&'static EffectInfo> {
+    REGISTRY.iter().find(|info| info.name == name || info.aliases.contains(&name))
+}
+
+/// Map a terminal cell (e.g. a mouse position) to the top-left pixel it covers.
+pub fn cell_to_pixel(column: u16, row: u16) -> (usize, usize) {
+    let (cell_w, cell_h) = crate::get_pixel_mode().cell_size();
+    (column as usize * cell_w, row as usize * cell_h)
+}
+
+/// A character drawn on top of the pixel grid, occupying one terminal cell.
+#[derive(Clone, Copy, PartialEq)]
+pub struct TextCell {
+    pub ch: char,
+    pub fg: (u8, u8, u8),
+    pub bg: Option<(u8, u8, u8)>, // None = keep the pixels underneath
+    pub bold: bool,
+}
+
+/// RGB framebuffer that effects draw into each frame.
+///
+/// Pixels are addressed in effect space (`width` x `height`), which the
+/// renderer maps onto terminal cells of `cell_size` pixels each. Text is
+/// addressed in cell space and drawn over the pixels.
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<(u8, u8, u8)>,
+    cols: usize,
+    rows: usize,
+    text: Vec<Option<TextCell>>,
+}
+
+impl Canvas {
+    pub fn new(cols: usize, rows: usize, cell_size: (usize, usize)) -> Self {
+        // A terminal can report 0x0, e.g. a pty nobody has sized yet
+        let (cols, rows) = (cols.max(1), rows.max(1));
+        let width = cols * cell_size.0;
+        let height = rows * cell_size.1;
+
+        Self {
+            width,
+            height,
+            pixels: vec![(0, 0, 0); width * height],
+            cols,
+            rows,
+            text: vec![None; cols * rows],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Terminal columns covered by the canvas.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Terminal rows covered by the canvas.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn pixels(&self) -> &[(u8, u8, u8)] {
+        &self.pixels
+    }
+
+    #[inline]
+    pub fn set(&mut self, x: usize, y: usize, color: (u8, u8, u8)) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, x: usize, y: usize) -> (u8, u8, u8) {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Copy a float RGB buffer `width` pixels wide, rounding and clamping each
+    /// channel. Whatever falls outside the canvas is left out.
+    pub fn copy_from_f32(&mut self, buffer: &[(f32, f32, f32)], width: usize) {
+        let columns = width.min(self.width);
+        for (row, line) in self.pixels.chunks_exact_mut(self.width).zip(buffer.chunks_exact(width)) {
+            for (pixel, color) in row[..columns].iter_mut().zip(line) {
+                *pixel = (
+                    color.0.round().clamp(0.0, 255.0) as u8,
+                    color.1.round().clamp(0.0, 255.0) as u8,
+                    color.2.round().clamp(0.0, 255.0) as u8,
+                );
+            }
+        }
+    }
+
+    /// Blend the pixels under `cols` x `rows` cells from `(col, row)` toward
+    /// `tint` by `alpha`, so text drawn there stays readable over the effect.
+    pub fn darken_cells(&mut self, (col, row): (usize, usize), (cols, rows): (usize, usize), tint: (u8, u8, u8), alpha: f32) {
+        let (cell_w, cell_h) = (self.width / self.cols, self.height / self.rows);
+        let mix = |c: u8, t: u8| (c as f32 * (1.0 - alpha) + t as f32 * alpha) as u8;
+        for y in row * cell_h..((row + rows) * cell_h).min(self.height) {
+            for x in col * cell_w..((col + cols) * cell_w).min(self.width) {
+                let (r, g, b) = self.pixels[y * self.width + x];
+                self.pixels[y * self.width + x] = (mix(r, tint.0), mix(g, tint.1), mix(b, tint.2));
+            }
+        }
+    }
+
+    /// Write a string starting at the given terminal cell, clipped to the canvas.
+    pub fn put_text(&mut self, col: usize, row: usize, text: &str, fg: (u8, u8, u8), bg: Option<(u8, u8, u8)>, bold: bool) {
+        if row >= self.rows {
+            return;
+        }
+        for (i, ch) in text.chars().enumerate() {
+            let x = col + i;
+            if x >= self.cols {
+                break;
+            }
+            self.text[row * self.cols + x] = Some(TextCell { ch, fg, bg, bold });
+        }
+    }
+
+    pub fn text_at(&self, col: usize, row: usize) -> Option<TextCell> {
+        self.text[row * self.cols + col]
+    }
+
+    pub fn clear_text(&mut self) {
+        self.text.fill(None);
+    }
+
+    /// Replace this canvas's text layer with another one of the same size.
+    pub fn copy_text_from(&mut self, other: &Canvas) {
+        self.text.copy_from_slice(&other.text);
+    }
 }
@@ -1,6 +1,6 @@
-use super::Effect;
+use super::{Canvas, Effect, EffectInfo, ParamInfo, ParamKind, ParamValue};
 use crossterm::event::{Event, MouseEvent, MouseEventKind};
-use std::io::{BufWriter, Stdout, Write};
+use fastrand::Rng;
 
 // Pink core with white glow
 const CORE_COLORS: [(u8, u8, u8); 6] = [
@@ -34,7 +34,9 @@ struct Tendril {
 }
 
 impl Tendril {
+    #[allow(clippy::too_many_arguments)]
     fn new(
+        rng: &mut Rng,
         center_x: f32,
         center_y: f32,
         orb_radius: f32,
@@ -54,7 +56,7 @@ impl Tendril {
             let perimeter = 2.0 * (width + height) as f32;
             let segment_length = perimeter / 12.0;
             let segment_start = edge_segment as f32 * segment_length;
-            let offset = fastrand::f32() * segment_length; // Random position within segment
+            let offset = rng.f32() * segment_length; // Random position within segment
 
             let distance = segment_start + offset;
 
@@ -85,6 +87,7 @@ impl Tendril {
 
         let mut segments = Vec::new();
         Self::generate_tendril(
+            rng,
             &mut segments,
             start_x,
             start_y,
@@ -102,7 +105,7 @@ impl Tendril {
             max_age: if is_mouse_tendril {
                 f32::INFINITY // Mouse tendrils never age out
             } else {
-                0.3 + fastrand::f32() * 0.2 // Shorter lifetime: 0.3-0.5s
+                0.3 + rng.f32() * 0.2 // Shorter lifetime: 0.3-0.5s
             },
             edge_segment,
             is_mouse_tendril,
@@ -111,7 +114,9 @@ impl Tendril {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn generate_tendril(
+        rng: &mut Rng,
         segments: &mut Vec<TendrilSegment>,
         x: f32,
         y: f32,
@@ -156,8 +161,8 @@ impl Tendril {
 
         // Add control points for smooth random curves
         let mid_t = 0.5;
-        let control_offset_x = (fastrand::f32() - 0.5) * dist * 0.3;
-        let control_offset_y = (fastrand::f32() - 0.5) * dist * 0.3;
+        let control_offset_x = (rng.f32() - 0.5) * dist * 0.3;
+        let control_offset_y = (rng.f32() - 0.5) * dist * 0.3;
         let control_x = x + dx * mid_t + control_offset_x;
         let control_y = y + dy * mid_t + control_offset_y;
 
@@ -170,8 +175,8 @@ impl Tendril {
             let next_y = inv_t * inv_t * y + 2.0 * inv_t * t * control_y + t * t * target_y;
 
             // Add small noise for organic feel
-            let noise_x = (fastrand::f32() - 0.5) * 2.0;
-            let noise_y = (fastrand::f32() - 0.5) * 2.0;
+            let noise_x = (rng.f32() - 0.5) * 2.0;
+            let noise_y = (rng.f32() - 0.5) * 2.0;
             let next_x = next_x + noise_x;
             let next_y = next_y + noise_y;
 
@@ -203,14 +208,15 @@ impl Tendril {
             });
 
             // Branching
-            if fastrand::f32() < 0.06 && generation < 2 && t > 0.3 && t < 0.7 {
-                let branch_angle = if fastrand::bool() { 0.6 } else { -0.6 };
+            if rng.f32() < 0.06 && generation < 2 && t > 0.3 && t < 0.7 {
+                let branch_angle = if rng.bool() { 0.6 } else { -0.6 };
                 let angle = dy.atan2(dx);
-                let branch_dist = dist * (0.4 + fastrand::f32() * 0.3);
+                let branch_dist = dist * (0.4 + rng.f32() * 0.3);
                 let branch_target_x = next_x + (angle + branch_angle).cos() * branch_dist;
                 let branch_target_y = next_y + (angle + branch_angle).sin() * branch_dist;
 
                 Self::generate_tendril(
+                    rng,
                     segments,
                     next_x,
                     next_y,
@@ -275,8 +281,10 @@ impl Tendril {
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn update_target(
         &mut self,
+        rng: &mut Rng,
         new_target_x: f32,
         new_target_y: f32,
         center_x: f32,
@@ -300,6 +308,7 @@ impl Tendril {
         // Regenerate segments
         self.segments.clear();
         Self::generate_tendril(
+            rng,
             &mut self.segments,
             start_x,
             start_y,
@@ -323,15 +332,32 @@ pub struct PlasmaEffect {
     center_y: f32,
     orb_radius: f32,
     tendrils: Vec<Tendril>,
+    tendril_count: usize, // Edge tendrils kept alive while the mouse is idle
+    quality: f32,
     mouse_x: Option<f32>,
     mouse_y: Option<f32>,
     mouse_inactive_time: f32,
     time: f32,
-    output_buf: Vec<u8>,
+    rng: Rng,
 }
 
+pub const INFO: EffectInfo = EffectInfo {
+    name: "plasma",
+    aliases: &[],
+    description: "Interactive plasma globe with mouse",
+    controls: &[("mouse", "a tendril follows the pointer")],
+    params: &[
+        ParamInfo {
+            name: "tendrils",
+            description: "Number of tendrils when the mouse is idle",
+            kind: ParamKind::Int { min: 0, max: 100 },
+        },
+    ],
+    create: super::construct::<PlasmaEffect>,
+};
+
 impl Effect for PlasmaEffect {
-    fn new(width: usize, height: usize) -> Self {
+    fn new(width: usize, height: usize, rng: Rng) -> Self {
         let center_x = width as f32 / 2.0;
         let center_y = height as f32 / 2.0;
         let orb_radius = 5.0;
@@ -343,11 +369,13 @@ impl Effect for PlasmaEffect {
             center_y,
             orb_radius,
             tendrils: Vec::new(),
+            tendril_count: 30,
+            quality: 1.0,
             mouse_x: None,
             mouse_y: None,
             mouse_inactive_time: 0.0,
             time: 0.0,
-            output_buf: Vec::with_capacity(width * height * 25),
+            rng,
         }
     }
 
@@ -376,6 +404,7 @@ impl Effect for PlasmaEffect {
             let has_mouse_tendril = self.tendrils.iter().any(|t| t.is_mouse_tendril);
             if !has_mouse_tendril {
                 self.tendrils.push(Tendril::new(
+                    &mut self.rng,
                     self.center_x,
                     self.center_y,
                     self.orb_radius,
@@ -388,12 +417,15 @@ impl Effect for PlasmaEffect {
                 ));
             }
         } else {
-            // When no mouse: Maintain 30 edge tendrils
+            // When no mouse: Maintain the configured number of edge tendrils
             // Remove any mouse tendrils
             self.tendrils.retain(|t| !t.is_mouse_tendril);
+            // Down to half as many at the lowest quality
+            let target = (self.tendril_count as f32 * (0.5 + self.quality * 0.5)).round() as usize;
+            self.tendrils.truncate(target);
 
-            // Spawn edge tendrils to maintain 30
-            while self.tendrils.len() < 30 {
+            // Spawn edge tendrils to maintain the count
+            while self.tendrils.len() < target {
                 // Count tendrils per segment
                 let mut segment_counts = [0u32; 12];
                 for tendril in &self.tendrils {
@@ -411,6 +443,7 @@ impl Effect for PlasmaEffect {
                 }
 
                 self.tendrils.push(Tendril::new(
+                    &mut self.rng,
                     self.center_x,
                     self.center_y,
                     self.orb_radius,
@@ -431,15 +464,14 @@ impl Effect for PlasmaEffect {
         });
     }
 
-    fn render(&mut self, stdout: &mut BufWriter<Stdout>) -> std::io::Result<()> {
-        self.output_buf.clear();
-        self.output_buf.extend_from_slice(b"\x1b[H");
-
+    fn render(&mut self, canvas: &mut Canvas) {
         // Color pulsing effect - oscillates between more white and more pink
         let pulse = (self.time * 0.8).sin() * 0.5 + 0.5; // Oscillates between 0.0 and 1.0
         let bg_color = crate::get_bg_color();
 
         let mut glow_buffer = vec![(0.0f32, bg_color); self.width * self.height];
+        // Outer 5x5 glow only at full quality
+        let glow_radius: i32 = if self.quality > 0.75 { 2 } else { 1 };
 
         // LAYER 1: Draw pinkish blur around core FIRST (background layer)
         let pink = (255, 100, 150);
@@ -509,9 +541,7 @@ impl Effect for PlasmaEffect {
                 // Apply rising motion to middle sections (not base or tip)
                 // Calculate rise for START of segment
                 let start_progress = dist_from_core / total_length;
-                let start_rise_factor = if start_progress < 0.2 {
-                    0.0
-                } else if start_progress > 0.8 {
+                let start_rise_factor = if !(0.2..=0.8).contains(&start_progress) {
                     0.0
                 } else {
                     let normalized = (start_progress - 0.2) / 0.6;
@@ -520,9 +550,7 @@ impl Effect for PlasmaEffect {
 
                 // Calculate rise for END of segment
                 let end_progress = (dist_from_core + seg_length) / total_length;
-                let end_rise_factor = if end_progress < 0.2 {
-                    0.0
-                } else if end_progress > 0.8 {
+                let end_rise_factor = if !(0.2..=0.8).contains(&end_progress) {
                     0.0
                 } else {
                     let normalized = (end_progress - 0.2) / 0.6;
@@ -601,8 +629,8 @@ impl Effect for PlasmaEffect {
                     }
 
                     // Outer glow layer (5x5, very subtle)
-                    for dy_offset in -2..=2 {
-                        for dx_offset in -2..=2 {
+                    for dy_offset in -glow_radius..=glow_radius {
+                        for dx_offset in -glow_radius..=glow_radius {
                             if dy_offset == 0 && dx_offset == 0 {
                                 continue; // Skip center (already drawn)
                             }
@@ -646,7 +674,7 @@ impl Effect for PlasmaEffect {
                         // Calculate progress within the delta zone (0.0 at start, 1.0 at edge)
                         let delta_progress = (end_progress - 0.70) / 0.30;
                         // Spread grows gradually from 1 to 7 pixels as we move through delta zone
-                        let spread_radius = (1.0 + delta_progress * 6.0) as i32;
+                        let spread_radius = (1.0 + delta_progress * 6.0 * self.quality) as i32;
 
                         // Bright pink for delta
                         let pink = (255, 100, 150);
@@ -756,18 +784,14 @@ impl Effect for PlasmaEffect {
                 let start_progress = dist_from_core / total_length;
                 let end_progress = (dist_from_core + seg_length) / total_length;
 
-                let start_rise_factor = if start_progress < 0.2 {
-                    0.0
-                } else if start_progress > 0.8 {
+                let start_rise_factor = if !(0.2..=0.8).contains(&start_progress) {
                     0.0
                 } else {
                     let normalized = (start_progress - 0.2) / 0.6;
                     (normalized * std::f32::consts::PI).sin()
                 };
 
-                let end_rise_factor = if end_progress < 0.2 {
-                    0.0
-                } else if end_progress > 0.8 {
+                let end_rise_factor = if !(0.2..=0.8).contains(&end_progress) {
                     0.0
                 } else {
                     let normalized = (end_progress - 0.2) / 0.6;
@@ -831,8 +855,8 @@ impl Effect for PlasmaEffect {
                     }
 
                     // Add glow around tendril using additive blending
-                    for dy_offset in -2..=2 {
-                        for dx_offset in -2..=2 {
+                    for dy_offset in -glow_radius..=glow_radius {
+                        for dx_offset in -glow_radius..=glow_radius {
                             if dy_offset == 0 && dx_offset == 0 {
                                 continue; // Skip center
                             }
@@ -882,80 +906,87 @@ impl Effect for PlasmaEffect {
             }
         }
 
-        let mut prev_top_color: (u8, u8, u8) = (255, 255, 255);
-        let mut prev_bot_color: (u8, u8, u8) = (255, 255, 255);
-
-        // Render using half-blocks
-        for y in (0..self.height).step_by(2) {
+        // No need to check intensity threshold - we initialized with bg_color
+        // and all additive blending preserves proper colors
+        for y in 0..self.height {
             for x in 0..self.width {
-                let top_idx = y * self.width + x;
-                let bot_idx = if y + 1 < self.height {
-                    (y + 1) * self.width + x
-                } else {
-                    top_idx
-                };
-
-                let (_top_intensity, top_color) = glow_buffer[top_idx];
-                let (_bot_intensity, bot_color) = glow_buffer[bot_idx];
-
-                // No need to check intensity threshold - we initialized with bg_color
-                // and all additive blending preserves proper colors
-
-                if top_color != prev_top_color {
-                    write!(
-                        self.output_buf,
-                        "\x1b[48;2;{};{};{}m",
-                        top_color.0, top_color.1, top_color.2
-                    )?;
-                    prev_top_color = top_color;
-                }
-                if bot_color != prev_bot_color {
-                    write!(
-                        self.output_buf,
-                        "\x1b[38;2;{};{};{}m",
-                        bot_color.0, bot_color.1, bot_color.2
-                    )?;
-                    prev_bot_color = bot_color;
-                }
-
-                self.output_buf.extend_from_slice("▄".as_bytes());
-            }
-            self.output_buf.extend_from_slice(b"\x1b[0m");
-            prev_top_color = (255, 255, 255);
-            prev_bot_color = (255, 255, 255);
-            if y + 2 < self.height {
-                self.output_buf.extend_from_slice(b"\r\n");
+                let (_intensity, color) = glow_buffer[y * self.width + x];
+                canvas.set(x, y, color);
             }
         }
+    }
 
-        stdout.write_all(&self.output_buf)?;
-        stdout.flush()?;
-        Ok(())
+    fn resize(&mut self, width: usize, height: usize) {
+        let sx = width as f32 / self.width as f32;
+        let sy = height as f32 / self.height as f32;
+        self.width = width;
+        self.height = height;
+        self.center_x = width as f32 / 2.0;
+        self.center_y = height as f32 / 2.0;
+        self.mouse_x = self.mouse_x.map(|x| x * sx);
+        self.mouse_y = self.mouse_y.map(|y| y * sy);
+
+        // Re-grow each tendril from the new center toward its moved target
+        for tendril in &mut self.tendrils {
+            let age = tendril.age;
+            tendril.update_target(
+                &mut self.rng,
+                tendril.target_x * sx,
+                tendril.target_y * sy,
+                self.center_x,
+                self.center_y,
+                self.orb_radius,
+                width,
+                height,
+            );
+            tendril.age = age;
+        }
     }
 
     fn handle_event(&mut self, event: &Event) {
-        if let Event::Mouse(MouseEvent { kind, column, row, .. }) = event {
-            if let MouseEventKind::Moved = kind {
-                let new_mouse_x = *column as f32;
-                let new_mouse_y = *row as f32 * 2.0;
-
-                self.mouse_x = Some(new_mouse_x);
-                self.mouse_y = Some(new_mouse_y);
-                self.mouse_inactive_time = 0.0;
-
-                // Update existing mouse tendril target if it exists
-                if let Some(mouse_tendril) = self.tendrils.iter_mut().find(|t| t.is_mouse_tendril) {
-                    mouse_tendril.update_target(
-                        new_mouse_x,
-                        new_mouse_y,
-                        self.center_x,
-                        self.center_y,
-                        self.orb_radius,
-                        self.width,
-                        self.height,
-                    );
-                }
+        if let Event::Mouse(MouseEvent { kind, column, row, .. }) = event
+            && let MouseEventKind::Moved = kind
+        {
+            let (x, y) = super::cell_to_pixel(*column, *row);
+            let new_mouse_x = x as f32;
+            let new_mouse_y = y as f32;
+
+            self.mouse_x = Some(new_mouse_x);
+            self.mouse_y = Some(new_mouse_y);
+            self.mouse_inactive_time = 0.0;
+
+            // Update existing mouse tendril target if it exists
+            if let Some(mouse_tendril) = self.tendrils.iter_mut().find(|t| t.is_mouse_tendril) {
+                mouse_tendril.update_target(
+                    &mut self.rng,
+                    new_mouse_x,
+                    new_mouse_y,
+                    self.center_x,
+                    self.center_y,
+                    self.orb_radius,
+                    self.width,
+                    self.height,
+                );
             }
         }
     }
+
+    fn set_quality(&mut self, quality: f32) {
+        self.quality = quality;
+    }
+
+    fn param(&self, name: &str) -> Option<ParamValue> {
+        Some(match name {
+            "tendrils" => ParamValue::Int(self.tendril_count as i64),
+            _ => return None,
+        })
+    }
+
+    fn set_param(&mut self, name: &str, value: &ParamValue) -> Result<(), String> {
+        match name {
+            "tendrils" => self.tendril_count = value.as_int() as usize,
+            _ => return Err(format!("unknown parameter '{}'", name)),
+        }
+        Ok(())
+    }
 }
@@ -1,6 +1,6 @@
-use super::Effect;
+use super::{Canvas, Effect, EffectInfo};
 use crossterm::event::{Event, MouseEvent, MouseEventKind};
-use std::io::{BufWriter, Stdout, Write};
+use fastrand::Rng;
 
 // Custom fast noise - reusing from clouds
 struct FastNoise {
@@ -10,8 +10,8 @@ struct FastNoise {
 impl FastNoise {
     fn new(seed: u32) -> Self {
         let mut perm = [0u8; 512];
-        for i in 0..256 {
-            perm[i] = i as u8;
+        for (i, p) in perm.iter_mut().take(256).enumerate() {
+            *p = i as u8;
         }
 
         let mut rng_state = seed;
@@ -82,9 +82,9 @@ struct Star {
 }
 
 impl Star {
-    fn random() -> Self {
+    fn random(rng: &mut Rng) -> Self {
         // Weight toward main sequence stars (most common in universe)
-        let luminosity_class = match fastrand::u8(0..100) {
+        let luminosity_class = match rng.u8(0..100) {
             0..=85 => LuminosityClass::MainSequence,
             86..=95 => LuminosityClass::Giant,
             _ => LuminosityClass::Supergiant,
@@ -95,30 +95,30 @@ impl Star {
             LuminosityClass::MainSequence => {
                 // Main sequence: 3000K (red dwarfs) to 30000K (blue stars)
                 // Weight toward cooler stars (more common)
-                let roll = fastrand::f32();
+                let roll = rng.f32();
                 if roll < 0.6 {
                     // Red/orange dwarfs (most common)
-                    3000.0 + fastrand::f32() * 2500.0  // 3000-5500K
+                    3000.0 + rng.f32() * 2500.0  // 3000-5500K
                 } else if roll < 0.85 {
                     // Yellow/white stars
-                    5500.0 + fastrand::f32() * 3000.0  // 5500-8500K
+                    5500.0 + rng.f32() * 3000.0  // 5500-8500K
                 } else {
                     // Hot blue stars (rare)
-                    8500.0 + fastrand::f32() * 21500.0  // 8500-30000K
+                    8500.0 + rng.f32() * 21500.0  // 8500-30000K
                 }
             }
             LuminosityClass::Giant => {
                 // Giants are usually cooler (red/orange giants)
-                3500.0 + fastrand::f32() * 2500.0  // 3500-6000K
+                3500.0 + rng.f32() * 2500.0  // 3500-6000K
             }
             LuminosityClass::Supergiant => {
                 // Supergiants can be red OR blue
-                if fastrand::bool() {
+                if rng.bool() {
                     // Red supergiant
-                    3200.0 + fastrand::f32() * 1500.0  // 3200-4700K
+                    3200.0 + rng.f32() * 1500.0  // 3200-4700K
                 } else {
                     // Blue supergiant
-                    10000.0 + fastrand::f32() * 20000.0  // 10000-30000K
+                    10000.0 + rng.f32() * 20000.0  // 10000-30000K
                 }
             }
         };
@@ -204,6 +204,7 @@ impl Star {
         }
     }
 
+    #[allow(clippy::excessive_precision)] // Published blackbody fit coefficients
     fn temperature_to_color(temp: f32) -> (u8, u8, u8) {
         // Simplified blackbody color approximation
         let temp_clamped = temp.clamp(1000.0, 40000.0);
@@ -275,19 +276,29 @@ pub struct StarEffect {
     noise3: FastNoise,
     flares: Vec<Flare>,
     last_click_time: f32,  // Cooldown to prevent double-clicks
-    output_buf: Vec<u8>,
+    quality: f32,
+    rng: Rng,
 }
 
+pub const INFO: EffectInfo = EffectInfo {
+    name: "star",
+    aliases: &["sun"],
+    description: "Realistic star with accurate stellar classification and physics",
+    controls: &[("click", "launch a flare toward the pointer")],
+    params: &[],
+    create: super::construct::<StarEffect>,
+};
+
 impl Effect for StarEffect {
-    fn new(width: usize, height: usize) -> Self {
-        let star = Star::random();
+    fn new(width: usize, height: usize, mut rng: Rng) -> Self {
+        let star = Star::random(&mut rng);
         let props = star.properties();
 
         // Generate star name
-        let star_name = Self::generate_star_name();
+        let star_name = Self::generate_star_name(&mut rng);
 
         // Calculate physical properties based on temperature and luminosity class
-        let (mass, radius, luminosity) = Self::calculate_star_stats(&star);
+        let (mass, radius, luminosity) = Self::calculate_star_stats(&mut rng, &star);
 
         Self {
             width,
@@ -300,12 +311,13 @@ impl Effect for StarEffect {
             radius,
             luminosity,
             rotation: 0.0,
-            noise1: FastNoise::new(fastrand::u32(..)),
-            noise2: FastNoise::new(fastrand::u32(..)),
-            noise3: FastNoise::new(fastrand::u32(..)),
+            noise1: FastNoise::new(rng.u32(..)),
+            noise2: FastNoise::new(rng.u32(..)),
+            noise3: FastNoise::new(rng.u32(..)),
             flares: Vec::new(),
             last_click_time: 0.0,
-            output_buf: Vec::with_capacity(width * height * 25),
+            quality: 1.0,
+            rng,
         }
     }
 
@@ -324,8 +336,8 @@ impl Effect for StarEffect {
         let props = &self.props;
 
         // Spawn flares (solar prominences)
-        if fastrand::f32() < props.flare_chance * dt {
-            let angle = fastrand::f32() * std::f32::consts::PI * 2.0;
+        if self.rng.f32() < props.flare_chance * dt {
+            let angle = self.rng.f32() * std::f32::consts::PI * 2.0;
 
             // Red dwarf superflares are more dramatic
             let is_red_dwarf = self._star.temperature < 4000.0;
@@ -343,28 +355,28 @@ impl Effect for StarEffect {
             };
 
             // Add significant randomization to each flare
-            let size_variation = 0.6 + fastrand::f32() * 0.8; // 0.6x to 1.4x size
-            let brightness_variation = 0.7 + fastrand::f32() * 0.6; // 0.7x to 1.3x brightness
-            let lifetime_variation = 0.7 + fastrand::f32() * 0.6; // 0.7x to 1.3x lifetime
+            let size_variation = 0.6 + self.rng.f32() * 0.8; // 0.6x to 1.4x size
+            let brightness_variation = 0.7 + self.rng.f32() * 0.6; // 0.7x to 1.3x brightness
+            let lifetime_variation = 0.7 + self.rng.f32() * 0.6; // 0.7x to 1.3x lifetime
 
             let randomized_max_height = max_height * size_variation;
-            let base_intensity = (intensity_range.0 + fastrand::f32() * (intensity_range.1 - intensity_range.0)) * brightness_variation;
-            let randomized_lifetime = (lifetime_range.0 + fastrand::f32() * (lifetime_range.1 - lifetime_range.0)) * lifetime_variation;
+            let base_intensity = (intensity_range.0 + self.rng.f32() * (intensity_range.1 - intensity_range.0)) * brightness_variation;
+            let randomized_lifetime = (lifetime_range.0 + self.rng.f32() * (lifetime_range.1 - lifetime_range.0)) * lifetime_variation;
 
-            let base_arc_width = (0.15 + fastrand::f32() * max_width) * size_variation;
+            let base_arc_width = (0.15 + self.rng.f32() * max_width) * size_variation;
             self.flares.push(Flare {
                 angle,
                 height: 0.0,
                 max_height: randomized_max_height,
                 arc_width: base_arc_width,
                 base_arc_width,
-                thickness: (0.08 + fastrand::f32() * 0.08) * size_variation,
+                thickness: (0.08 + self.rng.f32() * 0.08) * size_variation,
                 radial_offset: 0.0,
                 intensity: 0.0,
                 base_intensity,
                 lifetime: 0.0,
                 max_lifetime: randomized_lifetime,
-                noise_offset: fastrand::f32() * 1000.0,
+                noise_offset: self.rng.f32() * 1000.0,
             });
         }
 
@@ -403,10 +415,7 @@ impl Effect for StarEffect {
         });
     }
 
-    fn render(&mut self, stdout: &mut BufWriter<Stdout>) -> std::io::Result<()> {
-        self.output_buf.clear();
-        self.output_buf.extend_from_slice(b"\x1b[H");
-
+    fn render(&mut self, canvas: &mut Canvas) {
         let bg_color = crate::get_bg_color();
         let space_color = if bg_color == (0, 0, 0) {
             (5, 5, 15)
@@ -423,9 +432,10 @@ impl Effect for StarEffect {
 
         let mut frame_buffer = vec![(space_color.0 as f32, space_color.1 as f32, space_color.2 as f32); self.width * self.height];
 
-        // Render star
-        for y in 0..self.height {
-            for x in 0..self.width {
+        // Render star, one sample per block of pixels at reduced quality
+        let block = (1.0 / self.quality).round().max(1.0) as usize;
+        for y in (0..self.height).step_by(block) {
+            for x in (0..self.width).step_by(block) {
                 let dx = x as f32 - center_x;
                 let dy = y as f32 - center_y;
                 let dist = (dx * dx + dy * dy).sqrt();
@@ -450,10 +460,15 @@ impl Effect for StarEffect {
                         surface_x as f64 * props.granulation_scale as f64 * 3.0,
                         surface_y as f64 * props.granulation_scale as f64 * 3.0 + self.time as f64 * 0.45,
                     );
-                    let gran3 = self.noise3.get(
-                        surface_x as f64 * props.granulation_scale as f64 * 8.0,
-                        surface_y as f64 * props.granulation_scale as f64 * 8.0 + self.time as f64 * 0.6,
-                    );
+                    // The finest octave is the first detail to go when frames are slow
+                    let gran3 = if self.quality > 0.75 {
+                        self.noise3.get(
+                            surface_x as f64 * props.granulation_scale as f64 * 8.0,
+                            surface_y as f64 * props.granulation_scale as f64 * 8.0 + self.time as f64 * 0.6,
+                        )
+                    } else {
+                        0.0
+                    };
 
                     // Much more visible and varied granulation
                     let granulation = gran1 * 0.6 + gran2 * 0.4 + gran3 * 0.3;
@@ -483,7 +498,7 @@ impl Effect for StarEffect {
                             let corona_sample_dist = 1.05; // Just into corona
                             let corona_noise = self.noise1.get(
                                 angle as f64 * 25.0,
-                                corona_sample_dist as f64 * 2.0 + self.time as f64 * 0.4,
+                                corona_sample_dist * 2.0 + self.time as f64 * 0.4,
                             ) * 0.5 + 0.5;
                             let corona_intensity = 0.6 * corona_noise;
 
@@ -535,6 +550,15 @@ impl Effect for StarEffect {
                         }
                     }
                 }
+
+                if block > 1 {
+                    let sample = frame_buffer[y * self.width + x];
+                    for py in y..(y + block).min(self.height) {
+                        for px in x..(x + block).min(self.width) {
+                            frame_buffer[py * self.width + px] = sample;
+                        }
+                    }
+                }
             }
         }
 
@@ -543,133 +567,78 @@ impl Effect for StarEffect {
 
         // Render flares/prominences
         for flare in &self.flares {
-            self.render_flare(&mut frame_buffer, center_x, center_y, base_radius, flare, &props);
+            self.render_flare(&mut frame_buffer, center_x, center_y, base_radius, flare, props);
         }
 
-        // Convert to output
-        let mut prev_top_color: (u8, u8, u8) = (255, 255, 255);
-        let mut prev_bot_color: (u8, u8, u8) = (255, 255, 255);
-
-        for y in (0..self.height).step_by(2) {
-            for x in 0..self.width {
-                let top_idx = y * self.width + x;
-                let bot_idx = if y + 1 < self.height {
-                    (y + 1) * self.width + x
-                } else {
-                    top_idx
-                };
-
-                let top_color = (
-                    frame_buffer[top_idx].0.round().clamp(0.0, 255.0) as u8,
-                    frame_buffer[top_idx].1.round().clamp(0.0, 255.0) as u8,
-                    frame_buffer[top_idx].2.round().clamp(0.0, 255.0) as u8,
-                );
-                let bot_color = (
-                    frame_buffer[bot_idx].0.round().clamp(0.0, 255.0) as u8,
-                    frame_buffer[bot_idx].1.round().clamp(0.0, 255.0) as u8,
-                    frame_buffer[bot_idx].2.round().clamp(0.0, 255.0) as u8,
-                );
-
-                if top_color != prev_top_color {
-                    write!(
-                        self.output_buf,
-                        "\x1b[48;2;{};{};{}m",
-                        top_color.0, top_color.1, top_color.2
-                    )?;
-                    prev_top_color = top_color;
-                }
-                if bot_color != prev_bot_color {
-                    write!(
-                        self.output_buf,
-                        "\x1b[38;2;{};{};{}m",
-                        bot_color.0, bot_color.1, bot_color.2
-                    )?;
-                    prev_bot_color = bot_color;
-                }
-
-                self.output_buf.extend_from_slice("▄".as_bytes());
-            }
-            self.output_buf.extend_from_slice(b"\x1b[0m");
-            prev_top_color = (255, 255, 255);
-            prev_bot_color = (255, 255, 255);
-            if y + 2 < self.height {
-                self.output_buf.extend_from_slice(b"\r\n");
-            }
-        }
+        canvas.copy_from_f32(&frame_buffer, self.width);
 
         // Add star info overlay in top left
-        self.output_buf.extend_from_slice(b"\x1b[1;2H"); // Position at row 1, col 2
-        self.output_buf.extend_from_slice(b"\x1b[38;2;255;255;255m"); // White text
-        self.output_buf.extend_from_slice(b"\x1b[1m"); // Bold
-        write!(self.output_buf, "{}", self.star_name)?;
+        let white = (255, 255, 255);
+        let dim = (200, 200, 200);
+        canvas.put_text(1, 0, &self.star_name, white, None, true);
+        canvas.put_text(1, 1, &format!("Mass: {:.2} M☉", self.mass), dim, None, false);
+        canvas.put_text(1, 2, &format!("Radius: {:.1} R☉", self.radius), dim, None, false);
+        canvas.put_text(1, 3, &format!("Luminosity: {:.1} L☉", self.luminosity), dim, None, false);
+        canvas.put_text(1, 4, &format!("Temp: {:.0} K", self._star.temperature), dim, None, false);
+    }
 
-        self.output_buf.extend_from_slice(b"\x1b[2;2H"); // Row 2
-        self.output_buf.extend_from_slice(b"\x1b[0m\x1b[38;2;200;200;200m"); // Dimmer white
-        write!(self.output_buf, "Mass: {:.2} M☉", self.mass)?;
+    fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+    }
 
-        self.output_buf.extend_from_slice(b"\x1b[3;2H"); // Row 3
-        write!(self.output_buf, "Radius: {:.1} R☉", self.radius)?;
+    fn set_quality(&mut self, quality: f32) {
+        self.quality = quality;
+    }
 
-        self.output_buf.extend_from_slice(b"\x1b[4;2H"); // Row 4
-        write!(self.output_buf, "Luminosity: {:.1} L☉", self.luminosity)?;
+    fn handle_event(&mut self, event: &Event) {
+        if let Event::Mouse(MouseEvent { kind, column, row, .. }) = event
+            && matches!(kind, MouseEventKind::Down(_))
+        {
+            // Cooldown to prevent double-clicks (200ms)
+            if self.time - self.last_click_time < 0.2 {
+                return;
+            }
 
-        self.output_buf.extend_from_slice(b"\x1b[5;2H"); // Row 5
-        write!(self.output_buf, "Temp: {:.0} K", self._star.temperature)?;
+            // Limit total flares for performance
+            if self.flares.len() >= 8 {
+                return;
+            }
 
-        self.output_buf.extend_from_slice(b"\x1b[0m"); // Reset formatting
+            self.last_click_time = self.time;
 
-        stdout.write_all(&self.output_buf)?;
-        stdout.flush()?;
-        Ok(())
-    }
+            // Calculate star center
+            let center_x = self.width as f32 / 2.0;
+            let center_y = self.height as f32 / 2.0;
 
-    fn handle_event(&mut self, event: &Event) {
-        if let Event::Mouse(MouseEvent { kind, column, row, .. }) = event {
-            if matches!(kind, MouseEventKind::Down(_)) {
-                // Cooldown to prevent double-clicks (200ms)
-                if self.time - self.last_click_time < 0.2 {
-                    return;
-                }
+            // Mouse click position in pixels
+            let (x, y) = super::cell_to_pixel(*column, *row);
+            let target_x = x as f32;
+            let target_y = y as f32;
 
-                // Limit total flares for performance
-                if self.flares.len() >= 8 {
-                    return;
-                }
+            // Calculate angle toward click
+            let dx = target_x - center_x;
+            let dy = target_y - center_y;
+            let angle = dy.atan2(dx);
 
-                self.last_click_time = self.time;
-
-                // Calculate star center
-                let center_x = self.width as f32 / 2.0;
-                let center_y = self.height as f32 / 2.0;
-
-                // Mouse click position (accounting for half-block rendering)
-                let target_x = *column as f32;
-                let target_y = *row as f32 * 2.0; // Double because of half-blocks
-
-                // Calculate angle toward click
-                let dx = target_x - center_x;
-                let dy = target_y - center_y;
-                let angle = dy.atan2(dx);
-
-                // Spawn single dramatic prominence flare toward cursor
-                let size_variation = 1.2 + fastrand::f32() * 0.3; // 1.2x to 1.5x
-                let base_arc_width = (0.25 + fastrand::f32() * 0.15) * size_variation;
-
-                self.flares.push(Flare {
-                    angle,
-                    height: 0.0,
-                    max_height: 0.8 * size_variation, // Very tall and dramatic
-                    arc_width: base_arc_width,
-                    base_arc_width,
-                    thickness: (0.12 + fastrand::f32() * 0.05) * size_variation,
-                    radial_offset: 0.0,
-                    intensity: 0.0,
-                    base_intensity: 1.8 + fastrand::f32() * 0.4, // Very bright
-                    lifetime: 0.0,
-                    max_lifetime: 3.0 + fastrand::f32() * 1.5, // 3-4.5 seconds for better performance
-                    noise_offset: fastrand::f32() * 1000.0,
-                });
-            }
+            // Spawn single dramatic prominence flare toward cursor
+            let size_variation = 1.2 + self.rng.f32() * 0.3; // 1.2x to 1.5x
+            let base_arc_width = (0.25 + self.rng.f32() * 0.15) * size_variation;
+
+            self.flares.push(Flare {
+                angle,
+                height: 0.0,
+                max_height: 0.8 * size_variation, // Very tall and dramatic
+                arc_width: base_arc_width,
+                base_arc_width,
+                thickness: (0.12 + self.rng.f32() * 0.05) * size_variation,
+                radial_offset: 0.0,
+                intensity: 0.0,
+                base_intensity: 1.8 + self.rng.f32() * 0.4, // Very bright
+                lifetime: 0.0,
+                max_lifetime: 3.0 + self.rng.f32() * 1.5, // 3-4.5 seconds for better performance
+                noise_offset: self.rng.f32() * 1000.0,
+            });
         }
     }
 }
@@ -689,7 +658,7 @@ impl StarEffect {
         let right_base_y = center_y + base_angle_right.sin() * radius;
 
         // Sample many points along the horseshoe arc
-        let num_segments = 40;  // Reduced for better performance
+        let num_segments = ((40.0 * self.quality) as usize).max(12);  // Reduced for better performance
 
         for i in 0..=num_segments {
             let t = i as f32 / num_segments as f32;
@@ -828,7 +797,7 @@ impl StarEffect {
         }
     }
 
-    fn generate_star_name() -> String {
+    fn generate_star_name(rng: &mut Rng) -> String {
         // Generate procedural random names from syllables
         const CONSONANTS: &[&str] = &[
             "b", "c", "d", "f", "g", "h", "j", "k", "l", "m",
@@ -848,7 +817,7 @@ impl StarEffect {
         let mut name = String::new();
 
         // Bias towards shorter names: 2 syllables most common, sometimes 3
-        let num_syllables = if fastrand::f32() < 0.7 {
+        let num_syllables = if rng.f32() < 0.7 {
             2
         } else {
             3
@@ -856,20 +825,20 @@ impl StarEffect {
 
         for i in 0..num_syllables {
             // Start with consonant (or sometimes vowel for variety)
-            if i == 0 || fastrand::f32() > 0.3 {
-                name.push_str(CONSONANTS[fastrand::usize(..CONSONANTS.len())]);
+            if i == 0 || rng.f32() > 0.3 {
+                name.push_str(CONSONANTS[rng.usize(..CONSONANTS.len())]);
             }
-            name.push_str(VOWELS[fastrand::usize(..VOWELS.len())]);
+            name.push_str(VOWELS[rng.usize(..VOWELS.len())]);
 
             // Sometimes add consonant after vowel (not at end)
-            if i < num_syllables - 1 && fastrand::f32() > 0.6 {
-                name.push_str(CONSONANTS[fastrand::usize(..CONSONANTS.len())]);
+            if i < num_syllables - 1 && rng.f32() > 0.6 {
+                name.push_str(CONSONANTS[rng.usize(..CONSONANTS.len())]);
             }
         }
 
         // Add ending suffix less often for shorter names
-        if fastrand::f32() > 0.7 {
-            name.push_str(ENDINGS[fastrand::usize(..ENDINGS.len())]);
+        if rng.f32() > 0.7 {
+            name.push_str(ENDINGS[rng.usize(..ENDINGS.len())]);
         }
 
         // Capitalize first letter
@@ -881,7 +850,7 @@ impl StarEffect {
         chars.into_iter().collect()
     }
 
-    fn calculate_star_stats(star: &Star) -> (f32, f32, f32) {
+    fn calculate_star_stats(rng: &mut Rng, star: &Star) -> (f32, f32, f32) {
         // Calculate mass, radius, and luminosity based on temperature and class
         let (mass, radius, luminosity) = match star.luminosity_class {
             LuminosityClass::MainSequence => {
@@ -896,16 +865,16 @@ impl StarEffect {
             LuminosityClass::Giant => {
                 // Giants are evolved stars - larger radius, moderate mass
                 let temp_ratio = star.temperature / 4500.0;
-                let mass = (0.8 + fastrand::f32() * 2.0).clamp(0.8, 8.0);
-                let radius = (10.0 + fastrand::f32() * 40.0).clamp(10.0, 100.0);
+                let mass = (0.8 + rng.f32() * 2.0).clamp(0.8, 8.0);
+                let radius = (10.0 + rng.f32() * 40.0).clamp(10.0, 100.0);
                 let luminosity = (radius * radius * temp_ratio.powf(4.0)).clamp(10.0, 1000.0);
                 (mass, radius, luminosity)
             }
             LuminosityClass::Supergiant => {
                 // Supergiants - massive and extremely luminous
                 let temp_ratio = star.temperature / 4000.0;
-                let mass = (10.0 + fastrand::f32() * 30.0).clamp(10.0, 50.0);
-                let radius = (100.0 + fastrand::f32() * 400.0).clamp(100.0, 1000.0);
+                let mass = (10.0 + rng.f32() * 30.0).clamp(10.0, 50.0);
+                let radius = (100.0 + rng.f32() * 400.0).clamp(100.0, 1000.0);
                 let luminosity = (radius * radius * temp_ratio.powf(4.0)).clamp(1000.0, 100000.0);
                 (mass, radius, luminosity)
             }
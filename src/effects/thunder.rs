@@ -1,5 +1,5 @@
-use super::Effect;
-use std::io::{BufWriter, Stdout, Write};
+use super::{Canvas, Effect, EffectInfo};
+use fastrand::Rng;
 
 const GLOW_COLORS: [(u8, u8, u8); 5] = [
     (200, 220, 255), // Bright core
@@ -46,16 +46,17 @@ impl LightningBolt {
         }
 
         // Between initial flash and first flicker, or between flickers - stay dark
-        if let Some(&first_flicker) = self.flickers.first() {
-            if self.age < first_flicker {
-                return 0.0; // Dark before first flicker
-            }
+        if let Some(&first_flicker) = self.flickers.first()
+            && self.age < first_flicker
+        {
+            return 0.0; // Dark before first flicker
         }
 
-        if self.flickers.len() > 1 {
-            if self.age > self.flickers[0] + FLICKER_DURATION && self.age < self.flickers[1] {
-                return 0.0; // Dark between flickers
-            }
+        if self.flickers.len() > 1
+            && self.age > self.flickers[0] + FLICKER_DURATION
+            && self.age < self.flickers[1]
+        {
+            return 0.0; // Dark between flickers
         }
 
         // After all flickers (or no flickers at all) - slow fade
@@ -73,15 +74,17 @@ impl LightningBolt {
         0.0
     }
 
-    fn new(start_x: f32, start_y: f32, width: usize, height: usize) -> Self {
+    fn new(rng: &mut Rng, start_x: f32, start_y: f32, width: usize, height: usize) -> Self {
         let mut segments = Vec::new();
 
         // Main trunk
+        let target_x = start_x + (rng.f32() - 0.5) * 10.0;
         Self::generate_branch(
+            rng,
             &mut segments,
             start_x,
             start_y,
-            start_x + (fastrand::f32() - 0.5) * 10.0,
+            target_x,
             height as f32,
             1.0,
             0,
@@ -91,25 +94,27 @@ impl LightningBolt {
 
         // Generate flicker times - some bolts flicker once or twice
         let mut flickers = Vec::new();
-        if fastrand::f32() < 0.4 {
+        if rng.f32() < 0.4 {
             // 40% chance of one flicker
-            flickers.push(0.15 + fastrand::f32() * 0.1);
-            if fastrand::f32() < 0.3 {
+            flickers.push(0.15 + rng.f32() * 0.1);
+            if rng.f32() < 0.3 {
                 // 30% of those get a second flicker
-                flickers.push(0.35 + fastrand::f32() * 0.15);
+                flickers.push(0.35 + rng.f32() * 0.15);
             }
         }
 
         Self {
             segments,
             age: 0.0,
-            lifetime: 0.4 + fastrand::f32() * 0.3, // Slower fade: 0.4-0.7 seconds
-            flash_intensity: 0.8 + fastrand::f32() * 0.2,
+            lifetime: 0.4 + rng.f32() * 0.3, // Slower fade: 0.4-0.7 seconds
+            flash_intensity: 0.8 + rng.f32() * 0.2,
             flickers,
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn generate_branch(
+        rng: &mut Rng,
         segments: &mut Vec<LightningSegment>,
         x: f32,
         y: f32,
@@ -155,8 +160,8 @@ impl LightningBolt {
         for i in 0..num_segments {
             let t = (i + 1) as f32 / num_segments as f32;
             // Realistic physics: strong downward bias, less horizontal drift
-            let horizontal_drift = (fastrand::f32() - 0.5) * 8.0;
-            let vertical_bias = fastrand::f32() * 3.0; // Extra downward push
+            let horizontal_drift = (rng.f32() - 0.5) * 8.0;
+            let vertical_bias = rng.f32() * 3.0; // Extra downward push
             let next_x = x + dx * t + horizontal_drift;
             let next_y = y + dy * t + vertical_bias;
 
@@ -174,18 +179,19 @@ impl LightningBolt {
             });
 
             // Chance to spawn branches (reduced for narrower lightning)
-            if fastrand::f32() < 0.12 && generation < 3 {
+            if rng.f32() < 0.12 && generation < 3 {
                 // Realistic branching: prefer angles between 20-60 degrees, biased downward
-                let side = if fastrand::bool() { 1.0 } else { -1.0 };
-                let branch_angle = side * (0.3 + fastrand::f32() * 0.5); // 0.3-0.8 radians (~17-46 degrees)
+                let side = if rng.bool() { 1.0 } else { -1.0 };
+                let branch_angle = side * (0.3 + rng.f32() * 0.5); // 0.3-0.8 radians (~17-46 degrees)
 
-                let branch_length = dist * (0.3 + fastrand::f32() * 0.4);
+                let branch_length = dist * (0.3 + rng.f32() * 0.4);
                 let angle = dy.atan2(dx);
                 let branch_target_x = next_x + (angle + branch_angle).cos() * branch_length;
                 // Add extra downward bias to branch targets
                 let branch_target_y = next_y + (angle + branch_angle).sin() * branch_length + branch_length * 0.3;
 
                 Self::generate_branch(
+                    rng,
                     segments,
                     next_x,
                     next_y,
@@ -210,20 +216,31 @@ pub struct ThunderEffect {
     bolts: Vec<LightningBolt>,
     time: f32,
     next_strike_time: f32,
-    output_buf: Vec<u8>,
     ambient_flash: f32,
+    glow_radius: i32, // Cells lit around each point of a bolt; smaller at reduced quality
+    rng: Rng,
 }
 
+pub const INFO: EffectInfo = EffectInfo {
+    name: "thunder",
+    aliases: &["lightning"],
+    description: "Realistic branching lightning",
+    controls: &[],
+    params: &[],
+    create: super::construct::<ThunderEffect>,
+};
+
 impl Effect for ThunderEffect {
-    fn new(width: usize, height: usize) -> Self {
+    fn new(width: usize, height: usize, mut rng: Rng) -> Self {
         Self {
             width,
             height,
             bolts: Vec::new(),
             time: 0.0,
-            next_strike_time: 0.3 + fastrand::f32() * 1.0,
-            output_buf: Vec::with_capacity(width * height * 25),
+            next_strike_time: 0.3 + rng.f32() * 1.0,
             ambient_flash: 0.0,
+            glow_radius: 3,
+            rng,
         }
     }
 
@@ -237,25 +254,25 @@ impl Effect for ThunderEffect {
 
         // Spawn new lightning bolts
         if self.time >= self.next_strike_time {
-            let x = fastrand::usize(10..self.width - 10) as f32;
+            let x = self.rng.usize(10..self.width - 10) as f32;
             let y = 0.0;
 
-            self.bolts.push(LightningBolt::new(x, y, self.width, self.height));
+            self.bolts.push(LightningBolt::new(&mut self.rng, x, y, self.width, self.height));
 
             // Sometimes spawn multiple strikes in different locations
-            if fastrand::f32() < 0.25 {
+            if self.rng.f32() < 0.25 {
                 // Spawn at a completely different location
-                let x2 = fastrand::usize(10..self.width - 10) as f32;
-                self.bolts.push(LightningBolt::new(x2, y, self.width, self.height));
+                let x2 = self.rng.usize(10..self.width - 10) as f32;
+                self.bolts.push(LightningBolt::new(&mut self.rng, x2, y, self.width, self.height));
 
                 // Rare triple strike
-                if fastrand::f32() < 0.15 {
-                    let x3 = fastrand::usize(10..self.width - 10) as f32;
-                    self.bolts.push(LightningBolt::new(x3, y, self.width, self.height));
+                if self.rng.f32() < 0.15 {
+                    let x3 = self.rng.usize(10..self.width - 10) as f32;
+                    self.bolts.push(LightningBolt::new(&mut self.rng, x3, y, self.width, self.height));
                 }
             }
 
-            self.next_strike_time = self.time + 0.5 + fastrand::f32() * 2.0;
+            self.next_strike_time = self.time + 0.5 + self.rng.f32() * 2.0;
         }
 
         // Update bolts and calculate ambient flash
@@ -275,10 +292,7 @@ impl Effect for ThunderEffect {
         });
     }
 
-    fn render(&mut self, stdout: &mut BufWriter<Stdout>) -> std::io::Result<()> {
-        self.output_buf.clear();
-        self.output_buf.extend_from_slice(b"\x1b[H"); // Move to home
-
+    fn render(&mut self, canvas: &mut Canvas) {
         // Create buffer for lightning glow
         let mut glow_buffer = vec![0.0f32; self.width * self.height];
 
@@ -303,11 +317,12 @@ impl Effect for ThunderEffect {
                 let mut y = y0;
 
                 let intensity = segment.intensity * fade;
+                let radius = self.glow_radius;
 
                 loop {
                     // Draw main bolt and glow
-                    for gy in (y - 3).max(0)..=(y + 3).min(self.height as i32 - 1) {
-                        for gx in (x - 3).max(0)..=(x + 3).min(self.width as i32 - 1) {
+                    for gy in (y - radius).max(0)..=(y + radius).min(self.height as i32 - 1) {
+                        for gx in (x - radius).max(0)..=(x + radius).min(self.width as i32 - 1) {
                             let dist = ((gx - x).pow(2) + (gy - y).pow(2)) as f32;
                             let glow = (intensity * 5.0 / (dist + 1.0)).min(5.0);
                             let idx = gy as usize * self.width + gx as usize;
@@ -345,48 +360,31 @@ impl Effect for ThunderEffect {
             base_bg
         };
 
-        let mut prev_color: (u8, u8, u8) = (255, 255, 255);
-
-        // Render using half-blocks
-        for y in (0..self.height).step_by(2) {
+        for y in 0..self.height {
             for x in 0..self.width {
-                let top_glow = glow_buffer[y * self.width + x];
-                let bot_glow = if y + 1 < self.height {
-                    glow_buffer[(y + 1) * self.width + x]
-                } else {
-                    0.0
-                };
-
-                let top_color = Self::glow_to_color(top_glow, bg);
-                let bot_color = Self::glow_to_color(bot_glow, bg);
-
-                // Only emit color codes if changed
-                if top_color != prev_color {
-                    write!(
-                        self.output_buf,
-                        "\x1b[48;2;{};{};{}m",
-                        top_color.0, top_color.1, top_color.2
-                    )?;
-                }
-                write!(
-                    self.output_buf,
-                    "\x1b[38;2;{};{};{}m",
-                    bot_color.0, bot_color.1, bot_color.2
-                )?;
-                prev_color = top_color;
-
-                self.output_buf.extend_from_slice("▄".as_bytes());
-            }
-            self.output_buf.extend_from_slice(b"\x1b[0m");
-            prev_color = (255, 255, 255);
-            if y + 2 < self.height {
-                self.output_buf.extend_from_slice(b"\r\n");
+                let glow = glow_buffer[y * self.width + x];
+                canvas.set(x, y, Self::glow_to_color(glow, bg));
             }
         }
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        // Stretch the live bolts so they still reach the ground
+        let sx = width as f32 / self.width as f32;
+        let sy = height as f32 / self.height as f32;
+        for segment in self.bolts.iter_mut().flat_map(|bolt| bolt.segments.iter_mut()) {
+            segment.x *= sx;
+            segment.y *= sy;
+            segment.end_x *= sx;
+            segment.end_y *= sy;
+        }
+        self.width = width;
+        self.height = height;
+    }
 
-        stdout.write_all(&self.output_buf)?;
-        stdout.flush()?;
-        Ok(())
+    fn set_quality(&mut self, quality: f32) {
+        // The outer glow is faint; the bolt itself keeps a one-cell halo
+        self.glow_radius = (quality * 3.0).round().clamp(1.0, 3.0) as i32;
     }
 }
 
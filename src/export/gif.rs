@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+const BUCKET_BITS: u32 = 5; // Precision per channel when building the palette
+const PALETTE_SIZE: usize = 256;
+const MIN_CODE_SIZE: u8 = 8;
+const MAX_CODES: u16 = 4096;
+
+/// Largest width or height a GIF can describe.
+pub const MAX_SIZE: usize = u16::MAX as usize;
+
+/// Writes an animated GIF that loops forever, one frame at a time.
+///
+/// Each frame gets its own 256-color palette chosen by median cut, so slow
+/// color shifts (aurora, lava lamp) keep their gradients.
+pub struct GifWriter<W: Write> {
+    out: W,
+    width: u16,
+    height: u16,
+    delay: u16, // Hundredths of a second per frame
+}
+
+impl<W: Write> GifWriter<W> {
+    /// Fails without writing anything if the image is larger than GIF allows.
+    pub fn new(mut out: W, width: usize, height: usize, fps: f32) -> std::io::Result<Self> {
+        let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+            let message = format!("GIF images can be at most {}x{} pixels", MAX_SIZE, MAX_SIZE);
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, message));
+        };
+        out.write_all(b"GIF89a")?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        out.write_all(&[0, 0, 0])?; // No global color table
+        // Netscape extension: repeat forever
+        out.write_all(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00")?;
+        Ok(Self {
+            out,
+            width,
+            height,
+            // Viewers treat delays under 2 as "as fast as possible" and slow them down
+            delay: ((100.0 / fps).round() as u16).max(2),
+        })
+    }
+
+    pub fn frame(&mut self, pixels: &[(u8, u8, u8)]) -> std::io::Result<()> {
+        let (palette, indices) = quantize(pixels);
+
+        // Graphic control: leave the frame in place, then the delay
+        self.out.write_all(&[0x21, 0xf9, 0x04, 0x04])?;
+        self.out.write_all(&self.delay.to_le_bytes())?;
+        self.out.write_all(&[0, 0])?;
+
+        self.out.write_all(&[0x2c, 0, 0, 0, 0])?;
+        self.out.write_all(&self.width.to_le_bytes())?;
+        self.out.write_all(&self.height.to_le_bytes())?;
+        self.out.write_all(&[0x87])?; // Local color table of 256 entries
+        for &(r, g, b) in &palette {
+            self.out.write_all(&[r, g, b])?;
+        }
+
+        self.out.write_all(&[MIN_CODE_SIZE])?;
+        for block in lzw(&indices).chunks(255) {
+            self.out.write_all(&[block.len() as u8])?;
+            self.out.write_all(block)?;
+        }
+        self.out.write_all(&[0])
+    }
+
+    pub fn finish(mut self) -> std::io::Result<()> {
+        self.out.write_all(&[0x3b])?;
+        self.out.flush()
+    }
+}
+
+fn bucket((r, g, b): (u8, u8, u8)) -> usize {
+    let shift = 8 - BUCKET_BITS;
+    ((r as usize >> shift) << (2 * BUCKET_BITS)) | ((g as usize >> shift) << BUCKET_BITS) | (b as usize >> shift)
+}
+
+/// Reduce a frame to at most 256 colors. Returns the palette (padded to 256
+/// entries) and each pixel's index into it.
+fn quantize(pixels: &[(u8, u8, u8)]) -> (Vec<(u8, u8, u8)>, Vec<u8>) {
+    // Colors are grouped into buckets first so the cut only sorts distinct colors
+    let mut counts = vec![0u32; 1 << (3 * BUCKET_BITS)];
+    let mut sums = vec![[0u64; 3]; 1 << (3 * BUCKET_BITS)];
+    for &pixel in pixels {
+        let b = bucket(pixel);
+        counts[b] += 1;
+        sums[b][0] += pixel.0 as u64;
+        sums[b][1] += pixel.1 as u64;
+        sums[b][2] += pixel.2 as u64;
+    }
+    let colors: Vec<(usize, [u8; 3])> = counts
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .map(|(b, &count)| {
+            let mean = |channel: usize| (sums[b][channel] / count as u64) as u8;
+            (b, [mean(0), mean(1), mean(2)])
+        })
+        .collect();
+
+    // Median cut: keep splitting the box with the widest channel range at its median pixel
+    let mut boxes = vec![colors];
+    while boxes.len() < PALETTE_SIZE {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(i, colors)| {
+                let (channel, range) = (0..3)
+                    .map(|c| {
+                        let min = colors.iter().map(|(_, rgb)| rgb[c]).min().unwrap();
+                        let max = colors.iter().map(|(_, rgb)| rgb[c]).max().unwrap();
+                        (c, max - min)
+                    })
+                    .max_by_key(|&(_, range)| range)
+                    .unwrap();
+                (i, channel, range)
+            })
+            .max_by_key(|&(_, _, range)| range);
+        let Some((i, channel, _)) = widest else {
+            break;
+        };
+
+        let mut colors = boxes.swap_remove(i);
+        colors.sort_by_key(|(_, rgb)| rgb[channel]);
+        let total: u32 = colors.iter().map(|(b, _)| counts[*b]).sum();
+        let mut seen = 0;
+        let split = colors
+            .iter()
+            .position(|(b, _)| {
+                seen += counts[*b];
+                seen * 2 >= total
+            })
+            .unwrap()
+            .clamp(0, colors.len() - 2);
+        let upper = colors.split_off(split + 1);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    let mut palette = Vec::with_capacity(PALETTE_SIZE);
+    let mut lookup = vec![0u8; counts.len()];
+    for (index, colors) in boxes.iter().enumerate() {
+        let mut sum = [0u64; 3];
+        let mut total = 0u64;
+        for (b, rgb) in colors {
+            let weight = counts[*b] as u64;
+            for c in 0..3 {
+                sum[c] += rgb[c] as u64 * weight;
+            }
+            total += weight;
+            lookup[*b] = index as u8;
+        }
+        let total = total.max(1);
+        palette.push(((sum[0] / total) as u8, (sum[1] / total) as u8, (sum[2] / total) as u8));
+    }
+    palette.resize(PALETTE_SIZE, (0, 0, 0));
+
+    let indices = pixels.iter().map(|&pixel| lookup[bucket(pixel)]).collect();
+    (palette, indices)
+}
+
+/// GIF-flavored LZW: variable-width codes packed LSB first, with a clear code
+/// whenever the 12-bit table fills up.
+fn lzw(indices: &[u8]) -> Vec<u8> {
+    let clear: u16 = 1 << MIN_CODE_SIZE;
+    let end = clear + 1;
+    let mut out = Vec::new();
+    let mut bits = 0u32;
+    let mut count = 0u32;
+    let mut code_size = MIN_CODE_SIZE as u32 + 1;
+    let mut put = |code: u16, size: u32, out: &mut Vec<u8>| {
+        bits |= (code as u32) << count;
+        count += size;
+        while count >= 8 {
+            out.push(bits as u8);
+            bits >>= 8;
+            count -= 8;
+        }
+    };
+
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end + 1;
+    put(clear, code_size, &mut out);
+    let Some((&first, rest)) = indices.split_first() else {
+        put(end, code_size, &mut out);
+        put(0, 7, &mut out);
+        return out;
+    };
+    let mut prefix = first as u16;
+    for &index in rest {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+        put(prefix, code_size, &mut out);
+        if next_code < MAX_CODES {
+            table.insert((prefix, index), next_code);
+            next_code += 1;
+            if next_code as u32 > 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+        } else {
+            put(clear, code_size, &mut out);
+            table.clear();
+            next_code = end + 1;
+            code_size = MIN_CODE_SIZE as u32 + 1;
+        }
+        prefix = index as u16;
+    }
+    put(prefix, code_size, &mut out);
+    put(end, code_size, &mut out);
+    put(0, 7, &mut out); // Flush the last partial byte
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_lzw(data: &[u8]) -> Vec<u8> {
+        weezl::decode::Decoder::new(weezl::BitOrder::Lsb, MIN_CODE_SIZE).decode(data).unwrap()
+    }
+
+    #[test]
+    fn lzw_round_trips() {
+        let mut rng = fastrand::Rng::with_seed(7);
+        let noise: Vec<u8> = (0..20_000).map(|_| rng.u8(..)).collect();
+        let few: Vec<u8> = (0..20_000).map(|_| rng.u8(..4)).collect();
+        let runs: Vec<u8> = (0..50_000).map(|i| (i / 300) as u8).collect();
+        for indices in [Vec::new(), vec![42], vec![0; 5000], noise, few, runs] {
+            assert_eq!(decode_lzw(&lzw(&indices)), indices);
+        }
+    }
+
+    #[test]
+    fn lzw_resets_when_the_table_fills() {
+        // Noise adds a code for nearly every byte, so 20k bytes fill the 4096-entry table several times
+        let mut rng = fastrand::Rng::with_seed(1);
+        let indices: Vec<u8> = (0..20_000).map(|_| rng.u8(..)).collect();
+        let clear = 1u32 << MIN_CODE_SIZE;
+        let encoded = lzw(&indices);
+        // The first code is a clear code, 9 bits wide
+        assert_eq!(encoded[0] as u32 | ((encoded[1] as u32 & 1) << 8), clear);
+        assert_eq!(decode_lzw(&encoded), indices);
+    }
+
+    #[test]
+    fn quantize_keeps_exact_colors_when_there_are_few() {
+        let colors = [(0, 0, 0), (255, 0, 0), (10, 200, 30), (255, 255, 255)];
+        let pixels: Vec<(u8, u8, u8)> = (0..64).map(|i| colors[i % 4]).collect();
+        let (palette, indices) = quantize(&pixels);
+        assert_eq!(palette.len(), PALETTE_SIZE);
+        let mapped: Vec<(u8, u8, u8)> = indices.iter().map(|&i| palette[i as usize]).collect();
+        assert_eq!(mapped, pixels);
+    }
+
+    #[test]
+    fn quantize_caps_the_palette() {
+        let pixels: Vec<(u8, u8, u8)> = (0..4096u32).map(|i| ((i % 64 * 4) as u8, (i / 64 * 4) as u8, 128)).collect();
+        let (palette, indices) = quantize(&pixels);
+        assert_eq!(palette.len(), PALETTE_SIZE);
+        // Every pixel ends up close to its palette entry
+        for (pixel, &index) in pixels.iter().zip(&indices) {
+            let entry = palette[index as usize];
+            assert!(pixel.0.abs_diff(entry.0) <= 24 && pixel.1.abs_diff(entry.1) <= 24, "{:?} -> {:?}", pixel, entry);
+        }
+    }
+
+    #[test]
+    fn writes_a_decodable_animation() {
+        let (width, height) = (5, 3);
+        let frames: Vec<Vec<(u8, u8, u8)>> = (0..3u8)
+            .map(|f| (0..width * height).map(|i| (i as u8 * 10, f * 50, 7)).collect())
+            .collect();
+        let mut out = Vec::new();
+        let mut gif = GifWriter::new(&mut out, width, height, 25.0).unwrap();
+        for frame in &frames {
+            gif.frame(frame).unwrap();
+        }
+        gif.finish().unwrap();
+
+        assert_eq!(&out[..6], b"GIF89a");
+        assert_eq!(&out[6..10], &[5, 0, 3, 0]);
+        assert_eq!(out.last(), Some(&0x3b));
+
+        // Walk the frames: graphic control, image descriptor, local palette, then data sub-blocks
+        let mut at = 13 + 19; // Screen descriptor, then the Netscape loop extension
+        for frame in &frames {
+            assert_eq!(&out[at..at + 4], &[0x21, 0xf9, 0x04, 0x04]);
+            assert_eq!(u16::from_le_bytes([out[at + 4], out[at + 5]]), 4); // 25 fps is 4 hundredths
+            at += 8;
+            assert_eq!(out[at], 0x2c);
+            assert_eq!(out[at + 9], 0x87);
+            at += 10;
+            let palette: Vec<(u8, u8, u8)> = out[at..at + 3 * PALETTE_SIZE].chunks(3).map(|c| (c[0], c[1], c[2])).collect();
+            at += 3 * PALETTE_SIZE;
+            assert_eq!(out[at], MIN_CODE_SIZE);
+            at += 1;
+            let mut data = Vec::new();
+            while out[at] != 0 {
+                let length = out[at] as usize;
+                data.extend_from_slice(&out[at + 1..at + 1 + length]);
+                at += 1 + length;
+            }
+            at += 1;
+            let pixels: Vec<(u8, u8, u8)> = decode_lzw(&data).iter().map(|&i| palette[i as usize]).collect();
+            assert_eq!(&pixels, frame);
+        }
+        assert_eq!(at, out.len() - 1);
+    }
+
+    #[test]
+    fn rejects_sizes_gif_cannot_describe() {
+        let mut out = Vec::new();
+        assert!(GifWriter::new(&mut out, MAX_SIZE + 1, 1, 30.0).is_err());
+        assert!(GifWriter::new(&mut out, 1, MAX_SIZE + 1, 30.0).is_err());
+        assert!(out.is_empty());
+        GifWriter::new(&mut out, MAX_SIZE, MAX_SIZE, 30.0).unwrap().finish().unwrap();
+        assert_eq!(&out[6..10], &[0xff, 0xff, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn delay_has_a_floor_viewers_respect() {
+        let gif = GifWriter::new(Vec::new(), 1, 1, 240.0).unwrap();
+        assert_eq!(gif.delay, 2);
+    }
+}
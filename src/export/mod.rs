@@ -0,0 +1,94 @@
+use crate::effects::{Canvas, Effect};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+pub mod gif;
+pub mod png;
+
+use gif::GifWriter;
+
+/// Image formats `termsaver export` can write, picked by the output's extension.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    Ppm,
+    Png,
+    Gif,
+}
+
+impl ImageFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "ppm" => Some(ImageFormat::Ppm),
+            "png" => Some(ImageFormat::Png),
+            "gif" => Some(ImageFormat::Gif),
+            _ => None,
+        }
+    }
+}
+
+/// What to render and where.
+pub struct Export {
+    pub size: (usize, usize), // In pixels
+    pub out: PathBuf,
+    pub format: ImageFormat,
+}
+
+/// Path of frame `index` in a PPM or PNG sequence: `aurora.png` becomes
+/// `aurora-0001.png`, `aurora-0002.png` and so on.
+fn frame_path(path: &Path, index: usize, frames: usize) -> PathBuf {
+    if frames == 1 {
+        return path.to_path_buf();
+    }
+    let digits = frames.to_string().len().max(4);
+    let stem = path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    let extension = path.extension().map(|s| s.to_string_lossy()).unwrap_or_default();
+    path.with_file_name(format!("{}-{:0digits$}.{}", stem, index + 1, extension, digits = digits))
+}
+
+fn write_ppm<W: Write>(pixels: &[(u8, u8, u8)], width: usize, height: usize, out: &mut W) -> std::io::Result<()> {
+    write!(out, "P6\n{} {}\n255\n", width, height)?;
+    let bytes: Vec<u8> = pixels.iter().flat_map(|&(r, g, b)| [r, g, b]).collect();
+    out.write_all(&bytes)
+}
+
+/// Render `frames` frames of `effect`, one every `frame_time` seconds of
+/// simulation stepped at `dt`, straight from its pixel buffer.
+///
+/// The effect must have been created at `export.size`.
+pub fn run(mut effect: Box<dyn Effect>, export: &Export, frames: usize, frame_time: f32, dt: f32) -> std::io::Result<()> {
+    let (width, height) = export.size;
+    // One pixel per cell; text the effect draws on top is not part of the image
+    let mut canvas = Canvas::new(width, height, (1, 1));
+    let mut gif = match export.format {
+        ImageFormat::Gif => Some(GifWriter::new(BufWriter::new(File::create(&export.out)?), width, height, 1.0 / frame_time)?),
+        _ => None,
+    };
+
+    let mut accumulator = 0.0;
+    for index in 0..frames {
+        accumulator += frame_time;
+        while accumulator >= dt {
+            effect.update(dt);
+            accumulator -= dt;
+        }
+        effect.render(&mut canvas);
+
+        match &mut gif {
+            Some(gif) => gif.frame(canvas.pixels())?,
+            None => {
+                let mut out = BufWriter::new(File::create(frame_path(&export.out, index, frames))?);
+                match export.format {
+                    ImageFormat::Png => png::write(canvas.pixels(), width, height, &mut out)?,
+                    _ => write_ppm(canvas.pixels(), width, height, &mut out)?,
+                }
+                out.flush()?;
+            }
+        }
+    }
+
+    match gif {
+        Some(gif) => gif.finish(),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,266 @@
+use std::io::Write;
+
+const SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+const WINDOW: usize = 32768;
+const HASH_BITS: usize = 15;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 32; // Earlier positions tried per match; more compresses better but slower
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+/// Write an 8-bit RGB PNG.
+///
+/// Rows use the Sub filter, which turns the smooth gradients effects draw
+/// into long runs, and are compressed with fixed-Huffman deflate.
+pub fn write<W: Write>(pixels: &[(u8, u8, u8)], width: usize, height: usize, out: &mut W) -> std::io::Result<()> {
+    let mut raw = Vec::with_capacity((width * 3 + 1) * height);
+    for row in pixels.chunks(width) {
+        raw.push(1); // Sub: each byte minus the same channel of the pixel to its left
+        let mut left = (0u8, 0u8, 0u8);
+        for &(r, g, b) in row {
+            raw.extend_from_slice(&[r.wrapping_sub(left.0), g.wrapping_sub(left.1), b.wrapping_sub(left.2)]);
+            left = (r, g, b);
+        }
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]); // 8 bits per channel, RGB, deflate, adaptive filters, no interlace
+
+    out.write_all(SIGNATURE)?;
+    write_chunk(out, b"IHDR", &header)?;
+    write_chunk(out, b"IDAT", &zlib(&raw))?;
+    write_chunk(out, b"IEND", &[])
+}
+
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> std::io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = crc32(crc32(0xffff_ffff, kind), data) ^ 0xffff_ffff;
+    out.write_all(&crc.to_be_bytes())
+}
+
+fn crc32(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+/// LSB-first bit packing as deflate expects.
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn put(&mut self, value: u32, count: u32) {
+        self.bits |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Huffman codes are defined most significant bit first.
+    fn put_code(&mut self, code: u32, count: u32) {
+        self.put(code.reverse_bits() >> (32 - count), count);
+    }
+
+    fn put_symbol(&mut self, symbol: u16) {
+        let symbol = symbol as u32;
+        match symbol {
+            0..=143 => self.put_code(0x30 + symbol, 8),
+            144..=255 => self.put_code(0x190 + symbol - 144, 9),
+            256..=279 => self.put_code(symbol - 256, 7),
+            _ => self.put_code(0xc0 + symbol - 280, 8),
+        }
+    }
+
+    fn put_match(&mut self, length: usize, distance: usize) {
+        let i = LENGTH_BASE.iter().rposition(|&base| base as usize <= length).unwrap();
+        self.put_symbol(257 + i as u16);
+        self.put((length - LENGTH_BASE[i] as usize) as u32, LENGTH_EXTRA[i] as u32);
+        let i = DIST_BASE.iter().rposition(|&base| base as usize <= distance).unwrap();
+        self.put_code(i as u32, 5);
+        self.put((distance - DIST_BASE[i] as usize) as u32, DIST_EXTRA[i] as u32);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.bits as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Compress into a zlib stream holding a single fixed-Huffman deflate block.
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter {
+        bytes: vec![0x78, 0x01],
+        bits: 0,
+        count: 0,
+    };
+    writer.put(1, 1); // Final block
+    writer.put(1, 2); // Fixed Huffman codes
+
+    let hash = |i: usize| {
+        let key = (data[i] as usize) << 16 | (data[i + 1] as usize) << 8 | data[i + 2] as usize;
+        (key.wrapping_mul(2654435761) >> 7) & ((1 << HASH_BITS) - 1)
+    };
+    // Most recent position per hash, and the position before it with the same hash
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; WINDOW];
+    let insert = |i: usize, head: &mut Vec<usize>, prev: &mut Vec<usize>| {
+        if i + 2 < data.len() {
+            let h = hash(i);
+            prev[i % WINDOW] = head[h];
+            head[h] = i;
+        }
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        let mut best = (0, 0);
+        if i + 2 < data.len() {
+            let mut candidate = head[hash(i)];
+            let limit = (data.len() - i).min(MAX_MATCH);
+            for _ in 0..MAX_CHAIN {
+                if candidate == usize::MAX || i - candidate > WINDOW - 1 {
+                    break;
+                }
+                let length = (0..limit).take_while(|&k| data[candidate + k] == data[i + k]).count();
+                if length > best.0 {
+                    best = (length, i - candidate);
+                    if length == limit {
+                        break;
+                    }
+                }
+                candidate = prev[candidate % WINDOW];
+            }
+        }
+
+        if best.0 >= 3 {
+            writer.put_match(best.0, best.1);
+            for k in i..i + best.0 {
+                insert(k, &mut head, &mut prev);
+            }
+            i += best.0;
+        } else {
+            writer.put_symbol(data[i] as u16);
+            insert(i, &mut head, &mut prev);
+            i += 1;
+        }
+    }
+    writer.put_symbol(256);
+
+    let mut bytes = writer.finish();
+    bytes.extend_from_slice(&adler32(data).to_be_bytes());
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inflate(data: &[u8]) -> Vec<u8> {
+        miniz_oxide::inflate::decompress_to_vec_zlib(data).unwrap()
+    }
+
+    #[test]
+    fn checksums_match_known_values() {
+        assert_eq!(crc32(0xffff_ffff, b"123456789") ^ 0xffff_ffff, 0xcbf4_3926);
+        assert_eq!(crc32(0xffff_ffff, b"IEND") ^ 0xffff_ffff, 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        assert_eq!(adler32(&[]), 1);
+        // Long enough that the sums must be reduced mid-way
+        assert_eq!(adler32(&vec![0xff; 100_000]), miniz_oxide_adler(&vec![0xff; 100_000]));
+    }
+
+    fn miniz_oxide_adler(data: &[u8]) -> u32 {
+        // The trailer of a zlib stream is the Adler-32 of its contents
+        let stream = miniz_oxide::deflate::compress_to_vec_zlib(data, 1);
+        u32::from_be_bytes(stream[stream.len() - 4..].try_into().unwrap())
+    }
+
+    #[test]
+    fn zlib_round_trips() {
+        let mut rng = fastrand::Rng::with_seed(3);
+        let noise: Vec<u8> = (0..50_000).map(|_| rng.u8(..)).collect();
+        let few: Vec<u8> = (0..50_000).map(|_| rng.u8(..3)).collect();
+        // Repeats further back than the window, and runs longer than the longest match
+        let mut far = noise[..40_000].to_vec();
+        far.extend_from_slice(&noise[..40_000]);
+        let cases = [Vec::new(), vec![9], vec![0; 100_000], b"abcabcabcabcabd".repeat(50), noise, few, far];
+        for data in cases {
+            assert_eq!(inflate(&zlib(&data)), data);
+        }
+    }
+
+    #[test]
+    fn writes_a_decodable_image() {
+        let (width, height) = (7, 4);
+        let pixels: Vec<(u8, u8, u8)> = (0..width * height).map(|i| ((i * 37) as u8, 255 - i as u8, (i % width * 40) as u8)).collect();
+        let mut out = Vec::new();
+        write(&pixels, width, height, &mut out).unwrap();
+
+        assert_eq!(&out[..8], SIGNATURE);
+        let mut chunks = Vec::new();
+        let mut at = 8;
+        while at < out.len() {
+            let length = u32::from_be_bytes(out[at..at + 4].try_into().unwrap()) as usize;
+            let body = &out[at + 4..at + 8 + length];
+            let crc = u32::from_be_bytes(out[at + 8 + length..at + 12 + length].try_into().unwrap());
+            assert_eq!(crc32(0xffff_ffff, body) ^ 0xffff_ffff, crc);
+            chunks.push((body[..4].to_vec(), body[4..].to_vec()));
+            at += 12 + length;
+        }
+        let kinds: Vec<&[u8]> = chunks.iter().map(|(kind, _)| kind.as_slice()).collect();
+        assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+        assert_eq!(chunks[0].1, [0, 0, 0, 7, 0, 0, 0, 4, 8, 2, 0, 0, 0]);
+
+        // Undo the Sub filter on each row
+        let raw = inflate(&chunks[1].1);
+        assert_eq!(raw.len(), (width * 3 + 1) * height);
+        let mut decoded = Vec::new();
+        for row in raw.chunks(width * 3 + 1) {
+            assert_eq!(row[0], 1);
+            let mut left = (0u8, 0u8, 0u8);
+            for pixel in row[1..].chunks(3) {
+                left = (pixel[0].wrapping_add(left.0), pixel[1].wrapping_add(left.1), pixel[2].wrapping_add(left.2));
+                decoded.push(left);
+            }
+        }
+        assert_eq!(decoded, pixels);
+    }
+}
@@ -0,0 +1,137 @@
+use crate::effects::{Canvas, Effect};
+use crate::overlay::Overlays;
+use crate::render::Renderer;
+use std::io::Write;
+
+/// Drives an effect without a terminal.
+///
+/// Each step advances the effect by a fixed timestep, renders it and passes
+/// the renderer's output to any writer, so frames can be captured, compared
+/// or streamed elsewhere.
+pub struct Headless {
+    effect: Box<dyn Effect>,
+    canvas: Canvas,
+    renderer: Renderer,
+    overlays: Option<Overlays>,
+}
+
+impl Headless {
+    /// `effect` must have been created for `cols` x `rows` cells in the current pixel mode.
+    pub fn new(effect: Box<dyn Effect>, cols: usize, rows: usize, renderer: Renderer) -> Self {
+        let (cell_w, cell_h) = crate::get_pixel_mode().cell_size();
+        Self {
+            effect,
+            canvas: Canvas::new(cols, rows, (cell_w, cell_h)),
+            renderer,
+            overlays: None,
+        }
+    }
+
+    /// Draw `overlays` on top of every frame, as a terminal run would.
+    pub fn with_overlays(mut self, overlays: Overlays) -> Self {
+        self.overlays = Some(overlays);
+        self
+    }
+
+    /// Advance one frame and write whatever the renderer emits for it.
+    pub fn step<W: Write>(&mut self, dt: f32, out: &mut W) -> std::io::Result<()> {
+        self.effect.update(dt);
+        self.canvas.clear_text();
+        self.effect.render(&mut self.canvas);
+        if let Some(overlays) = &mut self.overlays {
+            overlays.draw(&mut self.canvas);
+        }
+        self.renderer.draw(&self.canvas, out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::{EffectInfo, REGISTRY};
+    use crate::overlay::{Overlay, OverlayKind};
+    use crate::render::color::ColorDepth;
+    use crate::render::graphics::Graphics;
+    use crate::render::pixels::PixelMode;
+    use crate::sysstats::SystemStats;
+
+    const COLS: usize = 40;
+    const ROWS: usize = 12;
+    const FRAMES: usize = 60;
+    const SEED: u64 = 7;
+
+    /// FNV-1a hashes of each effect's output over `FRAMES` frames, the same as
+    /// `termsaver EFFECT --headless 40x12 --frames 60 --seed 7 --colors truecolor`.
+    /// A change that is meant to alter an effect's picture updates its hash here.
+    const GOLDEN: &[(&str, u64)] = &[
+        ("fire", 0xc6fc80366b49a539),
+        ("thunder", 0x0f0775e6086d6e27),
+        ("plasma", 0x95d551fb002162fc),
+        ("fireworks", 0x12a6e5993a10e52a),
+        ("lavalamp", 0xa070f07c11c1739d),
+        ("aurora", 0x74527cd1bf30e600),
+        ("clouds", 0x95e45247342d4fe0),
+        ("biolum", 0x64a61bff712535f1),
+        ("star", 0x848f4d18d7aea996),
+        ("gol", 0xb8df0a3a5578b89d),
+    ];
+
+    fn headless(info: &EffectInfo) -> Headless {
+        let (cell_w, cell_h) = PixelMode::Half.cell_size();
+        let effect = (info.create)(COLS * cell_w, ROWS * cell_h, fastrand::Rng::with_seed(SEED));
+        Headless::new(effect, COLS, ROWS, Renderer::new(ColorDepth::TrueColor, PixelMode::Half, Graphics::Cells))
+    }
+
+    fn run(headless: &mut Headless) -> Vec<u8> {
+        let mut out = Vec::new();
+        for _ in 0..FRAMES {
+            headless.step(1.0 / 60.0, &mut out).unwrap();
+        }
+        out
+    }
+
+    fn fnv1a(data: &[u8]) -> u64 {
+        data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100_0000_01b3))
+    }
+
+    #[test]
+    fn effects_match_their_golden_output() {
+        assert_eq!(GOLDEN.len(), REGISTRY.len());
+        let mismatched: Vec<String> = REGISTRY
+            .iter()
+            .filter_map(|info| {
+                let hash = fnv1a(&run(&mut headless(info)));
+                let golden = GOLDEN.iter().find(|(name, _)| *name == info.name).map(|(_, hash)| *hash);
+                (golden != Some(hash)).then(|| format!("(\"{}\", {:#018x}),", info.name, hash))
+            })
+            .collect();
+        assert!(mismatched.is_empty(), "output changed:\n{}", mismatched.join("\n"));
+    }
+
+    #[test]
+    fn effects_each_draw_from_their_own_generator() {
+        // Stepped in turn, two copies sharing one generator would split its numbers and drift apart
+        for info in REGISTRY {
+            let (mut first, mut second) = (headless(info), headless(info));
+            let (mut first_out, mut second_out) = (Vec::new(), Vec::new());
+            for _ in 0..FRAMES {
+                first.step(1.0 / 60.0, &mut first_out).unwrap();
+                second.step(1.0 / 60.0, &mut second_out).unwrap();
+            }
+            assert!(first_out == second_out, "{} drifted apart", info.name);
+        }
+    }
+
+    #[test]
+    fn draws_overlays() {
+        let overlays = Overlays::new(
+            vec![Overlay::new(OverlayKind::Message)],
+            Some("HELLOXYZ".to_string()),
+            None,
+            SystemStats::new("/proc".into(), "/".into()),
+        );
+        let mut headless = headless(&REGISTRY[0]).with_overlays(overlays);
+        let out = String::from_utf8(run(&mut headless)).unwrap();
+        assert!(out.contains("HELLOXYZ"));
+    }
+}
@@ -0,0 +1,54 @@
+use crate::cli::GLOBAL_KEYS;
+use crate::effects::{Canvas, EffectInfo, Param};
+
+const FG: (u8, u8, u8) = (230, 230, 230);
+const DIM: (u8, u8, u8) = (150, 150, 170);
+const BG: (u8, u8, u8) = (16, 16, 28);
+
+/// Draw a centered box describing the running effect, its controls and current parameters.
+pub fn draw(canvas: &mut Canvas, info: &EffectInfo, params: &[Param]) {
+    let mut lines: Vec<(String, (u8, u8, u8), bool)> = Vec::new();
+    lines.push((format!("{} - {}", info.name, info.description), FG, true));
+    lines.push((String::new(), FG, false));
+
+    let key_width = info
+        .controls
+        .iter()
+        .chain(GLOBAL_KEYS)
+        .map(|(key, _)| key.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (key, action) in info.controls {
+        lines.push((format!("{:<width$}  {}", key, action, width = key_width), FG, false));
+    }
+    if !info.controls.is_empty() {
+        lines.push((String::new(), FG, false));
+    }
+    for (key, action) in GLOBAL_KEYS {
+        lines.push((format!("{:<width$}  {}", key, action, width = key_width), DIM, false));
+    }
+    if !params.is_empty() {
+        lines.push((String::new(), FG, false));
+        let settings: Vec<String> = params.iter().map(|p| format!("{} = {}", p.name, p.value_text())).collect();
+        let setting_width = settings.iter().map(|s| s.chars().count()).max().unwrap_or(0);
+        for (param, setting) in params.iter().zip(&settings) {
+            lines.push((format!("{:<width$}  {}", setting, param.description, width = setting_width), FG, false));
+        }
+    }
+
+    let inner_width = lines.iter().map(|(text, _, _)| text.chars().count()).max().unwrap_or(0);
+    let box_width = (inner_width + 4).min(canvas.cols());
+    let box_height = (lines.len() + 2).min(canvas.rows());
+    let left = (canvas.cols() - box_width) / 2;
+    let top = (canvas.rows() - box_height) / 2;
+
+    let blank = " ".repeat(box_width);
+    for row in 0..box_height {
+        canvas.put_text(left, top + row, &blank, FG, Some(BG), false);
+    }
+    for (i, (text, color, bold)) in lines.iter().enumerate().take(box_height.saturating_sub(2)) {
+        // Clip to the box so long descriptions don't spill over
+        let clipped: String = text.chars().take(box_width.saturating_sub(4)).collect();
+        canvas.put_text(left + 2, top + 1 + i, &clipped, *color, Some(BG), *bold);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::effects::Canvas;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::SaltString;
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant};
+
+const FG: (u8, u8, u8) = (230, 230, 230);
+const DIM: (u8, u8, u8) = (150, 150, 170);
+const ERROR: (u8, u8, u8) = (255, 120, 110);
+const SHADE: (u8, u8, u8) = (12, 12, 24);
+const SHADE_ALPHA: f32 = 0.75;
+const PROMPT_WIDTH: usize = 40;
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(30); // Untouched prompts go away, taking what was typed
+const FREE_ATTEMPTS: u32 = 3; // Failures allowed before each attempt has to wait
+const FIRST_WAIT: Duration = Duration::from_secs(5); // Doubles with every further failure
+const LONGEST_WAIT: Duration = Duration::from_secs(300);
+
+/// Hash a passphrase for the `[lock]` section of the config file, with a fresh random salt.
+pub fn hash(passphrase: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(passphrase.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|err| format!("cannot hash passphrase: {}", err))
+}
+
+/// Check that a stored hash is one `Lock` can verify against.
+pub fn check_hash(hash: &str) -> Result<(), String> {
+    PasswordHash::new(hash).map(|_| ()).map_err(|err| format!("invalid passphrase hash: {}", err))
+}
+
+/// Ask for a line on the terminal without echoing it.
+pub fn read_hidden(prompt: &str) -> io::Result<String> {
+    let input = libc::STDIN_FILENO;
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+    // SAFETY: tcgetattr and tcsetattr only read and write the termios we pass them
+    let saved = unsafe {
+        let mut termios: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(input, &mut termios) != 0 {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "stdin is not a terminal"));
+        }
+        let saved = termios;
+        termios.c_lflag &= !libc::ECHO;
+        libc::tcsetattr(input, libc::TCSANOW, &termios);
+        saved
+    };
+    let mut line = String::new();
+    let result = io::stdin().lock().read_line(&mut line);
+    // SAFETY: as above
+    unsafe { libc::tcsetattr(input, libc::TCSANOW, &saved) };
+    eprintln!();
+    result?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Keep Ctrl+C, Ctrl+\ and Ctrl+Z from ending or suspending a locked screensaver.
+///
+/// Raw mode already turns those keys into plain input; this also covers the
+/// signals arriving some other way, such as from a terminal that was switched
+/// back to cooked mode.
+pub fn ignore_signals() {
+    // SAFETY: setting a signal to be ignored installs no handler code
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_IGN);
+        libc::signal(libc::SIGQUIT, libc::SIG_IGN);
+        libc::signal(libc::SIGTSTP, libc::SIG_IGN);
+    }
+}
+
+/// How long the prompt refuses input after the given number of failed attempts.
+fn wait_after(failures: u32) -> Option<Duration> {
+    let extra = failures.checked_sub(FREE_ATTEMPTS)?;
+    Some(FIRST_WAIT.saturating_mul(1 << extra.min(16)).min(LONGEST_WAIT))
+}
+
+/// Stands between the screensaver and the shell: every key goes to a
+/// passphrase prompt, and only the right passphrase lets the screensaver exit.
+pub struct Lock {
+    hash: String,
+    prompt: Option<Prompt>,
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+struct Prompt {
+    entered: String,
+    touched: Instant,
+    message: Option<String>,
+}
+
+impl Lock {
+    /// `hash` must have passed `check_hash`.
+    pub fn new(hash: String) -> Self {
+        Self {
+            hash,
+            prompt: None,
+            failures: 0,
+            retry_at: None,
+        }
+    }
+
+    /// Handle a key press. Returns true once the right passphrase was entered.
+    pub fn handle_key(&mut self, key: &KeyEvent) -> bool {
+        let waiting = self.retry_at.is_some_and(|at| Instant::now() < at);
+        let prompt = self.prompt.get_or_insert_with(|| Prompt {
+            entered: String::new(),
+            touched: Instant::now(),
+            message: None,
+        });
+        prompt.touched = Instant::now();
+        if waiting {
+            return false;
+        }
+
+        match key.code {
+            KeyCode::Enter => {
+                let entered = std::mem::take(&mut prompt.entered);
+                if self.verify(&entered) {
+                    return true;
+                }
+                self.failures += 1;
+                self.retry_at = wait_after(self.failures).map(|wait| Instant::now() + wait);
+                if let Some(prompt) = &mut self.prompt {
+                    prompt.message = Some("Wrong passphrase".to_string());
+                }
+            }
+            KeyCode::Backspace => {
+                prompt.entered.pop();
+            }
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => prompt.entered.clear(),
+            // Other control keys, Ctrl+C and Ctrl+Z among them, only bring up the prompt
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                prompt.entered.push(c);
+                prompt.message = None;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn verify(&self, entered: &str) -> bool {
+        PasswordHash::new(&self.hash).is_ok_and(|hash| Argon2::default().verify_password(entered.as_bytes(), &hash).is_ok())
+    }
+
+    /// Draw the prompt, if a key has brought it up, centered over the effect.
+    pub fn draw(&mut self, canvas: &mut Canvas) {
+        if self.prompt.as_ref().is_some_and(|prompt| prompt.touched.elapsed() >= PROMPT_TIMEOUT) {
+            self.prompt = None;
+        }
+        let Some(prompt) = &self.prompt else {
+            return;
+        };
+
+        let wait = self.retry_at.map(|at| at.saturating_duration_since(Instant::now())).filter(|wait| !wait.is_zero());
+        // One mark per character, capped so the box doesn't give the length away past a point
+        let masked: String = "•".repeat(prompt.entered.chars().count().min(PROMPT_WIDTH - 4));
+        let mut lines: Vec<(String, (u8, u8, u8), bool)> = vec![
+            ("Locked".to_string(), FG, true),
+            (String::new(), FG, false),
+            (format!("Passphrase: {}▏", masked), FG, false),
+            (String::new(), FG, false),
+        ];
+        match (wait, &prompt.message) {
+            (Some(wait), _) => lines.push((format!("Too many attempts, try again in {}s", wait.as_secs() + 1), ERROR, false)),
+            (None, Some(message)) => lines.push((message.clone(), ERROR, false)),
+            (None, None) => lines.push(("Enter to unlock, Esc to cancel".to_string(), DIM, false)),
+        }
+
+        let width = (PROMPT_WIDTH + 4).min(canvas.cols());
+        let height = (lines.len() + 2).min(canvas.rows());
+        let left = (canvas.cols() - width) / 2;
+        let top = (canvas.rows() - height) / 2;
+        canvas.darken_cells((left, top), (width, height), SHADE, SHADE_ALPHA);
+        for (i, (text, color, bold)) in lines.iter().enumerate().take(height.saturating_sub(2)) {
+            let clipped: String = text.chars().take(width.saturating_sub(4)).collect();
+            canvas.put_text(left + 2, top + 1 + i, &clipped, *color, None, *bold);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn waits_double_after_the_free_attempts() {
+        let waits: Vec<Option<u64>> = (0..=10).map(|failures| wait_after(failures).map(|wait| wait.as_secs())).collect();
+        assert_eq!(
+            waits,
+            [None, None, None, Some(5), Some(10), Some(20), Some(40), Some(80), Some(160), Some(300), Some(300)]
+        );
+        assert_eq!(wait_after(u32::MAX), Some(LONGEST_WAIT));
+    }
+
+    #[test]
+    fn failures_past_the_free_attempts_lock_out_the_prompt() {
+        // Not a valid hash, so nothing verifies and no time goes into Argon2
+        let mut lock = Lock::new(String::new());
+        for attempt in 1..=FREE_ATTEMPTS {
+            assert!(lock.retry_at.is_none(), "attempt {}", attempt);
+            lock.handle_key(&key(KeyCode::Char('x')));
+            assert!(!lock.handle_key(&key(KeyCode::Enter)));
+        }
+        assert_eq!(lock.failures, FREE_ATTEMPTS);
+        let retry_at = lock.retry_at.expect("a wait after the free attempts");
+        assert!(retry_at > Instant::now() + FIRST_WAIT - Duration::from_secs(1));
+
+        // Keys during the wait neither type nor count as attempts
+        lock.handle_key(&key(KeyCode::Char('y')));
+        lock.handle_key(&key(KeyCode::Enter));
+        assert_eq!(lock.failures, FREE_ATTEMPTS);
+        assert_eq!(lock.prompt.as_ref().map(|prompt| prompt.entered.as_str()), Some(""));
+
+        // Once it is over, the next failure waits twice as long
+        lock.retry_at = Some(Instant::now());
+        lock.handle_key(&key(KeyCode::Enter));
+        assert_eq!(lock.failures, FREE_ATTEMPTS + 1);
+        assert!(lock.retry_at.unwrap() > Instant::now() + FIRST_WAIT * 2 - Duration::from_secs(1));
+    }
+}
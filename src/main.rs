@@ -1,109 +1,651 @@
 use crossterm::{
     cursor::{Hide, Show},
-    event::{self, Event, KeyCode, EnableMouseCapture, DisableMouseCapture},
+    event::{self, Event, KeyCode, EnableFocusChange, DisableFocusChange, EnableMouseCapture, DisableMouseCapture, MouseEventKind},
     execute,
     terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use std::env;
-use std::io::{stdout, BufWriter};
+use std::io::{self, stdout, BufWriter, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 use std::time::{Duration, Instant};
 
+mod bench;
+mod cli;
+mod config;
+mod control;
 mod effects;
-use effects::Effect;
+mod export;
+mod headless;
+mod help;
+mod lock;
+mod overlay;
+mod playlist;
+mod quality;
+mod record;
+mod render;
+mod stats;
+mod sysstats;
+mod transition;
+mod tweak;
+mod watch;
+use bench::Bench;
+use cli::Arg::{self, Flag, Word};
+use control::{Command, ControlServer};
+use effects::{Canvas, Effect, EffectInfo, ParamValue};
+use export::{Export, ImageFormat};
+use fastrand::Rng;
+use headless::Headless;
+use lock::Lock;
+use overlay::{Overlay, OverlayKind, Overlays};
+use sysstats::SystemStats;
+use playlist::Playlist;
+use quality::QualityController;
+use record::{Cast, Recorder};
+use transition::{Transition, TransitionKind};
+use tweak::TweakPanel;
+use render::color::ColorDepth;
+use render::graphics::Graphics;
+use render::pixels::PixelMode;
+use render::Renderer;
+use stats::FrameStats;
+
+const FIXED_DT: f32 = 1.0 / 60.0;
+const DEFAULT_FPS: f32 = 30.0;
+const FPS_RANGE: std::ops::RangeInclusive<f32> = 1.0..=240.0;
+const MAX_CATCH_UP: f32 = 0.25; // Most simulated time to make up after a slow frame, in seconds
+const UNFOCUSED_FPS: f32 = 5.0; // Frame rate while the terminal or tmux pane reports it lost focus
+const LOCKED_RETRIES: u32 = 10; // Frames in a row that may fail while locked before giving up
 
 static BG_COLOR: OnceLock<(u8, u8, u8)> = OnceLock::new();
+static PIXEL_MODE: OnceLock<PixelMode> = OnceLock::new();
 
 pub fn get_bg_color() -> (u8, u8, u8) {
     *BG_COLOR.get().unwrap_or(&(0, 0, 0))
 }
 
-fn print_usage() {
-    eprintln!("termsaver - Terminal screensaver with various effects");
-    eprintln!();
-    eprintln!("Usage: termsaver [EFFECT] [OPTIONS]");
-    eprintln!();
-    eprintln!("Effects:");
-    eprintln!("  fire      Fire screensaver (default)");
-    eprintln!("  thunder   Realistic branching lightning");
-    eprintln!("  plasma    Interactive plasma globe with mouse");
-    eprintln!("  fireworks Colorful fireworks display");
-    eprintln!("  lavalamp  Smooth metaball lava lamp animation");
-    eprintln!("  aurora    Aurora borealis with smooth wave-like patterns");
-    eprintln!("  clouds    Realistic clouds with multiple types and volumetric shading");
-    eprintln!("  biolum    Deep sea bioluminescence with jellyfish and schooling fish");
-    eprintln!("  star      Realistic star with accurate stellar classification and physics");
-    eprintln!("  gol       Conway's Game of Life with randomized multi-state rules");
-    eprintln!("            Controls: 0-8 = survival, !@#$%^&*() = birth, +/- = states, R = random, click = cycle cell");
-    eprintln!();
-    eprintln!("Options:");
-    eprintln!("  --bg-color RRGGBB  Set background color as hex (e.g., --bg-color 1a1b26)");
-    eprintln!();
-    eprintln!("Press 'q', ESC, or Ctrl+C to exit");
-}
-
-fn run_effect<E: Effect>() -> std::io::Result<()> {
-    let stdout = stdout();
-    let mut stdout = BufWriter::with_capacity(1024 * 64, stdout);
+pub fn get_pixel_mode() -> PixelMode {
+    *PIXEL_MODE.get().unwrap_or(&PixelMode::Half)
+}
 
-    terminal::enable_raw_mode()?;
-    execute!(stdout, EnterAlternateScreen, Hide, Clear(ClearType::All), EnableMouseCapture)?;
+fn cell_pixel_size() -> (usize, usize) {
+    match terminal::window_size() {
+        Ok(size) if size.columns > 0 && size.rows > 0 => {
+            ((size.width / size.columns) as usize, (size.height / size.rows) as usize)
+        }
+        _ => (0, 0),
+    }
+}
+
+/// Settings from the command line that apply to every effect.
+struct Options {
+    color_depth: ColorDepth,
+    graphics: Option<Graphics>,
+    headless: Option<(usize, usize)>,
+    export: Option<Export>,
+    bench: Option<Bench>,
+    watch: Option<Duration>, // How long the shell sits idle before the screensaver starts
+    exit_on_input: bool,     // Any key or click ends the screensaver, as under watch
+    frames: usize,
+    transition: Option<TransitionKind>, // None = pick at random each time
+    transition_time: f32,
+    fps: f32,
+    show_fps: bool,
+    quality: Option<f32>, // None = adapt to the measured frame time
+    effect_settings: Vec<EffectSetting>, // Config file first, then --set, so later ones win
+    config_path: Option<PathBuf>,        // Where the tweak panel saves, even with --no-config
+    record: Option<PathBuf>,
+    overlays: Vec<Overlay>,
+    message: Option<String>,
+    message_file: Option<PathBuf>,
+    lock: Option<String>, // Passphrase hash; when set, only the passphrase ends the screensaver
+    proc_root: PathBuf, // Where the system overlay reads load, memory and network usage
+    disk: PathBuf,      // A path on the filesystem whose usage the system overlay shows
+    control_socket: Option<PathBuf>,
+}
+
+/// A parameter value for one effect, from the config file, `--set` or the tweak panel.
+#[derive(Clone)]
+struct EffectSetting {
+    effect: &'static str,
+    param: String,
+    value: ParamValue,
+}
+
+/// Create an effect with its own generator forked from `rng` and apply its parameter settings.
+fn create_effect(info: &EffectInfo, width: usize, height: usize, settings: &[EffectSetting], rng: &mut Rng) -> Box<dyn Effect> {
+    let mut effect = (info.create)(width, height, rng.fork());
+    for setting in settings.iter().filter(|s| s.effect == info.name) {
+        // Settings were checked against the effect when they were parsed
+        let _ = effects::apply_param(info, effect.as_mut(), &setting.param, setting.value.clone());
+    }
+    effect
+}
+
+/// Parse `--set EFFECT.PARAM=VALUE`, checking it against the parameters the effect declares.
+fn parse_setting(text: &str) -> Result<EffectSetting, String> {
+    let (target, value) = text.split_once('=').ok_or("expected EFFECT.PARAM=VALUE")?;
+    let (effect, param) = target.split_once('.').ok_or("expected EFFECT.PARAM=VALUE")?;
+    let info = effects::lookup(effect).ok_or(format!("unknown effect '{}'", effect))?;
+    let kind = info.find_param(param)?.kind;
+    let value = kind.parse_text(value).map_err(|message| format!("{}.{} {}", info.name, param, message))?;
+    Ok(EffectSetting {
+        effect: info.name,
+        param: param.to_string(),
+        value,
+    })
+}
+
+/// Open the `--record` file, or exit if it can't be created.
+fn start_recording(path: &Path, cast: io::Result<Cast>) -> Cast {
+    cast.unwrap_or_else(|err| {
+        eprintln!("Cannot record to {}: {}", path.display(), err);
+        std::process::exit(1);
+    })
+}
+
+fn run(options: &Options, mut playlist: Playlist, mut rng: Rng) -> std::io::Result<()> {
+    if let Some(export) = &options.export {
+        return run_export(options, &playlist, export, &mut rng);
+    }
+    if let Some(bench) = &options.bench {
+        return run_bench(options, bench, &mut rng);
+    }
+    if let Some(idle) = options.watch {
+        return run_watch(options, &mut playlist, idle, &mut rng);
+    }
+    match options.headless {
+        Some((cols, rows)) => run_headless(options, &playlist, cols, rows, &mut rng),
+        None => run_effect(options, &mut playlist, &mut rng),
+    }
+}
+
+fn run_export(options: &Options, playlist: &Playlist, export: &Export, rng: &mut Rng) -> std::io::Result<()> {
+    let (width, height) = export.size;
+    let mut effect = create_effect(playlist.current(), width, height, &options.effect_settings, rng);
+    // Images are for showing off, so full detail unless asked otherwise
+    effect.set_quality(options.quality.unwrap_or(1.0));
+    if let Err(err) = export::run(effect, export, options.frames, 1.0 / options.fps, FIXED_DT) {
+        eprintln!("Cannot export to {}: {}", export.out.display(), err);
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn run_bench(options: &Options, bench: &Bench, rng: &mut Rng) -> std::io::Result<()> {
+    let (cols, rows) = bench.size;
+    let pixel_mode = get_pixel_mode();
+    let (cell_w, cell_h) = pixel_mode.cell_size();
+    let mut results = Vec::new();
+    for info in &bench.effects {
+        let mut effect = create_effect(info, cols * cell_w, rows * cell_h, &options.effect_settings, rng);
+        effect.set_quality(options.quality.unwrap_or(1.0));
+        let canvas = Canvas::new(cols, rows, (cell_w, cell_h));
+        let renderer = Renderer::new(options.color_depth, pixel_mode, options.graphics.unwrap_or(Graphics::Cells));
+        results.push(bench::measure(info.name, effect, canvas, renderer, options.frames, FIXED_DT)?);
+    }
+    bench::report(bench, options.frames, &results);
+    Ok(())
+}
+
+fn run_watch(options: &Options, playlist: &mut Playlist, idle: Duration, rng: &mut Rng) -> std::io::Result<()> {
+    // Each time the saver comes back, the playlist carries on where it left off
+    let code = watch::run(idle, || run_effect(options, playlist, rng)).unwrap_or_else(|err| {
+        eprintln!("watch: {}", err);
+        std::process::exit(1);
+    });
+    std::process::exit(code);
+}
+
+fn run_headless(options: &Options, playlist: &Playlist, cols: usize, rows: usize, rng: &mut Rng) -> std::io::Result<()> {
+    // There is no terminal to probe, so auto means character cells
+    let graphics = options.graphics.unwrap_or(Graphics::Cells);
+    let renderer = Renderer::new(options.color_depth, get_pixel_mode(), graphics);
+    let (cell_w, cell_h) = get_pixel_mode().cell_size();
+    let mut effect = create_effect(playlist.current(), cols * cell_w, rows * cell_h, &options.effect_settings, rng);
+    // Frame times mean nothing here, so only a fixed quality applies
+    if let Some(quality) = options.quality {
+        effect.set_quality(quality);
+    }
+    let mut headless = Headless::new(effect, cols, rows, renderer).with_overlays(create_overlays(options));
+
+    let cast = options.record.as_ref().map(|path| start_recording(path, Cast::simulated(path, cols, rows)));
+    // A recording takes the output in place of stdout
+    let out: Box<dyn Write> = if cast.is_some() { Box::new(io::sink()) } else { Box::new(stdout().lock()) };
+    let mut out = BufWriter::with_capacity(1024 * 64, Recorder::new(out, cast));
+    for _ in 0..options.frames {
+        headless.step(FIXED_DT, &mut out)?;
+        if let Some(cast) = out.get_mut().cast_mut() {
+            cast.advance(FIXED_DT);
+        }
+    }
+    out.flush()
+}
+
+fn create_overlays(options: &Options) -> Overlays {
+    Overlays::new(
+        options.overlays.clone(),
+        options.message.clone(),
+        options.message_file.clone(),
+        SystemStats::new(options.proc_root.clone(), options.disk.clone()),
+    )
+}
 
+fn run_effect(options: &Options, playlist: &mut Playlist, rng: &mut Rng) -> std::io::Result<()> {
     let (cols, rows) = terminal::size()?;
-    let mut effect = E::new(cols as usize, rows as usize * 2);
+    let mut control = options.control_socket.as_ref().map(|path| match ControlServer::bind(path) {
+        Ok(server) => server,
+        Err(err) => {
+            eprintln!("Cannot listen on {}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+    });
+    let cast = options
+        .record
+        .as_ref()
+        .map(|path| start_recording(path, Cast::live(path, cols as usize, rows as usize)));
+    let mut stdout = BufWriter::with_capacity(1024 * 64, Recorder::new(stdout(), cast));
+
+    terminal::enable_raw_mode()?;
+    execute!(stdout, EnterAlternateScreen, Hide, Clear(ClearType::All), EnableMouseCapture, EnableFocusChange)?;
+    // Probing reads the terminal's replies, so it has to happen in raw mode
+    let mut graphics = options.graphics.unwrap_or_else(Graphics::detect);
+    // From inside tmux, images only reach the terminal through passthrough, at the pane's position
+    let in_tmux = env::var_os("TMUX").is_some();
+    let tmux_pane = if in_tmux && graphics != Graphics::Cells { tmux_pane_origin() } else { None };
+    if in_tmux && tmux_pane.is_none() {
+        graphics = Graphics::Cells;
+    }
+
+    let pixel_mode = get_pixel_mode();
+    let (cell_w, cell_h) = pixel_mode.cell_size();
+
+    // Tweaks made in the panel join the startup settings so they survive resizes and effect switches
+    let mut settings = options.effect_settings.clone();
+    let mut info = playlist.current();
+    let frame_interval = Duration::from_secs_f32(1.0 / options.fps);
+    let mut quality = match options.quality {
+        Some(fixed) => QualityController::fixed(fixed),
+        None => QualityController::adaptive(frame_interval),
+    };
+    let mut effect = create_effect(info, cols as usize * cell_w, rows as usize * cell_h, &settings, rng);
+    effect.set_quality(quality.quality());
+    let mut canvas = Canvas::new(cols as usize, rows as usize, (cell_w, cell_h));
+    let mut renderer = Renderer::new(options.color_depth, pixel_mode, graphics);
+    let (cell_px_w, cell_px_h) = cell_pixel_size();
+    renderer.set_cell_pixels(cell_px_w, cell_px_h);
+    renderer.set_synchronized(true);
+    renderer.set_tmux_pane(tmux_pane);
 
+    let mut focused = true;
+    let mut next_frame = Instant::now();
+    let mut stats = FrameStats::new(frame_interval);
     let mut last_frame = Instant::now();
     let mut accumulator = 0.0f32;
-    const FIXED_DT: f32 = 1.0 / 60.0;
+    let mut effect_started = Instant::now();
+    let mut transition: Option<Transition> = None;
+    let mut paused: Option<Instant> = None; // Since when, so the playlist clock can skip the pause
+    let mut show_help = false;
+    let mut overlays = create_overlays(options);
+    let mut tweak: Option<TweakPanel> = None;
+    let mut lock = options.lock.clone().map(Lock::new);
+    if lock.is_some() {
+        lock::ignore_signals();
+    }
 
-    loop {
-        if event::poll(Duration::from_millis(1))? {
+    // A frame reads input and commands, steps the simulation and draws; true means time to exit
+    let locked = lock.is_some();
+    let mut frame = |recovering: bool| -> std::io::Result<bool> {
+        if recovering {
+            // Whatever failed may have left the effect or the screen half-done
+            effect = create_effect(info, canvas.width(), canvas.height(), &settings, rng);
+            effect.set_quality(quality.quality());
+            transition = None;
+            tweak = None;
+            show_help = false;
+            execute!(stdout, Clear(ClearType::All))?;
+            renderer.invalidate();
+        }
+
+        let mut switch = paused.is_none() && playlist.interval().is_some_and(|interval| effect_started.elapsed() >= interval);
+        let mut switch_to: Option<&'static EffectInfo> = None; // A particular effect, asked for over the control socket
+
+        // Sleep until the next frame is due, waking early only to handle input
+        while event::poll(next_frame.saturating_duration_since(Instant::now()))? {
             let event = event::read()?;
-            match &event {
-                Event::Key(key_event) => {
-                    if key_event.code == KeyCode::Char('q')
-                        || key_event.code == KeyCode::Esc
-                        || (key_event.code == KeyCode::Char('c')
-                            && key_event.modifiers.contains(event::KeyModifiers::CONTROL))
-                    {
-                        break;
+            // While locked every key goes to the passphrase prompt, exit keys included
+            if let (Some(lock), Event::Key(key)) = (&mut lock, &event) {
+                if lock.handle_key(key) {
+                    return Ok(true);
+                }
+                continue;
+            }
+            // Started by `watch`, the saver makes way for the shell at the first key or click
+            if options.exit_on_input
+                && lock.is_none()
+                && (matches!(event, Event::Key(_))
+                    || matches!(&event, Event::Mouse(mouse) if matches!(mouse.kind, MouseEventKind::Down(_))))
+            {
+                return Ok(true);
+            }
+            // The tweak panel gets the first look at input while it is open
+            let response = match &mut tweak {
+                Some(panel) => panel.handle_event(&event, info, effect.as_mut()),
+                None => tweak::Response::Ignored,
+            };
+            match response {
+                tweak::Response::Handled => {}
+                tweak::Response::Close => tweak = None,
+                tweak::Response::Changed(param, value) => {
+                    settings.retain(|s| !(s.effect == info.name && s.param == param));
+                    settings.push(EffectSetting {
+                        effect: info.name,
+                        param: param.to_string(),
+                        value,
+                    });
+                }
+                tweak::Response::Save => {
+                    let message = match &options.config_path {
+                        Some(path) => match tweak::save(path, info, &info.params(effect.as_ref())) {
+                            Ok(()) => format!("Saved to {}", path.display()),
+                            Err(message) => message,
+                        },
+                        None => "Nowhere to save: HOME is not set, use --config".to_string(),
+                    };
+                    if let Some(panel) = &mut tweak {
+                        panel.set_message(message);
                     }
-                    // Pass non-exit key events to the effect
-                    effect.handle_event(&event);
                 }
-                Event::Resize(cols, rows) => {
-                    effect = E::new(*cols as usize, *rows as usize * 2);
-                    execute!(stdout, Clear(ClearType::All))?;
+                tweak::Response::Ignored => match &event {
+                    Event::Key(key_event) => {
+                        if key_event.code == KeyCode::Char('q')
+                            || key_event.code == KeyCode::Esc
+                            || (key_event.code == KeyCode::Char('c')
+                                && key_event.modifiers.contains(event::KeyModifiers::CONTROL))
+                        {
+                            return Ok(true);
+                        }
+                        if key_event.code == KeyCode::Char('?') || key_event.code == KeyCode::F(1) {
+                            show_help = !show_help;
+                            tweak = None;
+                        } else if key_event.code == KeyCode::Tab {
+                            tweak = Some(TweakPanel::new());
+                            show_help = false;
+                        } else if key_event.code == KeyCode::Char('n') && playlist.len() > 1 {
+                            switch = true;
+                        } else {
+                            // Pass non-exit key events to the effect
+                            effect.handle_event(&event);
+                        }
+                    }
+                    Event::Resize(cols, rows) => {
+                        if let Some(cast) = stdout.get_mut().cast_mut() {
+                            cast.resize(*cols as usize, *rows as usize)?;
+                        }
+                        let (width, height) = effects::effect_size(*cols as usize * cell_w, *rows as usize * cell_h);
+                        effect.resize(width, height);
+                        canvas = Canvas::new(*cols as usize, *rows as usize, (cell_w, cell_h));
+                        transition = None;
+                        execute!(stdout, Clear(ClearType::All))?;
+                        let (cell_px_w, cell_px_h) = cell_pixel_size();
+                        renderer.set_cell_pixels(cell_px_w, cell_px_h);
+                        if tmux_pane.is_some() {
+                            // A resize usually means the pane layout changed too
+                            renderer.set_tmux_pane(tmux_pane_origin().or(tmux_pane));
+                        }
+                        renderer.invalidate();
+                    }
+                    Event::FocusLost => focused = false,
+                    Event::FocusGained => focused = true,
+                    _ => {
+                        effect.handle_event(&event);
+                    }
+                },
+            }
+        }
+
+        // Commands from the control socket apply between frames, like keys
+        let mut quit = false;
+        for request in control.as_mut().map(ControlServer::poll).unwrap_or_default() {
+            let result = match &request.command {
+                Err(message) => Err(message.clone()),
+                Ok(Command::Effect(next)) => {
+                    switch_to = Some(*next);
+                    Ok(String::new())
                 }
-                _ => {
-                    effect.handle_event(&event);
+                Ok(Command::Next) if playlist.len() > 1 => {
+                    switch_to = Some(playlist.advance());
+                    Ok(String::new())
                 }
+                Ok(Command::Next) => Err("the playlist has only one effect".to_string()),
+                Ok(Command::Set(text)) => parse_setting(text).map(|setting| {
+                    if setting.effect == info.name {
+                        // Checked by parse_setting against the declared parameter
+                        let _ = effects::apply_param(info, effect.as_mut(), &setting.param, setting.value.clone());
+                    }
+                    settings.retain(|s| !(s.effect == setting.effect && s.param == setting.param));
+                    settings.push(setting);
+                    String::new()
+                }),
+                Ok(Command::Message(text)) => {
+                    overlays.set_message(text.as_deref());
+                    Ok(String::new())
+                }
+                Ok(Command::Pause) => {
+                    paused.get_or_insert_with(Instant::now);
+                    Ok(String::new())
+                }
+                Ok(Command::Resume) => {
+                    if let Some(since) = paused.take() {
+                        effect_started += since.elapsed();
+                    }
+                    Ok(String::new())
+                }
+                Ok(Command::Status) => Ok(format!(
+                    "effect={} paused={} locked={}",
+                    switch_to.unwrap_or(info).name,
+                    paused.is_some(),
+                    lock.is_some()
+                )),
+                // The passphrase is the only way out of a locked screensaver
+                Ok(Command::Quit) if lock.is_some() => Err("locked; only the passphrase ends the screensaver".to_string()),
+                Ok(Command::Quit) => {
+                    quit = true;
+                    Ok(String::new())
+                }
+            };
+            request.respond(result);
+        }
+        if quit {
+            return Ok(true);
+        }
+
+        if switch && switch_to.is_none() {
+            switch_to = Some(playlist.advance());
+        }
+        if let Some(next) = switch_to {
+            // Stay in the alternate screen; the renderer only repaints what differs
+            info = next;
+            let outgoing = std::mem::replace(&mut effect, create_effect(info, canvas.width(), canvas.height(), &settings, rng));
+            effect.set_quality(quality.quality());
+            let kind = options
+                .transition
+                .unwrap_or_else(|| TransitionKind::ALL[rng.usize(..TransitionKind::ALL.len())]);
+            transition = Some(Transition::new(kind, outgoing, &canvas, options.transition_time, rng.fork()));
+            effect_started = Instant::now();
+            // The new effect's time starts now, not when the pause began
+            if paused.is_some() {
+                paused = Some(effect_started);
             }
         }
 
         let now = Instant::now();
+        // A slow frame pushes the schedule back rather than causing a burst of catch-up frames
+        // Out of focus the screen may still show, so slow down rather than stop
+        let interval = if focused { frame_interval } else { frame_interval.max(Duration::from_secs_f32(1.0 / UNFOCUSED_FPS)) };
+        next_frame = (next_frame + interval).max(now);
         let frame_time = now.duration_since(last_frame).as_secs_f32();
         last_frame = now;
 
-        accumulator += frame_time;
-        if accumulator > FIXED_DT * 3.0 {
-            accumulator = FIXED_DT * 3.0;
+        // The simulation runs at a fixed 60 Hz whatever the frame rate, within limits after a stall
+        accumulator = (accumulator + frame_time).min(MAX_CATCH_UP);
+        if paused.is_some() {
+            accumulator = 0.0;
         }
 
         while accumulator >= FIXED_DT {
             effect.update(FIXED_DT);
+            if let Some(transition) = &mut transition {
+                transition.update(FIXED_DT);
+            }
             accumulator -= FIXED_DT;
         }
 
-        effect.render(&mut stdout)?;
+        canvas.clear_text();
+        effect.render(&mut canvas);
+        if let Some(active) = &mut transition {
+            active.composite(&mut canvas);
+            if active.finished() {
+                transition = None;
+            }
+        }
+        overlays.draw(&mut canvas);
+        if show_help {
+            help::draw(&mut canvas, info, &info.params(effect.as_ref()));
+        }
+        if let Some(panel) = &mut tweak {
+            panel.draw(&mut canvas, info, &info.params(effect.as_ref()));
+        }
+        if let Some(lock) = &mut lock {
+            lock.draw(&mut canvas);
+        }
+        renderer.draw(&canvas, &mut stdout)?;
+        let work = now.elapsed();
+        stats.record(work);
+        if let Some(level) = quality.observe(work) {
+            effect.set_quality(level);
+        }
+        Ok(false)
+    };
+
+    let mut failures = 0;
+    loop {
+        match panic::catch_unwind(AssertUnwindSafe(|| frame(failures > 0))) {
+            Ok(Ok(true)) => break,
+            Ok(Ok(false)) => failures = 0,
+            Ok(Err(err)) if !locked => return Err(err),
+            Err(payload) if !locked => panic::resume_unwind(payload),
+            // A locked screensaver must not fail open: keep the screen up, and never hand back the shell
+            _ => {
+                failures += 1;
+                if failures > LOCKED_RETRIES {
+                    std::process::exit(1);
+                }
+            }
+        }
     }
 
-    execute!(stdout, Show, LeaveAlternateScreen, DisableMouseCapture)?;
+    renderer.finish(&mut stdout)?;
+    execute!(stdout, Show, LeaveAlternateScreen, DisableMouseCapture, DisableFocusChange)?;
     terminal::disable_raw_mode()?;
 
+    if options.show_fps {
+        eprint!("{}", stats.report());
+        let mode = if quality.is_adaptive() { "adaptive" } else { "fixed" };
+        eprintln!("quality: {:.2} ({})", quality.quality(), mode);
+    }
+
     Ok(())
 }
 
+/// Where the current tmux pane's top-left cell is on the terminal tmux runs in.
+fn tmux_pane_origin() -> Option<(usize, usize)> {
+    let pane = env::var("TMUX_PANE").ok()?;
+    let output = std::process::Command::new("tmux")
+        .args(["display-message", "-p", "-t", &pane, "#{pane_left} #{pane_top} #{status} #{status-position}"])
+        .output()
+        .ok()?;
+    let text = String::from_utf8(output.stdout).ok()?;
+    let fields: Vec<&str> = text.split_whitespace().collect();
+    let [left, top, status, position] = fields[..] else {
+        return None;
+    };
+    let (left, mut top): (usize, usize) = (left.parse().ok()?, top.parse().ok()?);
+    // Pane positions leave out a status line at the top
+    if position == "top" {
+        top += match status {
+            "on" => 1,
+            "off" => 0,
+            lines => lines.parse().unwrap_or(0),
+        };
+    }
+    Some((left, top))
+}
+
+/// Send a command to the screensaver listening at `path`, or one per line
+/// from stdin if none was given, and exit with 1 if any failed.
+fn run_ctl(path: &Path, words: &[String]) -> ! {
+    let commands = if words.is_empty() {
+        std::io::stdin().lines().map_while(Result::ok).collect()
+    } else {
+        vec![control::join_words(words)]
+    };
+    match control::send(path, &commands) {
+        Ok(true) => std::process::exit(0),
+        Ok(false) => std::process::exit(1),
+        Err(err) => {
+            eprintln!("Cannot reach {}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Print tmux settings that run termsaver in idle panes and as the lock screen.
+fn print_tmux_config(effect: Option<&str>, idle: Duration) {
+    let exe = env::current_exe().map(|path| path.display().to_string()).unwrap_or_else(|_| "termsaver".to_string());
+    let effect = effect.map(|name| format!(" {}", name)).unwrap_or_default();
+    println!("# termsaver in idle panes and as the lock screen. Add to ~/.tmux.conf,");
+    println!("# or load it now with: termsaver tmux | tmux source-file -");
+    println!("set -g focus-events on");
+    println!("# The lock screen only starts once a passphrase is set with: termsaver passphrase");
+    println!("set -g lock-command \"'{}'{} --tmux-lock\"", exe, effect);
+    println!("# set -g lock-after-time 900");
+    println!("# New panes run their shell under termsaver watch, which starts the screensaver");
+    println!("# once the pane has had no input for {}s", idle.as_secs());
+    // A server started from a watched shell would pass TERMSAVER_WATCH on, and watch refuses to nest
+    println!("set-environment -gu {}", watch::ENV_VAR);
+    println!("set -g default-command \"'{}' watch{} --idle {}s\"", exe, effect, idle.as_secs());
+}
+
+/// Ask for a new `--lock` passphrase twice and save its hash to the config file.
+fn set_passphrase(path: &Path) {
+    let read = |prompt: &str| {
+        lock::read_hidden(prompt).unwrap_or_else(|err| {
+            eprintln!("Cannot read passphrase: {}", err);
+            std::process::exit(1);
+        })
+    };
+    let passphrase = read("New passphrase: ");
+    if passphrase.is_empty() {
+        eprintln!("The passphrase cannot be empty");
+        std::process::exit(1);
+    }
+    if read("Repeat passphrase: ") != passphrase {
+        eprintln!("The passphrases do not match");
+        std::process::exit(1);
+    }
+    let result = lock::hash(&passphrase)
+        .and_then(|hash| config::update_section(path, &["lock"], &[("passphrase_hash", config::quote(&hash))]));
+    match result {
+        Ok(()) => eprintln!("Saved to {}", path.display()),
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(1);
+        }
+    }
+}
+
 fn parse_hex_color(hex: &str) -> Option<(u8, u8, u8)> {
     let hex = hex.trim_start_matches('#');
     if hex.len() != 6 {
@@ -117,70 +659,562 @@ fn parse_hex_color(hex: &str) -> Option<(u8, u8, u8)> {
     Some((r, g, b))
 }
 
+fn parse_quality(text: &str) -> Option<f32> {
+    let quality: f32 = text.parse().ok()?;
+    (quality::MIN_QUALITY..=1.0).contains(&quality).then_some(quality)
+}
+
+fn parse_size(size: &str) -> Option<(usize, usize)> {
+    let (cols, rows) = size.split_once('x')?;
+    let cols: usize = cols.parse().ok()?;
+    let rows: usize = rows.parse().ok()?;
+    if cols == 0 || rows == 0 {
+        return None;
+    }
+    Some((cols, rows))
+}
+
 fn main() -> std::io::Result<()> {
     let args: Vec<String> = env::args().collect();
 
-    let mut effect_name = "fire";
-    let mut bg_color: Option<(u8, u8, u8)> = None;
+    let cli_args: Vec<Arg> = cli::Args::new(&args).collect::<Result<_, _>>().unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        std::process::exit(1);
+    });
+
+    // The config file only provides defaults; the flags parsed below override it.
+    // Of --config and --no-config, the last one given wins
+    let mut config_path = config::default_path();
+    let mut config_required = false;
+    let mut use_config = true;
+    for arg in &cli_args {
+        match *arg {
+            Flag("--config", Some(path)) => {
+                config_path = Some(PathBuf::from(path));
+                // `passphrase` may be what creates the file
+                config_required = args[1] != "passphrase";
+                use_config = true;
+            }
+            Flag("--no-config", _) => use_config = false,
+            _ => {}
+        }
+    }
+    let save_path = config_path.clone();
+    let settings = match config_path {
+        Some(path) if use_config && (config_required || path.exists()) => {
+            config::load_settings(&path).unwrap_or_else(|message| {
+                eprintln!("{}", message);
+                std::process::exit(1);
+            })
+        }
+        _ => config::Settings::default(),
+    };
+    let config::Settings {
+        effect: mut effect_name,
+        mut bg_color,
+        mut color_depth,
+        mut pixel_mode,
+        mut graphics,
+        mut fps,
+        mut quality,
+        playlist: mut playlist_effects,
+        mut shuffle,
+        mut interval,
+        mut transition,
+        mut transition_time,
+        mut idle,
+        mut overlays,
+        mut message,
+        mut message_file,
+        mut proc_root,
+        mut disk,
+        mut control_socket,
+        lock_hash,
+        mut effect_settings,
+    } = settings;
+
+    let mut headless: Option<(usize, usize)> = None;
+    let mut frames = 60;
+    let mut duration: Option<f32> = None;
+    let mut export_mode = false;
+    let mut export_out: Option<PathBuf> = None;
+    let mut bench_mode = false;
+    let mut watch_mode = false;
+    let mut passphrase_mode = false;
+    let mut tmux_mode = false;
+    let mut ctl_mode = false;
+    let mut ctl_words: Vec<String> = Vec::new(); // The command for `ctl` to send, as given
+    let mut tmux_lock = false;
+    let mut lock_requested = false;
+    let mut bench_effects: Vec<&'static EffectInfo> = Vec::new();
+    let mut json = false;
+    let mut size: Option<(usize, usize)> = None; // Pixels for export, cells for bench
+    let mut record: Option<PathBuf> = None;
+    let mut cli_overlays = false;
+    let mut seed: Option<u64> = None;
+    let mut show_seed = false;
+    let mut show_fps = false;
 
     // Parse arguments
-    let mut i = 1;
-    while i < args.len() {
-        match args[i].as_str() {
-            "--bg-color" => {
-                if i + 1 < args.len() {
-                    if let Some(color) = parse_hex_color(&args[i + 1]) {
-                        bg_color = Some(color);
-                        i += 2;
-                    } else {
-                        eprintln!("Invalid hex color: {}", args[i + 1]);
-                        eprintln!("Expected format: RRGGBB (e.g., 1a1b26)");
+    let mut cli_effect = false;
+    let mut cli_playlist = false;
+    for (index, arg) in cli_args.into_iter().enumerate() {
+        match arg {
+            // Once `ctl` has its command word, the rest belongs to the command, dashes and all
+            Word(word) if !ctl_words.is_empty() => ctl_words.push(word.to_string()),
+            Flag("--bg-color", Some(value)) => {
+                if let Some(color) = parse_hex_color(value) {
+                    bg_color = Some(color);
+                } else {
+                    eprintln!("Invalid hex color: {}", value);
+                    eprintln!("Expected format: RRGGBB (e.g., 1a1b26)");
+                    std::process::exit(1);
+                }
+            }
+            Flag("--colors", Some(value)) => {
+                if let Some(depth) = ColorDepth::parse(value) {
+                    color_depth = Some(depth);
+                } else {
+                    eprintln!("Invalid color depth: {}", value);
+                    eprintln!("Expected one of: truecolor, 256, 16");
+                    std::process::exit(1);
+                }
+            }
+            Flag("--pixels", Some(value)) => {
+                if let Some(mode) = PixelMode::parse(value) {
+                    pixel_mode = Some(mode);
+                } else {
+                    eprintln!("Invalid pixel mode: {}", value);
+                    eprintln!("Expected one of: half, quadrant, sextant, braille");
+                    std::process::exit(1);
+                }
+            }
+            Flag("--graphics", Some(value)) => {
+                if value == "auto" {
+                    graphics = None;
+                } else if let Some(mode) = Graphics::parse(value) {
+                    graphics = Some(mode);
+                } else {
+                    eprintln!("Invalid graphics mode: {}", value);
+                    eprintln!("Expected one of: auto, kitty, sixel, cells");
+                    std::process::exit(1);
+                }
+            }
+            Flag("--headless", Some(value)) => {
+                if let Some(size) = parse_size(value) {
+                    headless = Some(size);
+                } else {
+                    eprintln!("Invalid size: {}", value);
+                    eprintln!("Expected format: COLSxROWS (e.g., 80x24)");
+                    std::process::exit(1);
+                }
+            }
+            Flag("--frames", Some(value)) => {
+                if let Ok(n) = value.parse() {
+                    frames = n;
+                } else {
+                    eprintln!("Invalid frame count: {}", value);
+                    std::process::exit(1);
+                }
+            }
+            Flag("--duration", Some(value)) => {
+                if let Some(length) = playlist::parse_interval(value) {
+                    duration = Some(length.as_secs_f32());
+                } else {
+                    eprintln!("Invalid duration: {}", value);
+                    eprintln!("Expected a duration like 10s or 1m");
+                    std::process::exit(1);
+                }
+            }
+            Flag("--size", Some(value)) => {
+                if let Some(parsed) = parse_size(value) {
+                    size = Some(parsed);
+                } else {
+                    eprintln!("Invalid size: {}", value);
+                    eprintln!("Expected format: WIDTHxHEIGHT (e.g., 320x180)");
+                    std::process::exit(1);
+                }
+            }
+            Flag("--out", Some(value)) => {
+                export_out = Some(PathBuf::from(value));
+            }
+            Word("export") if index == 0 => {
+                export_mode = true;
+            }
+            Word("bench") if index == 0 => {
+                bench_mode = true;
+            }
+            Word("watch") if index == 0 => {
+                watch_mode = true;
+            }
+            Word("passphrase") if index == 0 => {
+                passphrase_mode = true;
+            }
+            Word("tmux") if index == 0 => {
+                tmux_mode = true;
+            }
+            Word("ctl") if index == 0 => {
+                ctl_mode = true;
+            }
+            Flag("--tmux-lock", None) => {
+                tmux_lock = true;
+            }
+            Flag("--lock", None) => {
+                lock_requested = true;
+            }
+            Flag("--idle", Some(value)) => {
+                if let Some(duration) = playlist::parse_interval(value) {
+                    idle = duration;
+                } else {
+                    eprintln!("Invalid idle time: {}", value);
+                    eprintln!("Expected a duration like 90s, 5m or 1h");
+                    std::process::exit(1);
+                }
+            }
+            Flag("--json", None) => {
+                json = true;
+            }
+            Flag("--overlay", Some(value)) => {
+                match Overlay::parse(value) {
+                    Ok(overlay) => {
+                        // Overlays on the command line replace those from the config file
+                        if !cli_overlays {
+                            overlays.clear();
+                            cli_overlays = true;
+                        }
+                        overlays.push(overlay);
+                    }
+                    Err(message) => {
+                        eprintln!("Invalid overlay {}: {}", value, message);
                         std::process::exit(1);
                     }
+                }
+            }
+            Flag("--message", Some(value)) => {
+                message = Some(value.to_string());
+            }
+            Flag("--message-file", Some(value)) => {
+                message_file = Some(PathBuf::from(value));
+            }
+            Flag("--proc-root", Some(value)) => {
+                proc_root = PathBuf::from(value);
+            }
+            Flag("--disk", Some(value)) => {
+                disk = PathBuf::from(value);
+            }
+            Flag("--control-socket", Some(value)) => {
+                control_socket = Some(PathBuf::from(value));
+            }
+            Flag("--record", Some(value)) => {
+                record = Some(PathBuf::from(value));
+            }
+            Flag("--playlist", Some(value)) => {
+                let mut list = Vec::new();
+                for name in value.split(',').map(|s| s.trim()) {
+                    match effects::lookup(name) {
+                        Some(info) => list.push(info),
+                        None => {
+                            eprintln!("Unknown effect in playlist: {}", name);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                playlist_effects = Some(list);
+                cli_playlist = true;
+            }
+            Flag("--shuffle", None) => {
+                shuffle = true;
+                cli_playlist = true;
+            }
+            Flag("--interval", Some(value)) => {
+                if let Some(duration) = playlist::parse_interval(value) {
+                    interval = duration;
                 } else {
-                    eprintln!("--bg-color requires a hex color value");
+                    eprintln!("Invalid interval: {}", value);
+                    eprintln!("Expected a duration like 90s, 5m or 1h");
                     std::process::exit(1);
                 }
             }
-            "help" | "--help" | "-h" => {
-                print_usage();
-                return Ok(());
+            Flag("--transition", Some(value)) => {
+                if value == "random" {
+                    transition = None;
+                } else if let Some(kind) = TransitionKind::parse(value) {
+                    transition = Some(kind);
+                } else {
+                    eprintln!("Invalid transition: {}", value);
+                    eprintln!("Expected one of: crossfade, wipe, dissolve, burn, cut, random");
+                    std::process::exit(1);
+                }
+            }
+            Flag("--transition-time", Some(value)) => {
+                if let Some(duration) = playlist::parse_interval(value) {
+                    transition_time = duration.as_secs_f32();
+                } else {
+                    eprintln!("Invalid transition time: {}", value);
+                    eprintln!("Expected a duration like 0.5 or 2s");
+                    std::process::exit(1);
+                }
+            }
+            Flag("--seed", Some(value)) => {
+                if let Ok(n) = value.parse() {
+                    seed = Some(n);
+                } else {
+                    eprintln!("Invalid seed: {}", value);
+                    eprintln!("Expected a non-negative integer");
+                    std::process::exit(1);
+                }
             }
-            arg => {
-                if !arg.starts_with('-') {
-                    effect_name = arg;
-                    i += 1;
+            Flag("--set", Some(value)) => {
+                match parse_setting(value) {
+                    Ok(setting) => {
+                        effect_settings.push(setting);
+                    }
+                    Err(message) => {
+                        eprintln!("Invalid setting {}: {}", value, message);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            Flag("--fps", Some(value)) => {
+                match value.parse::<f32>() {
+                    Ok(rate) if FPS_RANGE.contains(&rate) => {
+                        fps = rate;
+                    }
+                    _ => {
+                        eprintln!("Invalid frame rate: {}", value);
+                        eprintln!("Expected 1 to 240 frames per second");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            Flag("--quality", Some(value)) => {
+                if value == "auto" {
+                    quality = None;
+                } else if let Some(level) = parse_quality(value) {
+                    quality = Some(level);
                 } else {
-                    eprintln!("Unknown option: {}", arg);
-                    eprintln!();
-                    print_usage();
+                    eprintln!("Invalid quality: {}", value);
+                    eprintln!("Expected auto or a number from 0.25 to 1");
+                    std::process::exit(1);
+                }
+            }
+            Flag("--show-fps", None) => {
+                show_fps = true;
+            }
+            Flag("--show-seed", None) => {
+                show_seed = true;
+            }
+            Flag("--list", None) => {
+                cli::print_list();
+                return Ok(());
+            }
+            Flag("--completions", Some(value)) => {
+                if !cli::print_completions(value) {
+                    eprintln!("Unknown shell: {}", value);
+                    eprintln!("Expected one of: bash, zsh, fish");
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+            // Already read above, to load the config file before the rest
+            Flag("--config" | "--no-config", _) => {}
+            Word("help") | Flag("--help" | "-h", _) => {
+                cli::print_usage();
+                return Ok(());
+            }
+            Word(word) if ctl_mode => ctl_words.push(word.to_string()),
+            Word(word) if bench_mode => match effects::lookup(word) {
+                Some(info) => bench_effects.push(info),
+                None => {
+                    eprintln!("Unknown effect: {}", word);
                     std::process::exit(1);
                 }
+            },
+            Word(word) => {
+                effect_name = word.to_string();
+                cli_effect = true;
+            }
+            Flag(flag, _) => {
+                eprintln!("Unknown option: {}", flag);
+                eprintln!();
+                cli::print_usage();
+                std::process::exit(1);
             }
         }
     }
 
+    let export = if export_mode {
+        let Some(out) = export_out else {
+            eprintln!("export requires --out PATH");
+            std::process::exit(1);
+        };
+        let Some(format) = ImageFormat::from_path(&out) else {
+            eprintln!("Unknown image format: {}", out.display());
+            eprintln!("Expected a path ending in .gif, .png or .ppm");
+            std::process::exit(1);
+        };
+        let size = size.unwrap_or((320, 180));
+        if format == ImageFormat::Gif && (size.0 > export::gif::MAX_SIZE || size.1 > export::gif::MAX_SIZE) {
+            eprintln!("GIF images can be at most {}x{} pixels", export::gif::MAX_SIZE, export::gif::MAX_SIZE);
+            std::process::exit(1);
+        }
+        Some(Export {
+            size,
+            out,
+            format,
+        })
+    } else {
+        None
+    };
+
+    let bench = if bench_mode {
+        // No names means every effect
+        if bench_effects.is_empty() {
+            bench_effects = effects::REGISTRY.iter().collect();
+        }
+        Some(Bench {
+            effects: bench_effects,
+            size: size.unwrap_or((200, 60)),
+            json,
+        })
+    } else {
+        None
+    };
+
+    if passphrase_mode {
+        let Some(path) = &save_path else {
+            eprintln!("Nowhere to save: HOME is not set, use --config");
+            std::process::exit(1);
+        };
+        set_passphrase(path);
+        return Ok(());
+    }
+    if ctl_mode {
+        let Some(path) = &control_socket else {
+            eprintln!("ctl needs --control-socket PATH, or control_socket in the config file");
+            std::process::exit(1);
+        };
+        run_ctl(path, &ctl_words);
+    }
+    if tmux_mode {
+        print_tmux_config(cli_effect.then_some(effect_name.as_str()), idle);
+        return Ok(());
+    }
+    // A lock screen any key dismisses would only look locked, so both need a passphrase
+    let lock = if lock_requested || tmux_lock {
+        if lock_hash.is_none() {
+            let flag = if tmux_lock { "--tmux-lock" } else { "--lock" };
+            eprintln!("{} needs a passphrase; set one with: termsaver passphrase", flag);
+            std::process::exit(1);
+        }
+        lock_hash
+    } else {
+        None
+    };
+
+    let watch = if watch_mode {
+        if env::var_os(watch::ENV_VAR).is_some() {
+            eprintln!("Already running inside termsaver watch");
+            std::process::exit(1);
+        }
+        if headless.is_some() || record.is_some() {
+            eprintln!("watch cannot be used with --headless or --record");
+            std::process::exit(1);
+        }
+        Some(idle)
+    } else {
+        None
+    };
+
+    // Exports step at the frame rate, headless runs at the simulation rate
+    if let Some(seconds) = duration {
+        let rate = if export.is_some() { fps } else { 1.0 / FIXED_DT };
+        frames = ((seconds * rate).round() as usize).max(1);
+    }
+
+    if let Some(path) = &message_file
+        && let Err(err) = std::fs::read_to_string(path)
+    {
+        eprintln!("Cannot read message file {}: {}", path.display(), err);
+        std::process::exit(1);
+    }
+    if overlays.iter().any(|o| o.kind == OverlayKind::System) {
+        if !proc_root.is_dir() {
+            eprintln!("Cannot read system stats: {} is not a directory", proc_root.display());
+            std::process::exit(1);
+        }
+        if let Err(err) = std::fs::metadata(&disk) {
+            eprintln!("Cannot show disk usage for {}: {}", disk.display(), err);
+            std::process::exit(1);
+        }
+    }
+    // A message needs somewhere to be shown
+    if (message.is_some() || message_file.is_some()) && !overlays.iter().any(|o| o.kind == OverlayKind::Message) {
+        overlays.push(Overlay::new(OverlayKind::Message));
+    }
+
+    // Naming an effect on the command line replaces a playlist from the config file
+    if cli_effect && !cli_playlist {
+        playlist_effects = None;
+        shuffle = false;
+    }
+
     // Set background color if provided
     if let Some(color) = bg_color {
         let _ = BG_COLOR.set(color);
     }
+    if let Some(mode) = pixel_mode {
+        let _ = PIXEL_MODE.set(mode);
+    }
 
-    match effect_name {
-        "fire" => run_effect::<effects::fire::FireEffect>(),
-        "thunder" => run_effect::<effects::thunder::ThunderEffect>(),
-        "plasma" => run_effect::<effects::plasma::PlasmaEffect>(),
-        "fireworks" => run_effect::<effects::fireworks::FireworksEffect>(),
-        "lavalamp" => run_effect::<effects::lavalamp::LavaLampEffect>(),
-        "aurora" => run_effect::<effects::aurora::AuroraEffect>(),
-        "clouds" => run_effect::<effects::clouds::CloudEffect>(),
-        "biolum" => run_effect::<effects::bioluminescence::BioluminescenceEffect>(),
-        "star" => run_effect::<effects::star::StarEffect>(),
-        "gol" => run_effect::<effects::gameoflife::GameOfLifeEffect>(),
-        _ => {
-            eprintln!("Unknown effect: {}", effect_name);
-            eprintln!();
-            print_usage();
-            std::process::exit(1);
-        }
+    // The playlist, each effect and each transition get their own generator forked
+    // from this one, so a seed replays the same show
+    let seed = seed.unwrap_or_else(|| fastrand::u64(..));
+    let mut rng = Rng::with_seed(seed);
+    // Printed now rather than at exit, which watch, ctl and a killed session never reach
+    if show_seed {
+        eprintln!("seed: {}", seed);
     }
+
+    let options = Options {
+        color_depth: color_depth.unwrap_or_else(ColorDepth::detect),
+        graphics,
+        headless,
+        export,
+        bench,
+        watch,
+        exit_on_input: watch_mode,
+        frames,
+        transition,
+        transition_time,
+        fps,
+        show_fps,
+        quality,
+        effect_settings,
+        config_path: save_path,
+        record,
+        overlays,
+        message,
+        message_file,
+        lock,
+        proc_root,
+        disk,
+        control_socket,
+    };
+
+    let Some(effect) = effects::lookup(&effect_name) else {
+        eprintln!("Unknown effect: {}", effect_name);
+        eprintln!();
+        cli::print_usage();
+        std::process::exit(1);
+    };
+
+    // A single effect is just a playlist that never advances
+    let playlist = match playlist_effects {
+        Some(list) => Playlist::new(list, shuffle, Some(interval), rng.fork()),
+        None if shuffle => Playlist::new(effects::REGISTRY.iter().collect(), true, Some(interval), rng.fork()),
+        None => Playlist::new(vec![effect], false, None, rng.fork()),
+    };
+
+    run(&options, playlist, rng)
 }
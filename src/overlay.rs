@@ -0,0 +1,385 @@
+use crate::effects::Canvas;
+use crate::sysstats::SystemStats;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+const DEFAULT_COLOR: (u8, u8, u8) = (235, 235, 235);
+const DARK: (u8, u8, u8) = (0, 0, 0);
+const SHADE_ALPHA: f32 = 0.6; // How much text shadows darken the effect behind them
+const FILE_CHECK: Duration = Duration::from_secs(2); // How often the message file is checked for changes
+const CLOCK_WIDTH: f32 = 0.5; // Fraction of the screen width the clock may take up
+
+// 3x5 block digits, one row per entry, most significant of the three bits on the left
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+const COLON: [u8; 5] = [0b0, 0b1, 0b0, 0b1, 0b0];
+const GLYPH_HEIGHT: usize = 5;
+
+const WEEKDAYS: [&str; 7] = ["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday"];
+const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November", "December",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum OverlayKind {
+    Clock,
+    Date,
+    Message,
+    Hostname,
+    System, // Load, memory, network and disk usage
+}
+
+/// Where on the screen an overlay sits. Overlays sharing a spot are stacked.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+/// What keeps an overlay readable over bright effects.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Style {
+    Plain,
+    Shadow,  // Darker copy offset down and right; for text, a darkened band behind it
+    Outline, // Dark border all the way round; for text, a darkened box with a margin
+}
+
+#[derive(Clone, Copy)]
+pub struct Overlay {
+    pub kind: OverlayKind,
+    anchor: Anchor,
+    color: (u8, u8, u8),
+    style: Style,
+}
+
+impl Anchor {
+    const NAMES: [(&'static str, Anchor); 9] = [
+        ("top-left", Anchor::TopLeft),
+        ("top", Anchor::Top),
+        ("top-right", Anchor::TopRight),
+        ("left", Anchor::Left),
+        ("center", Anchor::Center),
+        ("right", Anchor::Right),
+        ("bottom-left", Anchor::BottomLeft),
+        ("bottom", Anchor::Bottom),
+        ("bottom-right", Anchor::BottomRight),
+    ];
+
+    /// Horizontal and vertical placement: 0 = start, 1 = middle, 2 = end.
+    fn alignment(self) -> (usize, usize) {
+        let index = Self::NAMES.iter().position(|(_, anchor)| *anchor == self).unwrap();
+        (index % 3, index / 3)
+    }
+}
+
+impl Overlay {
+    pub fn new(kind: OverlayKind) -> Self {
+        let anchor = match kind {
+            OverlayKind::Clock | OverlayKind::Date => Anchor::Center,
+            OverlayKind::Message => Anchor::Bottom,
+            OverlayKind::Hostname => Anchor::BottomLeft,
+            OverlayKind::System => Anchor::TopRight,
+        };
+        Self {
+            kind,
+            anchor,
+            color: DEFAULT_COLOR,
+            style: Style::Shadow,
+        }
+    }
+
+    /// Parse `KIND[:POSITION][:COLOR][:STYLE]`, e.g. `clock:top-right:ffcc00:outline`.
+    /// The parts after the kind can come in any order.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut parts = spec.split(':');
+        let kind = match parts.next().unwrap_or("") {
+            "clock" => OverlayKind::Clock,
+            "date" => OverlayKind::Date,
+            "message" => OverlayKind::Message,
+            "hostname" => OverlayKind::Hostname,
+            "system" => OverlayKind::System,
+            other => return Err(format!("unknown overlay '{}', expected clock, date, message, hostname or system", other)),
+        };
+        let mut overlay = Self::new(kind);
+        for part in parts {
+            if let Some((_, anchor)) = Anchor::NAMES.iter().find(|(name, _)| *name == part) {
+                overlay.anchor = *anchor;
+            } else if let Some(style) = match part {
+                "plain" => Some(Style::Plain),
+                "shadow" => Some(Style::Shadow),
+                "outline" => Some(Style::Outline),
+                _ => None,
+            } {
+                overlay.style = style;
+            } else if let Some(color) = crate::parse_hex_color(part) {
+                overlay.color = color;
+            } else {
+                return Err(format!("'{}' is not a position, color (RRGGBB) or style (plain, shadow, outline)", part));
+            }
+        }
+        Ok(overlay)
+    }
+}
+
+/// What an overlay shows this frame.
+enum Content {
+    Digits(String, usize), // Block digits and the canvas pixels per font pixel
+    Lines(Vec<String>),
+}
+
+impl Content {
+    /// Width and height in terminal cells.
+    fn size(&self, cell_size: (usize, usize)) -> (usize, usize) {
+        match self {
+            Content::Digits(text, scale) => {
+                let (width, height) = (digits_width(text) * scale, GLYPH_HEIGHT * scale);
+                (width.div_ceil(cell_size.0), height.div_ceil(cell_size.1))
+            }
+            Content::Lines(lines) => (lines.iter().map(|l| l.chars().count()).max().unwrap_or(0), lines.len()),
+        }
+    }
+}
+
+/// The overlays drawn on top of the running effect, and where their text comes from.
+pub struct Overlays {
+    list: Vec<Overlay>,
+    message: Vec<String>,
+    message_file: Option<PathBuf>,
+    file_checked: Instant,
+    file_modified: Option<SystemTime>,
+    hostname: String,
+    system: Option<SystemStats>,
+}
+
+impl Overlays {
+    /// `message_file` is re-read whenever it changes, so a display can be updated
+    /// without restarting. `system` is only sampled if a system overlay is shown.
+    pub fn new(list: Vec<Overlay>, message: Option<String>, message_file: Option<PathBuf>, system: SystemStats) -> Self {
+        let shows_system = list.iter().any(|overlay| overlay.kind == OverlayKind::System);
+        let mut overlays = Self {
+            list,
+            message: message.as_deref().map(message_lines).unwrap_or_default(),
+            message_file,
+            file_checked: Instant::now(),
+            file_modified: None,
+            hostname: hostname(),
+            system: shows_system.then_some(system),
+        };
+        overlays.reload_message();
+        overlays
+    }
+
+    fn reload_message(&mut self) {
+        let Some(path) = &self.message_file else {
+            return;
+        };
+        self.file_checked = Instant::now();
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        if modified.is_some() && modified == self.file_modified {
+            return;
+        }
+        // Keep showing the last message if the file is briefly missing while being replaced
+        if let Ok(text) = fs::read_to_string(path) {
+            self.message = message_lines(&text);
+            self.file_modified = modified;
+        }
+    }
+
+    /// Show `text` in place of the current message, adding a message overlay if
+    /// there is none. `None` clears it. A changed message file still takes over.
+    pub fn set_message(&mut self, text: Option<&str>) {
+        self.message = text.map(message_lines).unwrap_or_default();
+        if !self.list.iter().any(|overlay| overlay.kind == OverlayKind::Message) {
+            self.list.push(Overlay::new(OverlayKind::Message));
+        }
+    }
+
+    pub fn draw(&mut self, canvas: &mut Canvas) {
+        if self.list.is_empty() {
+            return;
+        }
+        if self.file_checked.elapsed() >= FILE_CHECK {
+            self.reload_message();
+        }
+
+        let time = local_time();
+        let cell_size = (canvas.width() / canvas.cols(), canvas.height() / canvas.rows());
+        let system = self.system.as_mut().map(|system| system.lines().to_vec()).unwrap_or_default();
+        let contents: Vec<Content> = self.list.iter().map(|overlay| self.content(overlay, &time, &system, canvas)).collect();
+
+        for (_, anchor) in Anchor::NAMES {
+            let group: Vec<(&Overlay, &Content, (usize, usize))> = self
+                .list
+                .iter()
+                .zip(&contents)
+                .filter(|(overlay, _)| overlay.anchor == anchor)
+                .map(|(overlay, content)| (overlay, content, content.size(cell_size)))
+                .collect();
+            if group.is_empty() {
+                continue;
+            }
+
+            // Stack the group with a blank row between overlays, then place the stack
+            let (h_align, v_align) = anchor.alignment();
+            let height = group.iter().map(|(_, _, (_, rows))| rows).sum::<usize>() + group.len() - 1;
+            let mut row = match v_align {
+                0 => 1,
+                1 => canvas.rows().saturating_sub(height) / 2,
+                _ => canvas.rows().saturating_sub(height + 1),
+            };
+            for (overlay, content, (cols, rows)) in group {
+                let col = match h_align {
+                    0 => 2,
+                    1 => canvas.cols().saturating_sub(cols) / 2,
+                    _ => canvas.cols().saturating_sub(cols + 2),
+                };
+                match content {
+                    Content::Digits(text, scale) => draw_digits(canvas, overlay, text, *scale, (col, row), (cols, rows)),
+                    Content::Lines(lines) => draw_lines(canvas, overlay, lines, (col, row), cols),
+                }
+                row += rows + 1;
+            }
+        }
+    }
+
+    fn content(&self, overlay: &Overlay, time: &libc::tm, system: &[String], canvas: &Canvas) -> Content {
+        match overlay.kind {
+            OverlayKind::Clock => {
+                let text = format!("{:02}:{:02}", time.tm_hour, time.tm_min);
+                let fit_width = (canvas.width() as f32 * CLOCK_WIDTH) as usize / digits_width(&text);
+                let fit_height = canvas.height() / 3 / GLYPH_HEIGHT;
+                Content::Digits(text, fit_width.min(fit_height).max(1))
+            }
+            OverlayKind::Date => Content::Lines(vec![format!(
+                "{}, {} {} {}",
+                WEEKDAYS[time.tm_wday as usize % 7],
+                time.tm_mday,
+                MONTHS[time.tm_mon as usize % 12],
+                time.tm_year + 1900
+            )]),
+            OverlayKind::Message => Content::Lines(self.message.clone()),
+            OverlayKind::Hostname => Content::Lines(vec![self.hostname.clone()]),
+            OverlayKind::System => Content::Lines(system.to_vec()),
+        }
+    }
+}
+
+/// Split a message into lines, replacing control characters that would upset the terminal.
+fn message_lines(text: &str) -> Vec<String> {
+    text.trim_end()
+        .lines()
+        .map(|line| line.chars().map(|c| if c.is_control() { ' ' } else { c }).collect())
+        .collect()
+}
+
+fn local_time() -> libc::tm {
+    // SAFETY: localtime_r only writes to the tm we pass it
+    unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&now, &mut tm);
+        tm
+    }
+}
+
+fn hostname() -> String {
+    let mut buffer = [0u8; 256];
+    // SAFETY: the length passed is the buffer's size
+    let result = unsafe { libc::gethostname(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len()) };
+    if result != 0 {
+        return String::new();
+    }
+    let end = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+    String::from_utf8_lossy(&buffer[..end]).into_owned()
+}
+
+/// Width in font pixels of a row of block digits, with one pixel between glyphs.
+fn digits_width(text: &str) -> usize {
+    let glyphs: usize = text.chars().map(|c| if c == ':' { 1 } else { 3 }).sum();
+    glyphs + text.chars().count().saturating_sub(1)
+}
+
+fn draw_digits(canvas: &mut Canvas, overlay: &Overlay, text: &str, scale: usize, cell: (usize, usize), size: (usize, usize)) {
+    let cell_size = (canvas.width() / canvas.cols(), canvas.height() / canvas.rows());
+    // Center the digits within the cells they were given
+    let left = cell.0 * cell_size.0 + (size.0 * cell_size.0 - digits_width(text) * scale) / 2;
+    let top = cell.1 * cell_size.1 + (size.1 * cell_size.1 - GLYPH_HEIGHT * scale) / 2;
+
+    // Lit font pixels as canvas rectangles (x, y, size)
+    let mut blocks = Vec::new();
+    let mut x = left;
+    for c in text.chars() {
+        let (rows, width) = match c.to_digit(10) {
+            Some(digit) => (DIGITS[digit as usize], 3),
+            None => (COLON, 1),
+        };
+        for (row, bits) in rows.iter().enumerate() {
+            for column in 0..width {
+                if bits & (1 << (width - 1 - column)) != 0 {
+                    blocks.push((x + column * scale, top + row * scale));
+                }
+            }
+        }
+        x += (width + 1) * scale;
+    }
+
+    let border = (scale / 3).max(1);
+    for &(x, y) in &blocks {
+        match overlay.style {
+            Style::Plain => {}
+            Style::Shadow => fill(canvas, (x + border) as isize, (y + border) as isize, scale, scale, DARK),
+            Style::Outline => {
+                fill(canvas, x as isize - border as isize, y as isize - border as isize, scale + 2 * border, scale + 2 * border, DARK)
+            }
+        }
+    }
+    for &(x, y) in &blocks {
+        fill(canvas, x as isize, y as isize, scale, scale, overlay.color);
+    }
+}
+
+fn fill(canvas: &mut Canvas, x: isize, y: isize, width: usize, height: usize, color: (u8, u8, u8)) {
+    for py in y.max(0)..y + height as isize {
+        for px in x.max(0)..x + width as isize {
+            canvas.set(px as usize, py as usize, color);
+        }
+    }
+}
+
+fn draw_lines(canvas: &mut Canvas, overlay: &Overlay, lines: &[String], cell: (usize, usize), width: usize) {
+    if lines.is_empty() {
+        return;
+    }
+    let margin = match overlay.style {
+        Style::Plain => None,
+        Style::Shadow => Some(0),
+        Style::Outline => Some(1),
+    };
+    if let Some(margin) = margin {
+        let left = cell.0.saturating_sub(margin);
+        let top = cell.1.saturating_sub(margin);
+        let size = (cell.0 + width + margin - left, cell.1 + lines.len() + margin - top);
+        canvas.darken_cells((left, top), size, DARK, SHADE_ALPHA);
+    }
+    for (i, line) in lines.iter().enumerate() {
+        canvas.put_text(cell.0, cell.1 + i, line, overlay.color, None, false);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::effects::EffectInfo;
+use fastrand::Rng;
+use std::time::Duration;
+
+/// The effects a session cycles through and when to move on.
+pub struct Playlist {
+    effects: Vec<&'static EffectInfo>,
+    position: usize,
+    shuffle: bool,
+    interval: Option<Duration>,
+    rng: Rng, // Only for shuffling, so the order doesn't depend on what the effects draw
+}
+
+impl Playlist {
+    pub fn new(effects: Vec<&'static EffectInfo>, shuffle: bool, interval: Option<Duration>, rng: Rng) -> Self {
+        let mut playlist = Self {
+            effects,
+            position: 0,
+            shuffle,
+            interval,
+            rng,
+        };
+        if shuffle {
+            playlist.rng.shuffle(&mut playlist.effects);
+        }
+        playlist
+    }
+
+    pub fn len(&self) -> usize {
+        self.effects.len()
+    }
+
+    /// How long each effect runs, or `None` to stay on the current one.
+    pub fn interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    pub fn current(&self) -> &'static EffectInfo {
+        self.effects[self.position]
+    }
+
+    /// Move to the next effect, reshuffling after each full pass when shuffling.
+    pub fn advance(&mut self) -> &'static EffectInfo {
+        self.position += 1;
+        if self.position >= self.effects.len() {
+            self.position = 0;
+            if self.shuffle && self.effects.len() > 1 {
+                let last = self.effects[self.effects.len() - 1].name;
+                self.rng.shuffle(&mut self.effects);
+                // Don't show the same effect twice in a row across passes
+                if self.effects[0].name == last {
+                    let swap = self.rng.usize(1..self.effects.len());
+                    self.effects.swap(0, swap);
+                }
+            }
+        }
+        self.current()
+    }
+}
+
+/// Parse durations like `90`, `30s`, `5m` or `1h`.
+pub fn parse_interval(text: &str) -> Option<Duration> {
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit() && c != '.') {
+        Some(split) => text.split_at(split),
+        None => (text, "s"),
+    };
+    let value: f64 = number.parse().ok()?;
+    let seconds = match unit {
+        "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        _ => return None,
+    };
+    if seconds <= 0.0 {
+        return None;
+    }
+    // Far too long for a Duration is as invalid as negative
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::REGISTRY;
+
+    fn shuffled(seed: u64) -> Vec<&'static str> {
+        let mut playlist = Playlist::new(REGISTRY.iter().collect(), true, None, Rng::with_seed(seed));
+        (0..REGISTRY.len() * 4).map(|_| playlist.advance().name).collect()
+    }
+
+    #[test]
+    fn a_seed_replays_the_same_order() {
+        assert_eq!(shuffled(1), shuffled(1));
+        assert_ne!(shuffled(1), shuffled(2));
+    }
+
+    #[test]
+    fn no_effect_shows_twice_in_a_row() {
+        for seed in 0..50 {
+            let order = shuffled(seed);
+            assert!(order.windows(2).all(|pair| pair[0] != pair[1]), "seed {}: {:?}", seed, order);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+pub const MIN_QUALITY: f32 = 0.25;
+const STEP: f32 = 0.25;
+const SMOOTHING: f32 = 0.1; // Weight of the newest frame in the running average
+const SETTLE_FRAMES: u32 = 15; // Frames to wait after a change before judging again
+const RECOVER_FRAMES: u32 = 90; // Frames of headroom needed before raising quality
+const MAX_RECOVER_FRAMES: u32 = 1800; // Cap on the wait after raising failed repeatedly
+const SLOW: f32 = 0.9; // Fraction of the frame budget that counts as too slow
+const FAST: f32 = 0.4; // Fraction of the frame budget that leaves room for more detail
+
+/// Picks the quality hint passed to effects, lowering it while frames run over
+/// budget and raising it again once there is headroom.
+pub struct QualityController {
+    quality: f32,
+    adaptive: bool,
+    budget: f32,
+    average: f32,
+    frames_since_change: u32,
+    recover_frames: u32, // Doubles each time a raise has to be undone, so quality doesn't flap
+    last_raised: bool,
+}
+
+impl QualityController {
+    /// Adjust quality to keep frame work under `budget`.
+    pub fn adaptive(budget: Duration) -> Self {
+        Self {
+            quality: 1.0,
+            adaptive: true,
+            budget: budget.as_secs_f32(),
+            average: 0.0,
+            frames_since_change: 0,
+            recover_frames: RECOVER_FRAMES,
+            last_raised: false,
+        }
+    }
+
+    /// Always use the given quality.
+    pub fn fixed(quality: f32) -> Self {
+        Self {
+            quality,
+            adaptive: false,
+            budget: 0.0,
+            average: 0.0,
+            frames_since_change: 0,
+            recover_frames: RECOVER_FRAMES,
+            last_raised: false,
+        }
+    }
+
+    pub fn quality(&self) -> f32 {
+        self.quality
+    }
+
+    pub fn is_adaptive(&self) -> bool {
+        self.adaptive
+    }
+
+    /// Feed in one frame's work time. Returns the new quality when it changes.
+    pub fn observe(&mut self, work: Duration) -> Option<f32> {
+        if !self.adaptive {
+            return None;
+        }
+        self.average += (work.as_secs_f32() - self.average) * SMOOTHING;
+        self.frames_since_change += 1;
+        if self.frames_since_change < SETTLE_FRAMES {
+            return None;
+        }
+
+        let quality = if self.average > self.budget * SLOW {
+            (self.quality - STEP).max(MIN_QUALITY)
+        } else if self.average < self.budget * FAST && self.frames_since_change >= self.recover_frames {
+            (self.quality + STEP).min(1.0)
+        } else {
+            self.quality
+        };
+        if quality == self.quality {
+            return None;
+        }
+
+        let raised = quality > self.quality;
+        if !raised && self.last_raised && self.frames_since_change < RECOVER_FRAMES {
+            self.recover_frames = (self.recover_frames * 2).min(MAX_RECOVER_FRAMES);
+        }
+        self.last_raised = raised;
+        self.quality = quality;
+        self.frames_since_change = 0;
+        Some(quality)
+    }
+}
@@ -0,0 +1,137 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Where event timestamps come from.
+enum Clock {
+    Wall(Instant),
+    Simulated(f64), // Headless runs move the clock by the simulated time instead
+}
+
+/// An asciicast v2 recording: a JSON header line followed by one
+/// `[time, code, data]` line per event.
+pub struct Cast {
+    file: BufWriter<File>,
+    clock: Clock,
+}
+
+impl Cast {
+    /// Record a live terminal of `cols` x `rows` cells, timed by the wall clock.
+    pub fn live(path: &Path, cols: usize, rows: usize) -> io::Result<Self> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let env = match std::env::var("TERM") {
+            Ok(term) => format!(", \"env\": {{\"TERM\": {}}}", json_string(&term)),
+            Err(_) => String::new(),
+        };
+        let header = format!(
+            "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}{}}}",
+            cols, rows, timestamp, env
+        );
+        Self::create(path, &header, Clock::Wall(Instant::now()))
+    }
+
+    /// Record a headless run. The clock only moves with `advance`, so the
+    /// same seed gives the same file.
+    pub fn simulated(path: &Path, cols: usize, rows: usize) -> io::Result<Self> {
+        let header = format!("{{\"version\": 2, \"width\": {}, \"height\": {}}}", cols, rows);
+        Self::create(path, &header, Clock::Simulated(0.0))
+    }
+
+    fn create(path: &Path, header: &str, clock: Clock) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "{}", header)?;
+        Ok(Self { file, clock })
+    }
+
+    pub fn advance(&mut self, seconds: f32) {
+        if let Clock::Simulated(time) = &mut self.clock {
+            *time += seconds as f64;
+        }
+    }
+
+    /// Note a terminal size change so players resize along with the recording.
+    pub fn resize(&mut self, cols: usize, rows: usize) -> io::Result<()> {
+        self.event("r", &format!("{}x{}", cols, rows))
+    }
+
+    fn event(&mut self, code: &str, data: &str) -> io::Result<()> {
+        let time = match &self.clock {
+            Clock::Wall(started) => started.elapsed().as_secs_f64(),
+            Clock::Simulated(time) => *time,
+        };
+        writeln!(self.file, "[{:.6}, \"{}\", {}]", time, code, json_string(data))?;
+        // Flushed per event so a killed run still leaves a playable file
+        self.file.flush()
+    }
+}
+
+/// Passes output through to `inner`, copying it into a cast each time it is flushed.
+///
+/// The renderer flushes once per frame, so every frame becomes one output event.
+pub struct Recorder<W: Write> {
+    inner: W,
+    cast: Option<Cast>,
+    pending: Vec<u8>,
+}
+
+impl<W: Write> Recorder<W> {
+    pub fn new(inner: W, cast: Option<Cast>) -> Self {
+        Self {
+            inner,
+            cast,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn cast_mut(&mut self) -> Option<&mut Cast> {
+        self.cast.as_mut()
+    }
+}
+
+impl<W: Write> Write for Recorder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        if self.cast.is_some() {
+            self.pending.extend_from_slice(&buf[..written]);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        let Some(cast) = &mut self.cast else {
+            return Ok(());
+        };
+        // Hold back a character split across flushes until the rest of it arrives
+        let complete = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+        if complete == 0 {
+            return Ok(());
+        }
+        let text = String::from_utf8_lossy(&self.pending[..complete]).into_owned();
+        self.pending.drain(..complete);
+        cast.event("o", &text)
+    }
+}
+
+/// Quote text as a JSON string.
+pub fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 || c == '\u{7f}' => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,265 @@
+use std::env;
+
+/// How many colors the terminal can display.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+/// A color as it will be sent to the terminal.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Color {
+    Rgb(u8, u8, u8),
+    Indexed(u8),
+}
+
+// xterm's default values for the 16 ANSI colors
+const ANSI16: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+// Channel levels of the 6x6x6 color cube (indices 16-231)
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorDepth {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "truecolor" | "24bit" => Some(ColorDepth::TrueColor),
+            "256" => Some(ColorDepth::Ansi256),
+            "16" => Some(ColorDepth::Ansi16),
+            _ => None,
+        }
+    }
+
+    /// Guess the color depth from `COLORTERM` and `TERM`.
+    pub fn detect() -> Self {
+        Self::from_env(&env::var("COLORTERM").unwrap_or_default(), &env::var("TERM").unwrap_or_default())
+    }
+
+    fn from_env(colorterm: &str, term: &str) -> Self {
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorDepth::TrueColor;
+        }
+
+        if term.is_empty() {
+            // Nothing to go on (e.g. Windows consoles) - assume a modern terminal
+            ColorDepth::TrueColor
+        } else if term.ends_with("-direct") {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else if term == "linux"
+            || term == "ansi"
+            || term.ends_with("-16color")
+            || term.ends_with("-8color")
+            || term.starts_with("screen")
+            || term.starts_with("vt")
+            || term.starts_with("rxvt")
+            || term.starts_with("cons")
+        {
+            ColorDepth::Ansi16
+        } else {
+            // Including plain `xterm`, which nearly every terminal claiming it outgrew long ago
+            ColorDepth::Ansi256
+        }
+    }
+
+    /// Map an RGB color to the nearest color the terminal can show.
+    pub fn quantize(self, rgb: (u8, u8, u8)) -> Color {
+        match self {
+            ColorDepth::TrueColor => Color::Rgb(rgb.0, rgb.1, rgb.2),
+            ColorDepth::Ansi256 => Color::Indexed(nearest_256(rgb)),
+            ColorDepth::Ansi16 => Color::Indexed(nearest_16(rgb)),
+        }
+    }
+}
+
+impl Color {
+    /// Append the SGR sequence selecting this color as foreground or background.
+    pub fn write_sgr(self, buf: &mut Vec<u8>, background: bool) -> std::io::Result<()> {
+        use std::io::Write;
+
+        let layer = if background { 48 } else { 38 };
+        match self {
+            Color::Rgb(r, g, b) => write!(buf, "\x1b[{};2;{};{};{}m", layer, r, g, b),
+            // The basic 16 colors use the classic codes so 16-color terminals understand them
+            Color::Indexed(n) if n < 8 => write!(buf, "\x1b[{}m", layer - 8 + n),
+            Color::Indexed(n) if n < 16 => write!(buf, "\x1b[{}m", layer + 52 + n - 8),
+            Color::Indexed(n) => write!(buf, "\x1b[{};5;{}m", layer, n),
+        }
+    }
+}
+
+fn distance_sq(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let dr = a.0 as i32 - b.0 as i32;
+    let dg = a.1 as i32 - b.1 as i32;
+    let db = a.2 as i32 - b.2 as i32;
+    (dr * dr + dg * dg + db * db) as u32
+}
+
+fn nearest_16(rgb: (u8, u8, u8)) -> u8 {
+    let mut best = 0;
+    let mut best_dist = u32::MAX;
+    for (i, &color) in ANSI16.iter().enumerate() {
+        let dist = distance_sq(rgb, color);
+        if dist < best_dist {
+            best_dist = dist;
+            best = i as u8;
+        }
+    }
+    best
+}
+
+/// The RGB value xterm uses for a 256-color palette index.
+pub fn palette_rgb(n: u8) -> (u8, u8, u8) {
+    match n {
+        0..=15 => ANSI16[n as usize],
+        16..=231 => {
+            let i = n as usize - 16;
+            (CUBE_LEVELS[i / 36], CUBE_LEVELS[(i / 6) % 6], CUBE_LEVELS[i % 6])
+        }
+        _ => {
+            let v = 8 + (n - 232) * 10;
+            (v, v, v)
+        }
+    }
+}
+
+pub fn nearest_256(rgb: (u8, u8, u8)) -> u8 {
+    // Nearest point in the 6x6x6 cube
+    let level = |v: u8| -> usize {
+        if v < 48 {
+            0
+        } else if v < 115 {
+            1
+        } else {
+            ((v as usize - 35) / 40).min(5)
+        }
+    };
+    let (ri, gi, bi) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+    let cube_index = 16 + 36 * ri + 6 * gi + bi;
+
+    // Nearest step of the 24-level gray ramp (8, 18, ..., 238)
+    let avg = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let gray_step = if avg < 8 { 0 } else { ((avg - 8 + 5) / 10).min(23) };
+    let gray_value = (8 + gray_step * 10) as u8;
+    let gray = (gray_value, gray_value, gray_value);
+
+    if distance_sq(rgb, gray) < distance_sq(rgb, cube) {
+        (232 + gray_step) as u8
+    } else {
+        cube_index as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sgr(color: Color, background: bool) -> String {
+        let mut buf = Vec::new();
+        color.write_sgr(&mut buf, background).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn detects_depth_from_the_environment() {
+        let cases = [
+            ("truecolor", "xterm", ColorDepth::TrueColor),
+            ("24bit", "linux", ColorDepth::TrueColor),
+            ("", "", ColorDepth::TrueColor),
+            ("", "foot-direct", ColorDepth::TrueColor),
+            ("", "xterm-256color", ColorDepth::Ansi256),
+            ("", "screen-256color", ColorDepth::Ansi256),
+            ("", "xterm", ColorDepth::Ansi256),
+            ("", "alacritty", ColorDepth::Ansi256),
+            ("", "xterm-16color", ColorDepth::Ansi16),
+            ("", "linux", ColorDepth::Ansi16),
+            ("", "screen", ColorDepth::Ansi16),
+            ("", "vt100", ColorDepth::Ansi16),
+        ];
+        for (colorterm, term, depth) in cases {
+            assert_eq!(ColorDepth::from_env(colorterm, term), depth, "COLORTERM={} TERM={}", colorterm, term);
+        }
+    }
+
+    #[test]
+    fn quantizes_to_the_256_color_palette() {
+        let cases = [
+            ((0, 0, 0), 16),
+            ((255, 255, 255), 231),
+            ((255, 0, 0), 196),
+            ((0, 255, 0), 46),
+            ((0, 0, 255), 21),
+            ((255, 255, 0), 226),
+            // Either side of the first two cube steps, with blue keeping the gray ramp out of it
+            ((47, 0, 255), 21),
+            ((48, 0, 255), 57),
+            ((114, 0, 255), 57),
+            ((115, 0, 255), 93),
+            // A dark red is nearer a dark gray than black
+            ((47, 0, 0), 233),
+            // Grays between cube levels go to the ramp
+            ((8, 8, 8), 232),
+            ((47, 47, 47), 236),
+            ((128, 128, 128), 244),
+            ((238, 238, 238), 255),
+            ((135, 135, 135), 102),
+        ];
+        for (rgb, index) in cases {
+            assert_eq!(nearest_256(rgb), index, "{:?}", rgb);
+        }
+        // Every palette entry past the first 16 maps back to itself
+        for n in 16..=255 {
+            assert_eq!(nearest_256(palette_rgb(n)), n);
+        }
+    }
+
+    #[test]
+    fn quantizes_to_the_16_ansi_colors() {
+        let cases = [
+            ((0, 0, 0), 0),
+            ((205, 0, 0), 1),
+            ((255, 0, 0), 9),
+            ((0, 0, 255), 4),
+            ((92, 92, 255), 12),
+            ((128, 128, 128), 8),
+            ((200, 200, 200), 7),
+            ((255, 255, 255), 15),
+        ];
+        for (rgb, index) in cases {
+            assert!(ColorDepth::Ansi16.quantize(rgb) == Color::Indexed(index), "{:?}", rgb);
+        }
+        assert!(ColorDepth::TrueColor.quantize((1, 2, 3)) == Color::Rgb(1, 2, 3));
+    }
+
+    #[test]
+    fn writes_sgr_codes() {
+        assert_eq!(sgr(Color::Rgb(1, 2, 3), false), "\x1b[38;2;1;2;3m");
+        assert_eq!(sgr(Color::Rgb(1, 2, 3), true), "\x1b[48;2;1;2;3m");
+        assert_eq!(sgr(Color::Indexed(1), false), "\x1b[31m");
+        assert_eq!(sgr(Color::Indexed(7), true), "\x1b[47m");
+        assert_eq!(sgr(Color::Indexed(9), false), "\x1b[91m");
+        assert_eq!(sgr(Color::Indexed(15), true), "\x1b[107m");
+        assert_eq!(sgr(Color::Indexed(196), false), "\x1b[38;5;196m");
+        assert_eq!(sgr(Color::Indexed(232), true), "\x1b[48;5;232m");
+    }
+}
@@ -0,0 +1,110 @@
+use std::env;
+
+/// How frames reach the terminal: as character cells or as a pixel image.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Graphics {
+    Cells,
+    Kitty,
+    Sixel,
+}
+
+// Query sent while probing: a 1x1 kitty image query followed by primary device attributes.
+// Terminals answer DA1 in order, so its reply marks the end of anything the query produced.
+const PROBE: &[u8] = b"\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\\x1b[c";
+
+impl Graphics {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "cells" => Some(Graphics::Cells),
+            "kitty" => Some(Graphics::Kitty),
+            "sixel" => Some(Graphics::Sixel),
+            _ => None,
+        }
+    }
+
+    /// Ask the terminal which image protocols it supports, falling back to cells.
+    ///
+    /// Must be called in raw mode, before anything else reads terminal input.
+    pub fn detect() -> Self {
+        // Multiplexers answer queries themselves and rarely pass images through
+        if env::var_os("TMUX").is_some() || env::var("TERM").is_ok_and(|t| t.starts_with("screen")) {
+            return Graphics::Cells;
+        }
+
+        match probe() {
+            Some(reply) => Self::from_reply(&reply),
+            None => Graphics::Cells,
+        }
+    }
+
+    fn from_reply(reply: &str) -> Self {
+        if reply.contains("\x1b_Gi=31;OK") {
+            return Graphics::Kitty;
+        }
+
+        // DA1 looks like ESC [ ? 62 ; 4 ; 22 c - attribute 4 means sixel
+        if let Some(start) = reply.find("\x1b[?")
+            && let Some(len) = reply[start..].find('c')
+        {
+            let attributes = &reply[start + 3..start + len];
+            if attributes.split(';').any(|a| a == "4") {
+                return Graphics::Sixel;
+            }
+        }
+
+        Graphics::Cells
+    }
+}
+
+#[cfg(unix)]
+fn probe() -> Option<String> {
+    use std::fs::OpenOptions;
+    use std::io::{Read, Write};
+    use std::os::unix::io::AsRawFd;
+    use std::time::{Duration, Instant};
+
+    let mut tty = OpenOptions::new().read(true).write(true).open("/dev/tty").ok()?;
+    tty.write_all(PROBE).ok()?;
+    tty.flush().ok()?;
+
+    let deadline = Instant::now() + Duration::from_millis(300);
+    let mut reply = Vec::new();
+    let mut buf = [0u8; 256];
+
+    // Read until the DA1 reply is complete or the terminal stays silent
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+
+        let mut fds = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ready = unsafe { libc::poll(&mut fds, 1, remaining.as_millis() as libc::c_int) };
+        if ready <= 0 {
+            break;
+        }
+
+        let n = tty.read(&mut buf).ok()?;
+        if n == 0 {
+            break;
+        }
+        reply.extend_from_slice(&buf[..n]);
+
+        if let Some(start) = reply.windows(3).position(|w| w == b"\x1b[?")
+            && reply[start..].contains(&b'c')
+        {
+            break;
+        }
+    }
+
+    Some(String::from_utf8_lossy(&reply).into_owned())
+}
+
+#[cfg(not(unix))]
+fn probe() -> Option<String> {
+    None
+}
@@ -0,0 +1,153 @@
+use std::io::Write;
+
+// Escape-code payloads are limited to 4096 bytes of base64 per chunk
+const CHUNK_SIZE: usize = 4096;
+
+// Frames alternate between these two image IDs
+const IMAGE_IDS: [u32; 2] = [1, 2];
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes frames for the kitty graphics protocol.
+///
+/// Each frame is transmitted and placed under a fresh image ID before the
+/// previous frame's image is deleted, so the picture never blanks between
+/// frames. Only two IDs are ever in use.
+pub struct KittyEncoder {
+    frame: usize,
+    rgb: Vec<u8>,
+    encoded: Vec<u8>,
+}
+
+impl KittyEncoder {
+    pub fn new() -> Self {
+        Self {
+            frame: 0,
+            rgb: Vec::new(),
+            encoded: Vec::new(),
+        }
+    }
+
+    /// Append the escape codes that show `pixels` stretched over `cols` x `rows` cells
+    /// starting at the cursor position.
+    pub fn encode(
+        &mut self,
+        pixels: &[(u8, u8, u8)],
+        size: (usize, usize),
+        cells: (usize, usize),
+        out: &mut Vec<u8>,
+    ) -> std::io::Result<()> {
+        let (width, height) = size;
+        let (cols, rows) = cells;
+        let id = IMAGE_IDS[self.frame % 2];
+
+        self.rgb.clear();
+        for &(r, g, b) in pixels {
+            self.rgb.extend_from_slice(&[r, g, b]);
+        }
+        self.encoded.clear();
+        base64_encode(&self.rgb, &mut self.encoded);
+
+        // z=-1 keeps text cells drawn on top, C=1 leaves the cursor where it is, q=2 silences replies
+        let mut chunks = self.encoded.chunks(CHUNK_SIZE).peekable();
+        let mut first = true;
+        while let Some(chunk) = chunks.next() {
+            let more = if chunks.peek().is_some() { 1 } else { 0 };
+            if first {
+                write!(
+                    out,
+                    "\x1b_Ga=T,f=24,s={},v={},i={},p=1,c={},r={},z=-1,C=1,q=2,m={};",
+                    width, height, id, cols, rows, more
+                )?;
+                first = false;
+            } else {
+                write!(out, "\x1b_Gm={};", more)?;
+            }
+            out.extend_from_slice(chunk);
+            out.extend_from_slice(b"\x1b\\");
+        }
+
+        if self.frame > 0 {
+            let previous = IMAGE_IDS[(self.frame + 1) % 2];
+            write!(out, "\x1b_Ga=d,d=I,i={},q=2\x1b\\", previous)?;
+        }
+        self.frame += 1;
+        Ok(())
+    }
+
+    /// Append the escape code deleting every image we placed and start over.
+    pub fn clear(&mut self, out: &mut Vec<u8>) {
+        out.extend_from_slice(b"\x1b_Ga=d,d=A,q=2\x1b\\");
+        self.frame = 0;
+    }
+}
+
+pub fn base64_encode(data: &[u8], out: &mut Vec<u8>) {
+    for chunk in data.chunks(3) {
+        let b0 = chunk[0] as u32;
+        let b1 = chunk.get(1).copied().unwrap_or(0) as u32;
+        let b2 = chunk.get(2).copied().unwrap_or(0) as u32;
+        let triple = (b0 << 16) | (b1 << 8) | b2;
+
+        out.push(BASE64[(triple >> 18) as usize & 63]);
+        out.push(BASE64[(triple >> 12) as usize & 63]);
+        out.push(if chunk.len() > 1 { BASE64[(triple >> 6) as usize & 63] } else { b'=' });
+        out.push(if chunk.len() > 2 { BASE64[triple as usize & 63] } else { b'=' });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(encoder: &mut KittyEncoder, pixels: &[(u8, u8, u8)], size: (usize, usize)) -> Vec<u8> {
+        let mut out = Vec::new();
+        encoder.encode(pixels, size, (2, 1), &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn base64_matches_rfc_4648() {
+        for (data, expected) in [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foobar", "Zm9vYmFy")] {
+            let mut out = Vec::new();
+            base64_encode(data.as_bytes(), &mut out);
+            assert_eq!(out, expected.as_bytes());
+        }
+        let mut out = Vec::new();
+        base64_encode(&[0xff, 0xfe, 0xfd], &mut out);
+        assert_eq!(out, b"//79");
+    }
+
+    #[test]
+    fn alternates_ids_and_deletes_the_previous_frame() {
+        let mut encoder = KittyEncoder::new();
+        let header = |id: u32| format!("\x1b_Ga=T,f=24,s=1,v=1,i={},p=1,c=2,r=1,z=-1,C=1,q=2,m=0;AQID\x1b\\", id);
+        assert_eq!(encode(&mut encoder, &[(1, 2, 3)], (1, 1)), header(1).as_bytes());
+        assert_eq!(encode(&mut encoder, &[(1, 2, 3)], (1, 1)), format!("{}\x1b_Ga=d,d=I,i=1,q=2\x1b\\", header(2)).as_bytes());
+        assert_eq!(encode(&mut encoder, &[(1, 2, 3)], (1, 1)), format!("{}\x1b_Ga=d,d=I,i=2,q=2\x1b\\", header(1)).as_bytes());
+
+        let mut out = Vec::new();
+        encoder.clear(&mut out);
+        assert_eq!(out, b"\x1b_Ga=d,d=A,q=2\x1b\\");
+        assert_eq!(encode(&mut encoder, &[(1, 2, 3)], (1, 1)), header(1).as_bytes());
+    }
+
+    #[test]
+    fn splits_payloads_into_chunks() {
+        let mut encoder = KittyEncoder::new();
+        // 1024 pixels are exactly one chunk of base64
+        let out = encode(&mut encoder, &[(0, 0, 0); 1024], (32, 32));
+        let prefix = b"\x1b_Ga=T,f=24,s=32,v=32,i=1,p=1,c=2,r=1,z=-1,C=1,q=2,m=0;";
+        assert_eq!(&out[..prefix.len()], prefix);
+        assert_eq!(out.len(), prefix.len() + CHUNK_SIZE + 2);
+
+        // One more spills into a second chunk
+        let mut encoder = KittyEncoder::new();
+        let out = encode(&mut encoder, &[(0, 0, 0); 1025], (1025, 1));
+        let prefix = b"\x1b_Ga=T,f=24,s=1025,v=1,i=1,p=1,c=2,r=1,z=-1,C=1,q=2,m=1;";
+        assert_eq!(&out[..prefix.len()], prefix);
+        let rest = &out[prefix.len()..];
+        assert!(rest[..CHUNK_SIZE].iter().all(|&b| b == b'A'));
+        assert_eq!(&rest[CHUNK_SIZE..], b"\x1b\\\x1b_Gm=0;AAAA\x1b\\");
+    }
+}
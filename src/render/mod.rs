@@ -0,0 +1,480 @@
+use crate::effects::{Canvas, TextCell};
+use std::io::Write;
+
+pub mod color;
+pub mod graphics;
+pub mod kitty;
+pub mod pixels;
+pub mod sixel;
+
+use color::{Color, ColorDepth};
+use graphics::Graphics;
+use kitty::KittyEncoder;
+use pixels::PixelMode;
+use sixel::SixelEncoder;
+
+const BEGIN_SYNC: &[u8] = b"\x1b[?2026h";
+const END_SYNC: &[u8] = b"\x1b[?2026l";
+
+/// Append image escape codes drawn from the top-left cell.
+///
+/// Inside tmux they are wrapped in its passthrough (every ESC doubled), which
+/// needs `allow-passthrough on`. Passed-through codes bypass tmux's cursor, so
+/// the image is placed at the pane's position on the outer terminal and the
+/// cursor put back where tmux left it.
+fn write_image(image: &[u8], tmux_pane: Option<(usize, usize)>, out: &mut Vec<u8>) {
+    if image.is_empty() {
+        return;
+    }
+    let Some((left, top)) = tmux_pane else {
+        out.extend_from_slice(b"\x1b[H");
+        out.extend_from_slice(image);
+        return;
+    };
+    out.extend_from_slice(b"\x1bPtmux;");
+    let place = format!("\x1b7\x1b[{};{}H", top + 1, left + 1);
+    for &byte in place.as_bytes().iter().chain(image).chain(b"\x1b8") {
+        if byte == 0x1b {
+            out.push(0x1b);
+        }
+        out.push(byte);
+    }
+    out.extend_from_slice(b"\x1b\\");
+}
+
+/// What a single terminal cell shows.
+#[derive(Clone, Copy, PartialEq)]
+struct Cell {
+    ch: char,
+    fg: Color,
+    bg: Color,
+    bold: bool,
+}
+
+/// Turns a canvas into terminal output using block, sextant or braille glyphs.
+///
+/// In half mode each terminal cell shows two vertically stacked pixels: the
+/// top pixel as the background color and the bottom pixel as the foreground
+/// of `▄`. The finer modes reduce each cell's pixels to two colors and pick
+/// the glyph whose shape matches which pixels got the foreground.
+///
+/// The renderer keeps the previously emitted frame (front buffer) and only
+/// writes cells that differ from it, so static areas cost no bandwidth.
+/// Colors are reduced to what the terminal supports before comparing.
+///
+/// With a kitty or sixel backend the pixels are sent as one image per frame
+/// instead, and only the canvas text goes through character cells.
+pub struct Renderer {
+    depth: ColorDepth,
+    mode: PixelMode,
+    graphics: Graphics,
+    output_buf: Vec<u8>,
+    front: Vec<Cell>,
+    back: Vec<Cell>,
+    cols: usize,
+    rows: usize,
+    full_redraw: bool,
+    kitty: KittyEncoder,
+    sixel: SixelEncoder,
+    cell_pixels: (usize, usize),
+    last_pixels: Vec<(u8, u8, u8)>,
+    last_text: Vec<Option<TextCell>>,
+    image_buf: Vec<u8>,
+    synchronized: bool,
+    tmux_pane: Option<(usize, usize)>, // Pane's top-left cell on the outer terminal
+}
+
+impl Renderer {
+    pub fn new(depth: ColorDepth, mode: PixelMode, graphics: Graphics) -> Self {
+        Self {
+            depth,
+            mode,
+            graphics,
+            output_buf: Vec::with_capacity(1024 * 64),
+            front: Vec::new(),
+            back: Vec::new(),
+            cols: 0,
+            rows: 0,
+            full_redraw: true,
+            kitty: KittyEncoder::new(),
+            sixel: SixelEncoder::new(),
+            cell_pixels: (8, 16),
+            last_pixels: Vec::new(),
+            last_text: Vec::new(),
+            image_buf: Vec::new(),
+            synchronized: false,
+            tmux_pane: None,
+        }
+    }
+
+    /// Bracket each frame in synchronized update codes, so terminals (and tmux)
+    /// show it all at once instead of tearing halfway through.
+    pub fn set_synchronized(&mut self, synchronized: bool) {
+        self.synchronized = synchronized;
+    }
+
+    /// Running in a tmux pane with its top-left cell at `origin` on the outer
+    /// terminal: send images through tmux's passthrough.
+    pub fn set_tmux_pane(&mut self, origin: Option<(usize, usize)>) {
+        self.tmux_pane = origin;
+    }
+
+    /// Tell the renderer how many screen pixels a terminal cell has, for sixel scaling.
+    pub fn set_cell_pixels(&mut self, width: usize, height: usize) {
+        if width > 0 && height > 0 {
+            self.cell_pixels = (width, height);
+        }
+    }
+
+    /// Forget what is on screen so the next frame repaints every cell.
+    pub fn invalidate(&mut self) {
+        self.full_redraw = true;
+    }
+
+    /// Undo terminal state set up for image output. Call before leaving the alternate screen.
+    pub fn finish<W: Write>(&mut self, out: &mut W) -> std::io::Result<()> {
+        self.output_buf.clear();
+        self.image_buf.clear();
+        if self.graphics == Graphics::Kitty {
+            self.kitty.clear(&mut self.image_buf);
+        }
+        write_image(&self.image_buf, self.tmux_pane, &mut self.output_buf);
+        out.write_all(&self.output_buf)?;
+        out.flush()
+    }
+
+    pub fn draw<W: Write>(&mut self, canvas: &Canvas, out: &mut W) -> std::io::Result<()> {
+        let cols = canvas.cols();
+        let rows = canvas.rows();
+        if cols != self.cols || rows != self.rows {
+            self.cols = cols;
+            self.rows = rows;
+            self.full_redraw = true;
+        }
+
+        match self.graphics {
+            Graphics::Cells => self.draw_cells(canvas, out),
+            Graphics::Kitty | Graphics::Sixel => self.draw_image(canvas, out),
+        }
+    }
+
+    fn draw_cells<W: Write>(&mut self, canvas: &Canvas, out: &mut W) -> std::io::Result<()> {
+        let cols = self.cols;
+        let rows = self.rows;
+
+        self.compose(canvas);
+        if self.front.len() != self.back.len() {
+            self.full_redraw = true;
+        }
+
+        self.output_buf.clear();
+        if self.synchronized {
+            self.output_buf.extend_from_slice(BEGIN_SYNC);
+        }
+
+        // Cursor position after the last written cell, if known
+        let mut cursor: Option<(usize, usize)> = None;
+        let mut prev_bg: Option<Color> = None;
+        let mut prev_fg: Option<Color> = None;
+        let mut bold = false;
+
+        for row in 0..rows {
+            for col in 0..cols {
+                let idx = row * cols + col;
+                let cell = self.back[idx];
+                if !self.full_redraw && self.front[idx] == cell {
+                    continue;
+                }
+
+                if cursor != Some((col, row)) {
+                    write!(self.output_buf, "\x1b[{};{}H", row + 1, col + 1)?;
+                }
+
+                if cell.bold != bold {
+                    // SGR 22 turns off bold without touching colors
+                    self.output_buf.extend_from_slice(if cell.bold { b"\x1b[1m" } else { b"\x1b[22m" });
+                    bold = cell.bold;
+                }
+
+                // Only emit color codes if changed
+                if prev_bg != Some(cell.bg) {
+                    cell.bg.write_sgr(&mut self.output_buf, true)?;
+                    prev_bg = Some(cell.bg);
+                }
+                if prev_fg != Some(cell.fg) {
+                    cell.fg.write_sgr(&mut self.output_buf, false)?;
+                    prev_fg = Some(cell.fg);
+                }
+
+                let mut utf8 = [0u8; 4];
+                self.output_buf.extend_from_slice(cell.ch.encode_utf8(&mut utf8).as_bytes());
+
+                // Past the last column the cursor sits in a pending-wrap state
+                cursor = if col + 1 < cols { Some((col + 1, row)) } else { None };
+            }
+        }
+
+        // Anything past the opening code means some cell changed
+        let begin = if self.synchronized { BEGIN_SYNC.len() } else { 0 };
+        if self.output_buf.len() > begin {
+            self.output_buf.extend_from_slice(b"\x1b[0m");
+            if self.synchronized {
+                self.output_buf.extend_from_slice(END_SYNC);
+            }
+            out.write_all(&self.output_buf)?;
+            out.flush()?;
+        }
+
+        std::mem::swap(&mut self.front, &mut self.back);
+        self.full_redraw = false;
+        Ok(())
+    }
+
+    fn draw_image<W: Write>(&mut self, canvas: &Canvas, out: &mut W) -> std::io::Result<()> {
+        let cols = self.cols;
+        let rows = self.rows;
+        let text_changed = (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| (col, row)))
+            .any(|(col, row)| self.last_text.get(row * cols + col).copied().flatten() != canvas.text_at(col, row));
+        if !self.full_redraw && !text_changed && self.last_pixels == canvas.pixels() {
+            return Ok(());
+        }
+
+        self.output_buf.clear();
+        if self.synchronized {
+            self.output_buf.extend_from_slice(BEGIN_SYNC);
+        }
+        self.image_buf.clear();
+        if self.full_redraw {
+            self.last_text = vec![None; cols * rows];
+            // Sixels draw at the cursor, which write_image puts at the pane's origin;
+            // sixel display mode (?80h) would pin them to the screen's top-left instead
+            if self.graphics == Graphics::Kitty {
+                self.kitty.clear(&mut self.image_buf);
+            }
+        }
+
+        let size = (canvas.width(), canvas.height());
+        match self.graphics {
+            Graphics::Kitty => self.kitty.encode(canvas.pixels(), size, (cols, rows), &mut self.image_buf)?,
+            _ => {
+                let (cell_w, cell_h) = self.mode.cell_size();
+                let scale = ((self.cell_pixels.0 / cell_w).max(1), (self.cell_pixels.1 / cell_h).max(1));
+                self.sixel.encode(canvas.pixels(), size, scale, &mut self.image_buf)?;
+            }
+        }
+        write_image(&self.image_buf, self.tmux_pane, &mut self.output_buf);
+
+        // Text goes on top of the image; cells whose text disappeared are blanked
+        for row in 0..rows {
+            for col in 0..cols {
+                let idx = row * cols + col;
+                let text = canvas.text_at(col, row);
+                if text.is_none() && self.last_text[idx].is_none() {
+                    continue;
+                }
+
+                write!(self.output_buf, "\x1b[{};{}H\x1b[0m", row + 1, col + 1)?;
+                match text {
+                    Some(text) => {
+                        if text.bold {
+                            self.output_buf.extend_from_slice(b"\x1b[1m");
+                        }
+                        self.depth.quantize(text.fg).write_sgr(&mut self.output_buf, false)?;
+                        if let Some(bg) = text.bg {
+                            self.depth.quantize(bg).write_sgr(&mut self.output_buf, true)?;
+                        }
+                        let mut utf8 = [0u8; 4];
+                        self.output_buf.extend_from_slice(text.ch.encode_utf8(&mut utf8).as_bytes());
+                    }
+                    // Sixel pixels already replaced the cell; kitty needs the old glyph erased
+                    None if self.graphics == Graphics::Kitty => self.output_buf.push(b' '),
+                    None => {}
+                }
+                self.last_text[idx] = text;
+            }
+        }
+
+        self.output_buf.extend_from_slice(b"\x1b[0m");
+        if self.synchronized {
+            self.output_buf.extend_from_slice(END_SYNC);
+        }
+        out.write_all(&self.output_buf)?;
+        out.flush()?;
+
+        self.last_pixels.clear();
+        self.last_pixels.extend_from_slice(canvas.pixels());
+        self.full_redraw = false;
+        Ok(())
+    }
+
+    /// Build the back buffer from the canvas pixels and text.
+    fn compose(&mut self, canvas: &Canvas) {
+        let (cell_w, cell_h) = self.mode.cell_size();
+        let width = canvas.width();
+        let height = canvas.height();
+        let depth = self.depth;
+        let mode = self.mode;
+        let mut samples = [(0u8, 0u8, 0u8); 8];
+        self.back.clear();
+
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                // Gather the cell's pixels in row-major order, repeating edge pixels
+                let count = cell_w * cell_h;
+                for (i, sample) in samples.iter_mut().take(count).enumerate() {
+                    let x = (col * cell_w + i % cell_w).min(width - 1);
+                    let y = (row * cell_h + i / cell_w).min(height - 1);
+                    *sample = canvas.get(x, y);
+                }
+
+                let (mask, fg, bg) = if mode == PixelMode::Half {
+                    // Always draw `▄` so the output matches the plain half-block look
+                    (2, samples[1], samples[0])
+                } else {
+                    pixels::split_two_colors(&samples[..count])
+                };
+
+                self.back.push(match canvas.text_at(col, row) {
+                    Some(text) => Cell {
+                        ch: text.ch,
+                        fg: depth.quantize(text.fg),
+                        bg: depth.quantize(text.bg.unwrap_or(bg)),
+                        bold: text.bold,
+                    },
+                    None => Cell {
+                        ch: mode.glyph(mask),
+                        fg: depth.quantize(fg),
+                        bg: depth.quantize(bg),
+                        bold: false,
+                    },
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: (u8, u8, u8) = (255, 0, 0);
+    const BLUE: (u8, u8, u8) = (0, 0, 255);
+
+    fn renderer() -> Renderer {
+        Renderer::new(ColorDepth::TrueColor, PixelMode::Half, Graphics::Cells)
+    }
+
+    fn draw(renderer: &mut Renderer, canvas: &Canvas) -> String {
+        let mut out = Vec::new();
+        renderer.draw(canvas, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn unchanged_frames_write_nothing() {
+        let mut renderer = renderer();
+        let canvas = Canvas::new(3, 1, (1, 2));
+        assert_eq!(draw(&mut renderer, &canvas), "\x1b[1;1H\x1b[48;2;0;0;0m\x1b[38;2;0;0;0m▄▄▄\x1b[0m");
+        assert_eq!(draw(&mut renderer, &canvas), "");
+    }
+
+    #[test]
+    fn writes_only_changed_cells() {
+        let mut renderer = renderer();
+        let mut canvas = Canvas::new(4, 2, (1, 2));
+        draw(&mut renderer, &canvas);
+
+        canvas.set(1, 1, RED);
+        assert_eq!(draw(&mut renderer, &canvas), "\x1b[1;2H\x1b[48;2;0;0;0m\x1b[38;2;255;0;0m▄\x1b[0m");
+
+        // Neighbours share one cursor move; a gap needs another
+        canvas.set(2, 1, RED);
+        canvas.set(0, 3, BLUE);
+        canvas.set(1, 3, BLUE);
+        canvas.set(3, 3, BLUE);
+        assert_eq!(
+            draw(&mut renderer, &canvas),
+            "\x1b[1;3H\x1b[48;2;0;0;0m\x1b[38;2;255;0;0m▄\x1b[2;1H\x1b[38;2;0;0;255m▄▄\x1b[2;4H▄\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn reuses_colors_and_moves_the_cursor_only_between_rows() {
+        let mut renderer = renderer();
+        let mut canvas = Canvas::new(3, 2, (1, 2));
+        for (x, color) in [(0, RED), (1, RED), (2, BLUE)] {
+            canvas.set(x, 1, color);
+        }
+        canvas.set(0, 2, BLUE);
+        assert_eq!(
+            draw(&mut renderer, &canvas),
+            concat!(
+                "\x1b[1;1H\x1b[48;2;0;0;0m\x1b[38;2;255;0;0m▄▄\x1b[38;2;0;0;255m▄",
+                "\x1b[2;1H\x1b[48;2;0;0;255m\x1b[38;2;0;0;0m▄\x1b[48;2;0;0;0m▄▄\x1b[0m"
+            )
+        );
+    }
+
+    #[test]
+    fn bold_text_is_switched_on_and_off() {
+        let mut renderer = renderer();
+        let mut canvas = Canvas::new(2, 1, (1, 2));
+        canvas.put_text(0, 0, "A", (255, 255, 255), None, true);
+        assert_eq!(
+            draw(&mut renderer, &canvas),
+            "\x1b[1;1H\x1b[1m\x1b[48;2;0;0;0m\x1b[38;2;255;255;255mA\x1b[22m\x1b[38;2;0;0;0m▄\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn invalidate_repaints_everything() {
+        let mut renderer = renderer();
+        let mut canvas = Canvas::new(2, 1, (1, 2));
+        canvas.set(0, 0, RED);
+        let first = draw(&mut renderer, &canvas);
+        assert_eq!(draw(&mut renderer, &canvas), "");
+        renderer.invalidate();
+        assert_eq!(draw(&mut renderer, &canvas), first);
+
+        // So does a new size
+        let canvas = Canvas::new(1, 1, (1, 2));
+        assert_eq!(draw(&mut renderer, &canvas), "\x1b[1;1H\x1b[48;2;0;0;0m\x1b[38;2;0;0;0m▄\x1b[0m");
+    }
+
+    #[test]
+    fn synchronized_frames_are_bracketed() {
+        let mut renderer = renderer();
+        renderer.set_synchronized(true);
+        let mut canvas = Canvas::new(1, 1, (1, 2));
+        assert_eq!(
+            draw(&mut renderer, &canvas),
+            "\x1b[?2026h\x1b[1;1H\x1b[48;2;0;0;0m\x1b[38;2;0;0;0m▄\x1b[0m\x1b[?2026l"
+        );
+        // No empty bracket for a frame without changes
+        assert_eq!(draw(&mut renderer, &canvas), "");
+        canvas.set(0, 0, RED);
+        assert_eq!(
+            draw(&mut renderer, &canvas),
+            "\x1b[?2026h\x1b[1;1H\x1b[48;2;255;0;0m\x1b[38;2;0;0;0m▄\x1b[0m\x1b[?2026l"
+        );
+    }
+
+    #[test]
+    fn quantizes_before_comparing() {
+        let mut renderer = Renderer::new(ColorDepth::Ansi16, PixelMode::Half, Graphics::Cells);
+        let mut canvas = Canvas::new(1, 1, (1, 2));
+        assert_eq!(draw(&mut renderer, &canvas), "\x1b[1;1H\x1b[40m\x1b[30m▄\x1b[0m");
+        // Still black in 16 colors, so nothing to send
+        canvas.set(0, 0, (20, 20, 20));
+        assert_eq!(draw(&mut renderer, &canvas), "");
+    }
+
+    #[test]
+    fn images_are_skipped_when_nothing_changed() {
+        let mut renderer = Renderer::new(ColorDepth::TrueColor, PixelMode::Half, Graphics::Kitty);
+        let canvas = Canvas::new(1, 1, (1, 2));
+        assert!(draw(&mut renderer, &canvas).starts_with("\x1b[H\x1b_Ga=d,d=A,q=2\x1b\\\x1b_Ga=T,"));
+        assert_eq!(draw(&mut renderer, &canvas), "");
+    }
+}
@@ -0,0 +1,233 @@
+/// How many pixels each terminal cell represents and which glyphs draw them.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PixelMode {
+    Half,     // 1x2 with ▄
+    Quadrant, // 2x2 with quadrant blocks
+    Sextant,  // 2x3 with sextant blocks (Unicode 13)
+    Braille,  // 2x4 with braille dots
+}
+
+// Quadrant glyphs indexed by mask (bit 0 = top-left, 1 = top-right, 2 = bottom-left, 3 = bottom-right)
+const QUADRANTS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+// Half block glyphs indexed by mask (bit 0 = top, 1 = bottom)
+const HALVES: [char; 4] = [' ', '▀', '▄', '█'];
+
+// Braille dot bit for each pixel, row-major within the 2x4 cell
+const BRAILLE_BITS: [u8; 8] = [0x01, 0x08, 0x02, 0x10, 0x04, 0x20, 0x40, 0x80];
+
+impl PixelMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "half" => Some(PixelMode::Half),
+            "quadrant" => Some(PixelMode::Quadrant),
+            "sextant" => Some(PixelMode::Sextant),
+            "braille" => Some(PixelMode::Braille),
+            _ => None,
+        }
+    }
+
+    /// Pixels per terminal cell as (columns, rows).
+    pub fn cell_size(self) -> (usize, usize) {
+        match self {
+            PixelMode::Half => (1, 2),
+            PixelMode::Quadrant => (2, 2),
+            PixelMode::Sextant => (2, 3),
+            PixelMode::Braille => (2, 4),
+        }
+    }
+
+    /// Glyph for a cell whose set bits (row-major pixel order) show the foreground color.
+    pub fn glyph(self, mask: u8) -> char {
+        match self {
+            PixelMode::Half => HALVES[mask as usize & 3],
+            PixelMode::Quadrant => QUADRANTS[mask as usize & 15],
+            PixelMode::Sextant => sextant_glyph(mask & 63),
+            PixelMode::Braille => {
+                let mut dots = 0u32;
+                for (i, bit) in BRAILLE_BITS.iter().enumerate() {
+                    if mask & (1 << i) != 0 {
+                        dots |= *bit as u32;
+                    }
+                }
+                if dots == 0 {
+                    // Blank braille renders oddly in some fonts
+                    return ' ';
+                }
+                char::from_u32(0x2800 + dots).unwrap_or(' ')
+            }
+        }
+    }
+}
+
+fn sextant_glyph(mask: u8) -> char {
+    // U+1FB00.. covers every pattern except the four that already exist as blocks
+    match mask {
+        0 => ' ',
+        21 => '▌',
+        42 => '▐',
+        63 => '█',
+        _ => {
+            let mut offset = mask as u32 - 1;
+            if mask > 21 {
+                offset -= 1;
+            }
+            if mask > 42 {
+                offset -= 1;
+            }
+            char::from_u32(0x1FB00 + offset).unwrap_or(' ')
+        }
+    }
+}
+
+fn luminance(c: (u8, u8, u8)) -> u32 {
+    c.0 as u32 * 299 + c.1 as u32 * 587 + c.2 as u32 * 114
+}
+
+fn distance_sq(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let dr = a.0 as i32 - b.0 as i32;
+    let dg = a.1 as i32 - b.1 as i32;
+    let db = a.2 as i32 - b.2 as i32;
+    (dr * dr + dg * dg + db * db) as u32
+}
+
+fn mean(pixels: &[(u8, u8, u8)], mask: u8, set: bool) -> Option<(u8, u8, u8)> {
+    let mut sum = (0u32, 0u32, 0u32);
+    let mut count = 0u32;
+    for (i, p) in pixels.iter().enumerate() {
+        if (mask & (1 << i) != 0) == set {
+            sum.0 += p.0 as u32;
+            sum.1 += p.1 as u32;
+            sum.2 += p.2 as u32;
+            count += 1;
+        }
+    }
+    if count == 0 {
+        return None;
+    }
+    Some(((sum.0 / count) as u8, (sum.1 / count) as u8, (sum.2 / count) as u8))
+}
+
+/// Approximate up to 8 pixels with two colors.
+///
+/// Returns the mask of pixels that take the foreground (the brighter group),
+/// plus the foreground and background colors. Uniform cells get an empty mask.
+pub fn split_two_colors(pixels: &[(u8, u8, u8)]) -> (u8, (u8, u8, u8), (u8, u8, u8)) {
+    // Seed the two groups with the darkest and brightest pixel
+    let mut dark = pixels[0];
+    let mut bright = pixels[0];
+    for &p in pixels {
+        if luminance(p) < luminance(dark) {
+            dark = p;
+        }
+        if luminance(p) > luminance(bright) {
+            bright = p;
+        }
+    }
+
+    if distance_sq(dark, bright) < 48 {
+        let avg = mean(pixels, 0, false).unwrap_or(dark);
+        return (0, avg, avg);
+    }
+
+    // Two rounds of 2-means are plenty for at most 8 samples
+    let mut mask = 0u8;
+    for _ in 0..2 {
+        mask = 0;
+        for (i, &p) in pixels.iter().enumerate() {
+            if distance_sq(p, bright) < distance_sq(p, dark) {
+                mask |= 1 << i;
+            }
+        }
+        bright = mean(pixels, mask, true).unwrap_or(bright);
+        dark = mean(pixels, mask, false).unwrap_or(dark);
+    }
+
+    (mask, bright, dark)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_half_and_quadrant_blocks() {
+        let half = [(0b00, ' '), (0b01, '▀'), (0b10, '▄'), (0b11, '█')];
+        for (mask, glyph) in half {
+            assert_eq!(PixelMode::Half.glyph(mask), glyph, "{:02b}", mask);
+        }
+        let quadrant = [
+            (0b0000, ' '),
+            (0b0001, '▘'),
+            (0b0010, '▝'),
+            (0b0100, '▖'),
+            (0b1000, '▗'),
+            (0b0011, '▀'),
+            (0b0101, '▌'),
+            (0b1001, '▚'),
+            (0b0110, '▞'),
+            (0b1110, '▟'),
+            (0b1111, '█'),
+        ];
+        for (mask, glyph) in quadrant {
+            assert_eq!(PixelMode::Quadrant.glyph(mask), glyph, "{:04b}", mask);
+        }
+    }
+
+    #[test]
+    fn picks_sextants_around_the_existing_blocks() {
+        let cases = [
+            (0, ' '),
+            (1, '\u{1FB00}'),  // Sextant-1
+            (20, '\u{1FB13}'), // Sextant-35, just before the left half block
+            (21, '▌'),
+            (22, '\u{1FB14}'), // Sextant-235
+            (41, '\u{1FB27}'), // Sextant-146, just before the right half block
+            (42, '▐'),
+            (43, '\u{1FB28}'), // Sextant-1246
+            (62, '\u{1FB3B}'), // Sextant-23456, the last one
+            (63, '█'),
+        ];
+        for (mask, glyph) in cases {
+            assert_eq!(PixelMode::Sextant.glyph(mask), glyph, "{}", mask);
+        }
+        // The 60 remaining patterns use each sextant character once
+        let mut sextants: Vec<char> = (1..63).filter(|&m| m != 21 && m != 42).map(|m| PixelMode::Sextant.glyph(m)).collect();
+        sextants.dedup();
+        assert_eq!(sextants.len(), 60);
+        assert!(sextants.iter().all(|&c| ('\u{1FB00}'..='\u{1FB3B}').contains(&c)));
+    }
+
+    #[test]
+    fn picks_braille_dots() {
+        let cases = [
+            (0, ' '),
+            (0b0000_0001, '⠁'), // Top left is dot 1
+            (0b0000_0010, '⠈'), // Top right is dot 4
+            (0b0101_0101, '⡇'), // The left column: dots 1, 2, 3 and 7
+            (0b1010_1010, '⢸'), // The right column: dots 4, 5, 6 and 8
+            (0b1100_0000, '⣀'),
+            (0b1111_1111, '⣿'),
+        ];
+        for (mask, glyph) in cases {
+            assert_eq!(PixelMode::Braille.glyph(mask), glyph, "{:08b}", mask);
+        }
+    }
+
+    #[test]
+    fn splits_cells_into_two_colors() {
+        const WHITE: (u8, u8, u8) = (255, 255, 255);
+        const BLACK: (u8, u8, u8) = (0, 0, 0);
+        assert_eq!(split_two_colors(&[WHITE, BLACK, BLACK, WHITE]), (0b1001, WHITE, BLACK));
+        // Groups are averaged, and the brighter one takes the foreground
+        assert_eq!(
+            split_two_colors(&[(255, 0, 0), (0, 0, 0), (201, 0, 0), (10, 10, 10), (0, 0, 0), (255, 0, 0)]),
+            (0b100101, (237, 0, 0), (3, 3, 3))
+        );
+        // Nearly uniform cells are one color
+        assert_eq!(split_two_colors(&[(10, 10, 10), (12, 12, 12)]), (0, (11, 11, 11), (11, 11, 11)));
+        assert_eq!(split_two_colors(&[WHITE; 8]), (0, WHITE, WHITE));
+    }
+}
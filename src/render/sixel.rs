@@ -0,0 +1,188 @@
+use super::color::{nearest_256, palette_rgb};
+use std::io::Write;
+
+/// Encodes frames as sixel images.
+///
+/// Colors are reduced to the xterm 256-color palette (only the entries a
+/// frame uses are defined), and each canvas pixel is blown up to a
+/// `scale.0` x `scale.1` block so the image covers the cells it replaces.
+pub struct SixelEncoder {
+    indices: Vec<u8>,
+    line: Vec<u8>,
+}
+
+impl SixelEncoder {
+    pub fn new() -> Self {
+        Self {
+            indices: Vec::new(),
+            line: Vec::new(),
+        }
+    }
+
+    /// Append a complete sixel image of `pixels` (`size` in canvas pixels).
+    pub fn encode(
+        &mut self,
+        pixels: &[(u8, u8, u8)],
+        size: (usize, usize),
+        scale: (usize, usize),
+        out: &mut Vec<u8>,
+    ) -> std::io::Result<()> {
+        let (width, height) = size;
+        let (scale_x, scale_y) = scale;
+        let out_width = width * scale_x;
+        let out_height = height * scale_y;
+
+        self.indices.clear();
+        self.indices.extend(pixels.iter().map(|&p| nearest_256(p)));
+
+        let mut used = [false; 256];
+        for &i in &self.indices {
+            used[i as usize] = true;
+        }
+
+        // Pixel aspect 1:1, background painted by us, then the raster size
+        write!(out, "\x1bP0;1;0q\"1;1;{};{}", out_width, out_height)?;
+        for (n, _) in used.iter().enumerate().filter(|(_, u)| **u) {
+            let (r, g, b) = palette_rgb(n as u8);
+            // Sixel color components are percentages
+            write!(
+                out,
+                "#{};2;{};{};{}",
+                n,
+                r as u32 * 100 / 255,
+                g as u32 * 100 / 255,
+                b as u32 * 100 / 255
+            )?;
+        }
+
+        // Each band is six output rows tall
+        for band_top in (0..out_height).step_by(6) {
+            let mut source_rows = [None; 6];
+            for (bit, row) in source_rows.iter_mut().enumerate() {
+                let y = band_top + bit;
+                if y < out_height {
+                    *row = Some(y / scale_y);
+                }
+            }
+
+            let mut band_colors = [false; 256];
+            for y in source_rows.iter().flatten() {
+                for &i in &self.indices[y * width..(y + 1) * width] {
+                    band_colors[i as usize] = true;
+                }
+            }
+
+            let mut first = true;
+            for color in (0..256).filter(|&c| band_colors[c]) {
+                self.line.clear();
+                for x in 0..width {
+                    let mut bits = 0u8;
+                    for (bit, row) in source_rows.iter().enumerate() {
+                        if let Some(y) = row
+                            && self.indices[y * width + x] as usize == color
+                        {
+                            bits |= 1 << bit;
+                        }
+                    }
+                    for _ in 0..scale_x {
+                        self.line.push(b'?' + bits);
+                    }
+                }
+
+                // Trailing empty sixels draw nothing
+                while self.line.last() == Some(&b'?') {
+                    self.line.pop();
+                }
+                if self.line.is_empty() {
+                    continue;
+                }
+
+                if !first {
+                    out.push(b'$');
+                }
+                first = false;
+                write!(out, "#{}", color)?;
+                write_run_length(&self.line, out)?;
+            }
+            if band_top + 6 < out_height {
+                out.push(b'-');
+            }
+        }
+
+        out.extend_from_slice(b"\x1b\\");
+        Ok(())
+    }
+}
+
+fn write_run_length(line: &[u8], out: &mut Vec<u8>) -> std::io::Result<()> {
+    let mut i = 0;
+    while i < line.len() {
+        let ch = line[i];
+        let mut run = 1;
+        while i + run < line.len() && line[i + run] == ch {
+            run += 1;
+        }
+        if run > 3 {
+            write!(out, "!{}", run)?;
+            out.push(ch);
+        } else {
+            for _ in 0..run {
+                out.push(ch);
+            }
+        }
+        i += run;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLACK: (u8, u8, u8) = (0, 0, 0);
+    const RED: (u8, u8, u8) = (255, 0, 0);
+
+    fn encode(pixels: &[(u8, u8, u8)], size: (usize, usize), scale: (usize, usize)) -> String {
+        let mut out = Vec::new();
+        SixelEncoder::new().encode(pixels, size, scale, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn encodes_each_color_as_its_own_pass() {
+        assert_eq!(
+            encode(&[BLACK, RED, RED, BLACK], (2, 2), (1, 1)),
+            "\x1bP0;1;0q\"1;1;2;2#16;2;0;0;0#196;2;100;0;0#16@A$#196A@\x1b\\"
+        );
+    }
+
+    #[test]
+    fn scales_pixels_across_bands() {
+        // Seven output rows need a second band holding just the last one
+        assert_eq!(
+            encode(&[(255, 255, 255); 3], (3, 1), (2, 7)),
+            "\x1bP0;1;0q\"1;1;6;7#231;2;100;100;100#231!6~-#231!6@\x1b\\"
+        );
+    }
+
+    #[test]
+    fn trims_trailing_empty_sixels() {
+        assert_eq!(encode(&[RED, BLACK, BLACK], (3, 1), (1, 1)), "\x1bP0;1;0q\"1;1;3;1#16;2;0;0;0#196;2;100;0;0#16?@@$#196@\x1b\\");
+    }
+
+    #[test]
+    fn defines_only_the_palette_entries_in_use() {
+        // A cube color and a gray, with components as truncated percentages
+        assert_eq!(
+            encode(&[(95, 135, 175), (128, 128, 128)], (2, 1), (1, 1)),
+            "\x1bP0;1;0q\"1;1;2;1#67;2;37;52;68#244;2;50;50;50#67@$#244?@\x1b\\"
+        );
+    }
+
+    #[test]
+    fn run_length_encodes_runs_longer_than_three() {
+        let mut out = Vec::new();
+        write_run_length(b"aaabbbbc~~~~~~~~~~~~", &mut out).unwrap();
+        assert_eq!(out, b"aaa!4bc!12~");
+    }
+}
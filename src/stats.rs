@@ -0,0 +1,67 @@
+use std::time::{Duration, Instant};
+
+const BUCKET_MICROS: u64 = 100;
+const BUCKETS: usize = 1000; // 0.1 ms buckets up to 100 ms; slower frames land in the last one
+
+/// Frame times gathered while running, for `--show-fps`.
+pub struct FrameStats {
+    started: Instant,
+    budget: Duration,
+    frames: u64,
+    total: Duration,
+    max: Duration,
+    histogram: Vec<u32>,
+}
+
+impl FrameStats {
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            started: Instant::now(),
+            budget,
+            frames: 0,
+            total: Duration::ZERO,
+            max: Duration::ZERO,
+            histogram: vec![0; BUCKETS],
+        }
+    }
+
+    /// Record the time spent updating, rendering and writing one frame.
+    pub fn record(&mut self, work: Duration) {
+        self.frames += 1;
+        self.total += work;
+        self.max = self.max.max(work);
+        let bucket = (work.as_micros() as u64 / BUCKET_MICROS).min(BUCKETS as u64 - 1);
+        self.histogram[bucket as usize] += 1;
+    }
+
+    /// Frame time below which `fraction` of frames finished.
+    fn percentile(&self, fraction: f64) -> Duration {
+        let target = (self.frames as f64 * fraction).ceil() as u64;
+        let mut seen = 0;
+        for (bucket, &count) in self.histogram.iter().enumerate() {
+            seen += count as u64;
+            if seen >= target {
+                return Duration::from_micros((bucket as u64 + 1) * BUCKET_MICROS);
+            }
+        }
+        self.max
+    }
+
+    pub fn report(&self) -> String {
+        if self.frames == 0 {
+            return "no frames drawn\n".to_string();
+        }
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        format!(
+            "frames: {} in {:.1}s ({:.1} fps)\nframe time: avg {:.2} ms, p95 {:.1} ms, max {:.2} ms (budget {:.1} ms)\n",
+            self.frames,
+            elapsed,
+            self.frames as f64 / elapsed,
+            ms(self.total) / self.frames as f64,
+            ms(self.percentile(0.95)),
+            ms(self.max),
+            ms(self.budget)
+        )
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::VecDeque;
+use std::ffi::CString;
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const SAMPLE_EVERY: Duration = Duration::from_secs(1);
+const HISTORY: usize = 16; // Samples kept for each sparkline
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Load, memory, network and disk usage for the `system` overlay.
+///
+/// Everything but disk usage comes from files under `proc_root`, so the
+/// overlay can be pointed at a fake `/proc` to try it out.
+pub struct SystemStats {
+    proc_root: PathBuf,
+    disk: PathBuf,
+    sampled: Option<Instant>,
+    last_net: Option<(u64, u64, Instant)>, // Bytes received and sent, and when
+    load: VecDeque<f32>,
+    memory: VecDeque<f32>,
+    net: VecDeque<f32>,
+    lines: Vec<String>,
+}
+
+impl SystemStats {
+    pub fn new(proc_root: PathBuf, disk: PathBuf) -> Self {
+        Self {
+            proc_root,
+            disk,
+            sampled: None,
+            last_net: None,
+            load: VecDeque::new(),
+            memory: VecDeque::new(),
+            net: VecDeque::new(),
+            lines: Vec::new(),
+        }
+    }
+
+    /// The overlay's text, re-sampled at most once a second.
+    pub fn lines(&mut self) -> &[String] {
+        if self.sampled.is_none_or(|sampled| sampled.elapsed() >= SAMPLE_EVERY) {
+            self.sample();
+        }
+        &self.lines
+    }
+
+    fn sample(&mut self) {
+        self.sampled = Some(Instant::now());
+        self.lines.clear();
+
+        // A file that can't be read just leaves its line out
+        if let Some(load) = read_loadavg(&self.proc_root.join("loadavg")) {
+            push(&mut self.load, load[0]);
+            let top = self.load.iter().copied().fold(1.0, f32::max);
+            let numbers = format!("{:.2} {:.2} {:.2}", load[0], load[1], load[2]);
+            self.lines.push(line("load", &numbers, Some(sparkline(&self.load, top))));
+        }
+        if let Some((total, available)) = read_meminfo(&self.proc_root.join("meminfo")) {
+            let used = total.saturating_sub(available);
+            push(&mut self.memory, used as f32 / total.max(1) as f32);
+            let numbers = format!("{}/{} {:.0}%", format_bytes(used), format_bytes(total), used as f32 * 100.0 / total.max(1) as f32);
+            self.lines.push(line("mem", &numbers, Some(sparkline(&self.memory, 1.0))));
+        }
+        if let Some((received, sent)) = read_net_dev(&self.proc_root.join("net/dev")) {
+            let now = Instant::now();
+            // Rates need two samples; counters that went backwards mean an interface went away
+            let rates = self.last_net.map(|(last_received, last_sent, at)| {
+                let seconds = now.duration_since(at).as_secs_f32().max(0.001);
+                (received.saturating_sub(last_received) as f32 / seconds, sent.saturating_sub(last_sent) as f32 / seconds)
+            });
+            self.last_net = Some((received, sent, now));
+            if let Some((down, up)) = rates {
+                push(&mut self.net, down + up);
+                let top = self.net.iter().copied().fold(1.0, f32::max);
+                let numbers = format!("↓{}/s ↑{}/s", format_bytes(down as u64), format_bytes(up as u64));
+                self.lines.push(line("net", &numbers, Some(sparkline(&self.net, top))));
+            } else {
+                self.lines.push(line("net", "…", None));
+            }
+        }
+        if let Some((used, available)) = disk_usage(&self.disk) {
+            let numbers = format!(
+                "{:.0}% {} free",
+                used as f32 * 100.0 / (used + available).max(1) as f32,
+                format_bytes(available)
+            );
+            self.lines.push(line("disk", &numbers, None));
+        }
+    }
+}
+
+fn push(history: &mut VecDeque<f32>, value: f32) {
+    if history.len() == HISTORY {
+        history.pop_front();
+    }
+    history.push_back(value);
+}
+
+/// A label, its numbers and an optional sparkline, in columns that line up.
+fn line(label: &str, numbers: &str, sparkline: Option<String>) -> String {
+    match sparkline {
+        Some(sparkline) => format!("{:<5}{:<17} {}", label, numbers, sparkline),
+        None => format!("{:<5}{}", label, numbers),
+    }
+}
+
+/// One block character per sample, scaled so `top` is a full block.
+fn sparkline(history: &VecDeque<f32>, top: f32) -> String {
+    history
+        .iter()
+        .map(|&value| {
+            let level = (value / top * SPARKS.len() as f32) as usize;
+            SPARKS[level.min(SPARKS.len() - 1)]
+        })
+        .collect()
+}
+
+/// `1536` becomes `1.5K`; binary units, as `free -h` and `df -h` use.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 || value >= 100.0 {
+        format!("{:.0}{}", value, UNITS[unit])
+    } else {
+        format!("{:.1}{}", value, UNITS[unit])
+    }
+}
+
+/// The 1, 5 and 15 minute load averages.
+fn read_loadavg(path: &Path) -> Option<[f32; 3]> {
+    let text = fs::read_to_string(path).ok()?;
+    let mut fields = text.split_whitespace().map(|field| field.parse().ok());
+    Some([fields.next()??, fields.next()??, fields.next()??])
+}
+
+/// Total and available memory in bytes.
+fn read_meminfo(path: &Path) -> Option<(u64, u64)> {
+    let text = fs::read_to_string(path).ok()?;
+    let field = |name: &str| -> Option<u64> {
+        let line = text.lines().find(|line| line.split(':').next() == Some(name))?;
+        let kilobytes: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+        kilobytes.checked_mul(1024)
+    };
+    let total = field("MemTotal")?;
+    // Kernels before 3.14 have no MemAvailable
+    let estimate = || field("MemFree")?.checked_add(field("Buffers")?)?.checked_add(field("Cached")?);
+    let available = field("MemAvailable").or_else(estimate)?;
+    Some((total, available))
+}
+
+/// Bytes received and sent over all interfaces except loopback.
+fn read_net_dev(path: &Path) -> Option<(u64, u64)> {
+    let text = fs::read_to_string(path).ok()?;
+    let mut totals = (0, 0);
+    // Two header lines, then `iface: rx_bytes packets ... (8 receive fields) tx_bytes ...`
+    for line in text.lines().skip(2) {
+        let (name, counters) = line.split_once(':')?;
+        if name.trim() == "lo" {
+            continue;
+        }
+        let counters: Vec<u64> = counters.split_whitespace().map(|c| c.parse().unwrap_or(0)).collect();
+        totals.0 = counters.first().map_or(totals.0, |&c| c.saturating_add(totals.0));
+        totals.1 = counters.get(8).map_or(totals.1, |&c| c.saturating_add(totals.1));
+    }
+    Some(totals)
+}
+
+/// Used and available bytes on the filesystem holding `path`, counted as `df` does.
+fn disk_usage(path: &Path) -> Option<(u64, u64)> {
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: statvfs only writes to the struct we pass it
+    let stats = unsafe {
+        let mut stats: libc::statvfs = std::mem::zeroed();
+        if libc::statvfs(path.as_ptr(), &mut stats) != 0 {
+            return None;
+        }
+        stats
+    };
+    let block = stats.f_frsize as u64;
+    let used = (stats.f_blocks as u64).saturating_sub(stats.f_bfree as u64) * block;
+    Some((used, stats.f_bavail as u64 * block))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NET_HEADER: &str = "Inter-|   Receive                                                |  Transmit\n face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed\n";
+
+    /// A scratch directory standing in for `/proc`, removed on drop.
+    struct ProcRoot(PathBuf);
+
+    impl ProcRoot {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("termsaver-sysstats-{}-{}", std::process::id(), name));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(path.join("net")).unwrap();
+            Self(path)
+        }
+
+        fn write(&self, file: &str, text: &str) {
+            fs::write(self.0.join(file), text).unwrap();
+        }
+
+        fn net_dev(&self, interfaces: &[(&str, u64, u64)]) {
+            let mut text = NET_HEADER.to_string();
+            for (name, received, sent) in interfaces {
+                text += &format!("{:>6}: {} 10 0 0 0 0 0 0 {} 10 0 0 0 0 0 0\n", name, received, sent);
+            }
+            self.write("net/dev", &text);
+        }
+
+        fn stats(&self) -> SystemStats {
+            SystemStats::new(self.0.clone(), self.0.clone())
+        }
+    }
+
+    impl Drop for ProcRoot {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn resample(stats: &mut SystemStats) -> Vec<String> {
+        stats.sampled = None;
+        stats.lines().to_vec()
+    }
+
+    #[test]
+    fn shows_load_and_memory() {
+        let root = ProcRoot::new("load");
+        root.write("loadavg", "0.52 1.25 2.00 2/345 6789\n");
+        root.write("meminfo", "MemTotal:       16384000 kB\nMemFree:         1000000 kB\nMemAvailable:    4096000 kB\n");
+        let lines = root.stats().lines().to_vec();
+        assert_eq!(lines[0], format!("load {:<17} {}", "0.52 1.25 2.00", '▅'));
+        assert_eq!(lines[1], format!("mem  {:<17} {}", "11.7G/15.6G 75%", '▇'));
+        assert!(lines[2].starts_with("disk "), "{:?}", lines);
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn estimates_memory_without_mem_available() {
+        let root = ProcRoot::new("meminfo");
+        root.write("meminfo", "MemTotal: 1000 kB\nMemFree: 100 kB\nBuffers: 50 kB\nCached: 350 kB\n");
+        assert_eq!(read_meminfo(&root.0.join("meminfo")), Some((1000 * 1024, 500 * 1024)));
+        root.write("meminfo", "MemTotal: 1000 kB\nMemFree: 100 kB\n");
+        assert_eq!(read_meminfo(&root.0.join("meminfo")), None);
+        root.write("meminfo", "MemTotal: 18446744073709551615 kB\nMemAvailable: 1 kB\n");
+        assert_eq!(read_meminfo(&root.0.join("meminfo")), None);
+    }
+
+    #[test]
+    fn net_rates_skip_loopback_and_counter_resets() {
+        let root = ProcRoot::new("net");
+        root.net_dev(&[("lo", 5000, 5000), ("eth0", 1000, 2000), ("wlan0", 100, 200)]);
+        assert_eq!(read_net_dev(&root.0.join("net/dev")), Some((1100, 2200)));
+
+        let mut stats = root.stats();
+        // The first sample has nothing to compare against
+        assert!(stats.lines().contains(&"net  …".to_string()));
+
+        // An interface going away makes the totals drop; that reads as no traffic, not a huge rate
+        root.net_dev(&[("lo", 9000, 9000), ("eth0", 1000, 2000)]);
+        let lines = resample(&mut stats);
+        let net = lines.iter().find(|line| line.starts_with("net")).unwrap();
+        assert!(net.starts_with(&format!("net  {:<17} ", "↓0B/s ↑0B/s")), "{}", net);
+        assert!(net.ends_with('▁'));
+    }
+
+    #[test]
+    fn leaves_out_unreadable_files() {
+        let root = ProcRoot::new("missing");
+        root.write("loadavg", "not a number\n");
+        let lines = SystemStats::new(root.0.clone(), root.0.join("nowhere")).lines().to_vec();
+        assert!(lines.is_empty(), "{:?}", lines);
+    }
+
+    #[test]
+    fn formats_bytes_like_df() {
+        assert_eq!(format_bytes(0), "0B");
+        assert_eq!(format_bytes(1023), "1023B");
+        assert_eq!(format_bytes(1536), "1.5K");
+        assert_eq!(format_bytes(150 * 1024 * 1024), "150M");
+        assert_eq!(format_bytes(u64::MAX), "16777216T");
+    }
+
+    #[test]
+    fn sparkline_scales_to_top() {
+        let history: VecDeque<f32> = [0.0, 0.25, 0.5, 1.0, 2.0].into_iter().collect();
+        assert_eq!(sparkline(&history, 1.0), "▁▃▅██");
+        assert_eq!(sparkline(&history, 2.0), "▁▂▃▅█");
+        assert_eq!(sparkline(&VecDeque::new(), 1.0), "");
+    }
+}
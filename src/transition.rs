@@ -0,0 +1,160 @@
+use crate::effects::fire::PALETTE;
+use crate::effects::{Canvas, Effect};
+use fastrand::Rng;
+use noise::{NoiseFn, Perlin};
+
+// Fraction of the progress range taken up by soft edges and the burning front
+const EDGE: f32 = 0.15;
+
+/// How one effect gives way to the next.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TransitionKind {
+    Cut,
+    Crossfade,
+    Wipe,
+    Dissolve,
+    Burn,
+}
+
+impl TransitionKind {
+    pub const ALL: [TransitionKind; 4] = [
+        TransitionKind::Crossfade,
+        TransitionKind::Wipe,
+        TransitionKind::Dissolve,
+        TransitionKind::Burn,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "cut" | "none" => Some(TransitionKind::Cut),
+            "crossfade" | "fade" => Some(TransitionKind::Crossfade),
+            "wipe" => Some(TransitionKind::Wipe),
+            "dissolve" => Some(TransitionKind::Dissolve),
+            "burn" => Some(TransitionKind::Burn),
+            _ => None,
+        }
+    }
+}
+
+/// An outgoing effect being blended into the incoming one.
+///
+/// The outgoing effect keeps updating and rendering into its own canvas for
+/// the duration of the transition; `composite` then mixes it into the canvas
+/// the incoming effect just drew.
+pub struct Transition {
+    kind: TransitionKind,
+    outgoing: Box<dyn Effect>,
+    canvas: Canvas,
+    elapsed: f32,
+    duration: f32,
+    thresholds: Vec<f32>, // Per-pixel switch point in 0..1 for dissolve and burn
+}
+
+impl Transition {
+    /// Start blending `outgoing` away. Dissolve and burn draw their pattern from `rng`.
+    pub fn new(kind: TransitionKind, outgoing: Box<dyn Effect>, canvas: &Canvas, duration: f32, mut rng: Rng) -> Self {
+        let width = canvas.width();
+        let height = canvas.height();
+        let (cell_w, cell_h) = crate::get_pixel_mode().cell_size();
+
+        let thresholds = match kind {
+            TransitionKind::Dissolve => (0..width * height).map(|_| rng.f32()).collect(),
+            TransitionKind::Burn => {
+                // Ragged noise front that starts at the bottom and climbs up
+                let perlin = Perlin::new(rng.u32(..));
+                let mut thresholds = Vec::with_capacity(width * height);
+                for y in 0..height {
+                    for x in 0..width {
+                        let n = perlin.get([x as f64 * 0.06, y as f64 * 0.06]) as f32 * 0.5 + 0.5;
+                        let rise = 1.0 - y as f32 / height as f32;
+                        thresholds.push((rise * 0.6 + n * 0.4).clamp(0.0, 1.0));
+                    }
+                }
+                thresholds
+            }
+            _ => Vec::new(),
+        };
+
+        Self {
+            kind,
+            outgoing,
+            canvas: Canvas::new(canvas.cols(), canvas.rows(), (cell_w, cell_h)),
+            elapsed: 0.0,
+            duration: duration.max(0.001),
+            thresholds,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.outgoing.update(dt);
+        self.elapsed += dt;
+    }
+
+    pub fn finished(&self) -> bool {
+        self.kind == TransitionKind::Cut || self.elapsed >= self.duration
+    }
+
+    /// Mix the outgoing effect into `incoming`, which already holds the new effect's frame.
+    pub fn composite(&mut self, incoming: &mut Canvas) {
+        if self.kind == TransitionKind::Cut {
+            return;
+        }
+
+        self.canvas.clear_text();
+        self.outgoing.render(&mut self.canvas);
+
+        let progress = (self.elapsed / self.duration).clamp(0.0, 1.0);
+        let width = incoming.width();
+        let height = incoming.height();
+
+        for y in 0..height {
+            for x in 0..width {
+                let old = self.canvas.get(x, y);
+                let new = incoming.get(x, y);
+                let color = match self.kind {
+                    TransitionKind::Crossfade => {
+                        let t = progress * progress * (3.0 - 2.0 * progress);
+                        mix(old, new, t)
+                    }
+                    TransitionKind::Wipe => {
+                        // Soft vertical edge sweeping left to right
+                        let edge = progress * (1.0 + EDGE) - x as f32 / width as f32;
+                        mix(old, new, (edge / EDGE).clamp(0.0, 1.0))
+                    }
+                    TransitionKind::Dissolve => {
+                        if self.thresholds[y * width + x] < progress { new } else { old }
+                    }
+                    TransitionKind::Burn => {
+                        let front = progress * (1.0 + EDGE);
+                        let t = self.thresholds[y * width + x];
+                        if t < front - EDGE {
+                            new
+                        } else if t < front {
+                            // Hottest in the middle of the burning band
+                            let f = (t - (front - EDGE)) / EDGE;
+                            let heat = 1.0 - (2.0 * f - 1.0).abs();
+                            PALETTE[(heat * (PALETTE.len() - 1) as f32) as usize]
+                        } else {
+                            old
+                        }
+                    }
+                    TransitionKind::Cut => new,
+                };
+                incoming.set(x, y, color);
+            }
+        }
+
+        // Text can't be blended, so it switches over halfway
+        if progress < 0.5 {
+            incoming.copy_text_from(&self.canvas);
+        }
+    }
+}
+
+fn mix(a: (u8, u8, u8), b: (u8, u8, u8), t: f32) -> (u8, u8, u8) {
+    (
+        (a.0 as f32 + (b.0 as f32 - a.0 as f32) * t) as u8,
+        (a.1 as f32 + (b.1 as f32 - a.1 as f32) * t) as u8,
+        (a.2 as f32 + (b.2 as f32 - a.2 as f32) * t) as u8,
+    )
+}
@@ -0,0 +1,246 @@
+use crate::config;
+use crate::effects::{self, Canvas, Effect, EffectInfo, Param, ParamKind, ParamValue};
+use crossterm::event::{Event, KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use std::path::Path;
+
+const FG: (u8, u8, u8) = (230, 230, 230);
+const DIM: (u8, u8, u8) = (150, 150, 170);
+const ACCENT: (u8, u8, u8) = (120, 190, 255);
+const SHADE: (u8, u8, u8) = (12, 12, 24);
+const SHADE_ALPHA: f32 = 0.75; // How much of the effect is hidden behind the panel
+const SLIDER_WIDTH: usize = 17;
+const HINT: &str = "↑↓ select  ←→ adjust  s save  Tab close";
+
+/// What the main loop should do after the panel saw an event.
+pub enum Response {
+    Ignored, // Not for the panel; pass it on to the effect
+    Handled,
+    Changed(&'static str, ParamValue),
+    Save,
+    Close,
+}
+
+/// Where the last frame put the panel, for mapping mouse positions.
+struct Layout {
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+    first_param_row: usize,
+    slider_col: usize,
+}
+
+/// An overlay listing the running effect's parameters as adjustable sliders.
+pub struct TweakPanel {
+    selected: usize,
+    dragging: bool,
+    message: Option<String>,
+    layout: Option<Layout>,
+}
+
+impl TweakPanel {
+    pub fn new() -> Self {
+        Self {
+            selected: 0,
+            dragging: false,
+            message: None,
+            layout: None,
+        }
+    }
+
+    /// Show a line of feedback (e.g. where the settings were saved) under the sliders.
+    pub fn set_message(&mut self, message: String) {
+        self.message = Some(message);
+    }
+
+    pub fn handle_event(&mut self, event: &Event, info: &EffectInfo, effect: &mut dyn Effect) -> Response {
+        let params = info.params(effect);
+        match event {
+            Event::Key(key) => {
+                let multiplier = if key.modifiers.contains(KeyModifiers::SHIFT) { 10.0 } else { 1.0 };
+                match key.code {
+                    KeyCode::Tab | KeyCode::Esc => Response::Close,
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        self.selected = self.selected.saturating_sub(1);
+                        Response::Handled
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        self.selected = (self.selected + 1).min(params.len().saturating_sub(1));
+                        Response::Handled
+                    }
+                    KeyCode::Left => self.adjust(info, effect, &params, -multiplier),
+                    KeyCode::Right | KeyCode::Enter => self.adjust(info, effect, &params, multiplier),
+                    KeyCode::Char('s') => Response::Save,
+                    _ => Response::Ignored,
+                }
+            }
+            Event::Mouse(MouseEvent { kind, column, row, .. }) => {
+                let Some(layout) = &self.layout else {
+                    return Response::Ignored;
+                };
+                let (col, row) = (*column as usize, *row as usize);
+                let inside = (layout.left..layout.left + layout.width).contains(&col)
+                    && (layout.top..layout.top + layout.height).contains(&row);
+                match kind {
+                    MouseEventKind::Down(MouseButton::Left) if inside => {
+                        if let Some(index) = row.checked_sub(layout.first_param_row).filter(|&i| i < params.len()) {
+                            self.selected = index;
+                            self.dragging = true;
+                            return self.slide(info, effect, &params, col);
+                        }
+                        Response::Handled
+                    }
+                    MouseEventKind::Drag(MouseButton::Left) if self.dragging => self.slide(info, effect, &params, col),
+                    MouseEventKind::Up(MouseButton::Left) if self.dragging => {
+                        self.dragging = false;
+                        Response::Handled
+                    }
+                    _ if inside => Response::Handled,
+                    _ => Response::Ignored,
+                }
+            }
+            _ => Response::Ignored,
+        }
+    }
+
+    /// Step the selected parameter by `amount` steps.
+    fn adjust(&mut self, info: &EffectInfo, effect: &mut dyn Effect, params: &[Param], amount: f32) -> Response {
+        let Some(param) = params.get(self.selected) else {
+            return Response::Handled;
+        };
+        let value = match (param.kind, &param.value) {
+            (ParamKind::Float { min, max, step }, ParamValue::Float(v)) => {
+                // Snap to the step grid so repeated presses don't drift
+                let snapped = ((v + step * amount) / step).round() * step;
+                ParamValue::Float(snapped.clamp(min, max))
+            }
+            (ParamKind::Int { min, max }, ParamValue::Int(v)) => ParamValue::Int((v + amount as i64).clamp(min, max)),
+            (ParamKind::Choice(choices), ParamValue::Text(v)) => {
+                let index = choices.iter().position(|c| c == v).unwrap_or(0) as i64;
+                let next = (index + amount.signum() as i64).rem_euclid(choices.len() as i64);
+                ParamValue::Text(choices[next as usize].to_string())
+            }
+            _ => return Response::Handled, // Text can only be set with --set or the config file
+        };
+        self.apply(info, effect, param.name, value)
+    }
+
+    /// Set the selected parameter from a mouse position on its slider.
+    fn slide(&mut self, info: &EffectInfo, effect: &mut dyn Effect, params: &[Param], col: usize) -> Response {
+        let (Some(layout), Some(param)) = (&self.layout, params.get(self.selected)) else {
+            return Response::Handled;
+        };
+        let t = (col as f32 - layout.slider_col as f32) / (SLIDER_WIDTH - 1) as f32;
+        let t = t.clamp(0.0, 1.0);
+        let value = match param.kind {
+            ParamKind::Float { min, max, step } => {
+                let snapped = ((min + t * (max - min)) / step).round() * step;
+                ParamValue::Float(snapped.clamp(min, max))
+            }
+            ParamKind::Int { min, max } => ParamValue::Int(min + (t * (max - min) as f32).round() as i64),
+            ParamKind::Choice(choices) => {
+                ParamValue::Text(choices[(t * (choices.len() - 1) as f32).round() as usize].to_string())
+            }
+            ParamKind::Text(_) => return Response::Handled,
+        };
+        if value == param.value {
+            return Response::Handled;
+        }
+        self.apply(info, effect, param.name, value)
+    }
+
+    fn apply(&mut self, info: &EffectInfo, effect: &mut dyn Effect, name: &'static str, value: ParamValue) -> Response {
+        match effects::apply_param(info, effect, name, value.clone()) {
+            Ok(()) => {
+                self.message = None;
+                Response::Changed(name, value)
+            }
+            Err(message) => {
+                self.message = Some(message);
+                Response::Handled
+            }
+        }
+    }
+
+    pub fn draw(&mut self, canvas: &mut Canvas, info: &EffectInfo, params: &[Param]) {
+        let mut lines: Vec<(String, (u8, u8, u8), bool)> = Vec::new();
+        lines.push((format!("Tweak {}", info.name), FG, true));
+        lines.push((String::new(), FG, false));
+        let first_param_line = lines.len();
+
+        let name_width = params.iter().map(|p| p.name.len()).max().unwrap_or(0);
+        if params.is_empty() {
+            lines.push(("This effect has no parameters".to_string(), DIM, false));
+        }
+        self.selected = self.selected.min(params.len().saturating_sub(1));
+        for (i, param) in params.iter().enumerate() {
+            let marker = if i == self.selected { '▸' } else { ' ' };
+            let line = format!("{} {:<width$}  {}  {}", marker, param.name, slider(param), param.value_text(), width = name_width);
+            let color = if i == self.selected { ACCENT } else { FG };
+            lines.push((line, color, i == self.selected));
+        }
+
+        lines.push((String::new(), FG, false));
+        lines.push((HINT.to_string(), DIM, false));
+        if let Some(message) = &self.message {
+            lines.push((message.clone(), DIM, false));
+        }
+
+        let inner_width = lines.iter().map(|(text, _, _)| text.chars().count()).max().unwrap_or(0);
+        let width = (inner_width + 4).min(canvas.cols());
+        let height = (lines.len() + 2).min(canvas.rows());
+        // Top right, out of the way of effects that draw their own text top left
+        let left = canvas.cols().saturating_sub(width + 1);
+        let top = 1.min(canvas.rows() - height);
+
+        canvas.darken_cells((left, top), (width, height), SHADE, SHADE_ALPHA);
+        for (i, (text, color, bold)) in lines.iter().enumerate().take(height.saturating_sub(2)) {
+            let clipped: String = text.chars().take(width.saturating_sub(4)).collect();
+            canvas.put_text(left + 2, top + 1 + i, &clipped, *color, None, *bold);
+        }
+
+        self.layout = Some(Layout {
+            left,
+            top,
+            width,
+            height,
+            first_param_row: top + 1 + first_param_line,
+            slider_col: left + 2 + 2 + name_width + 2,
+        });
+    }
+}
+
+/// Write the effect's current parameters into its section of the config file.
+pub fn save(path: &Path, info: &EffectInfo, params: &[Param]) -> Result<(), String> {
+    let values: Vec<(&str, String)> = params
+        .iter()
+        .map(|param| {
+            let literal = match param.value {
+                ParamValue::Text(ref text) => config::quote(text),
+                _ => param.value_text(),
+            };
+            (param.name, literal)
+        })
+        .collect();
+    let names: Vec<&str> = std::iter::once(info.name).chain(info.aliases.iter().copied()).collect();
+    config::update_section(path, &names, &values)
+}
+
+fn slider(param: &Param) -> String {
+    let fraction = match (param.kind, &param.value) {
+        (ParamKind::Float { min, max, .. }, ParamValue::Float(v)) => (v - min) / (max - min),
+        (ParamKind::Int { min, max }, ParamValue::Int(v)) => (v - min) as f32 / (max - min) as f32,
+        (ParamKind::Choice(choices), ParamValue::Text(v)) if choices.len() > 1 => {
+            choices.iter().position(|c| c == v).unwrap_or(0) as f32 / (choices.len() - 1) as f32
+        }
+        _ => return " ".repeat(SLIDER_WIDTH),
+    };
+    let knob = (fraction.clamp(0.0, 1.0) * (SLIDER_WIDTH - 1) as f32).round() as usize;
+    (0..SLIDER_WIDTH)
+        .map(|i| match i.cmp(&knob) {
+            std::cmp::Ordering::Less => '━',
+            std::cmp::Ordering::Equal => '●',
+            std::cmp::Ordering::Greater => '─',
+        })
+        .collect()
+}
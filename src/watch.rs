@@ -0,0 +1,214 @@
+use crossterm::terminal;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Set in the shell's environment, so a shell startup file can start `watch`
+/// without it starting itself again inside.
+pub const ENV_VAR: &str = "TERMSAVER_WATCH";
+
+static RESIZED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_resize(_: libc::c_int) {
+    RESIZED.store(true, Ordering::Relaxed);
+}
+
+/// Follows the shell's switches to and from the alternate screen, which the
+/// screensaver's own alternate screen wipes out.
+#[derive(Default)]
+struct ScreenTracker {
+    alternate: bool,
+    tail: Vec<u8>, // End of the previous output, in case a sequence was split across reads
+}
+
+impl ScreenTracker {
+    const TAIL: usize = 16;
+
+    fn scan(&mut self, output: &[u8]) {
+        let mut data = std::mem::take(&mut self.tail);
+        data.extend_from_slice(output);
+        // Private modes 1049, 1047 and 47 all mean the alternate screen
+        let mut i = 0;
+        while let Some(start) = data[i..].windows(3).position(|w| w == b"\x1b[?") {
+            let params = i + start + 3;
+            let Some(length) = data[params..].iter().position(|b| !b.is_ascii_digit() && *b != b';') else {
+                break;
+            };
+            let end = params + length;
+            if matches!(data[end], b'h' | b'l')
+                && data[params..end].split(|b| *b == b';').any(|mode| matches!(mode, b"1049" | b"1047" | b"47"))
+            {
+                self.alternate = data[end] == b'h';
+            }
+            i = end;
+        }
+        // Seeing a sequence again next time is harmless, so just keep the last few bytes
+        self.tail = data[data.len().saturating_sub(Self::TAIL)..].to_vec();
+    }
+}
+
+/// Run the user's shell in a pseudo-terminal, passing input and output
+/// through, and call `saver` whenever no key has been pressed for `idle`.
+/// `saver` returns at the next key press and the shell's screen comes back.
+///
+/// Returns the shell's exit code.
+pub fn run(idle: Duration, mut saver: impl FnMut() -> io::Result<()>) -> io::Result<i32> {
+    let input = io::stdin().as_raw_fd();
+    // SAFETY: tcgetattr only writes to the termios we pass it
+    let termios = unsafe {
+        let mut termios: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(input, &mut termios) != 0 {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "stdin is not a terminal"));
+        }
+        termios
+    };
+    let (cols, rows) = terminal::size()?;
+    let (master, slave) = open_pty(&termios, cols, rows)?;
+
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+    let mut command = Command::new(&shell);
+    command
+        .env(ENV_VAR, "1")
+        .stdin(Stdio::from(slave.try_clone()?))
+        .stdout(Stdio::from(slave.try_clone()?))
+        .stderr(Stdio::from(slave));
+    // SAFETY: only async-signal-safe calls between fork and exec
+    unsafe {
+        command.pre_exec(|| {
+            // A session of its own, with the pty as its controlling terminal, so job control works
+            if libc::setsid() < 0 || libc::ioctl(0, libc::TIOCSCTTY, 0) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let mut child = command.spawn().map_err(|err| io::Error::new(err.kind(), format!("cannot start {}: {}", shell, err)))?;
+    // Dropping the command closes our copies of the pty's shell end, so reads see it close
+    drop(command);
+
+    // SAFETY: the handler only stores to an atomic; no SA_RESTART so poll wakes up
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = on_resize as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        libc::sigaction(libc::SIGWINCH, &action, std::ptr::null_mut());
+    }
+
+    terminal::enable_raw_mode()?;
+    let result = proxy(input, File::from(master), idle, &mut saver);
+    terminal::disable_raw_mode()?;
+    result?;
+
+    let status = child.wait()?;
+    // A shell killed by a signal exits with 128 + the signal, as shells report it
+    Ok(status.code().unwrap_or_else(|| 128 + std::os::unix::process::ExitStatusExt::signal(&status).unwrap_or(0)))
+}
+
+fn proxy(input: RawFd, mut master: File, idle: Duration, saver: &mut impl FnMut() -> io::Result<()>) -> io::Result<()> {
+    let mut out = io::stdout();
+    let mut screen = ScreenTracker::default();
+    let mut buffer = [0u8; 4096];
+    let mut last_input = Instant::now();
+
+    loop {
+        if RESIZED.swap(false, Ordering::Relaxed) {
+            let (cols, rows) = terminal::size()?;
+            set_size(&master, cols, rows)?;
+        }
+
+        let remaining = idle.saturating_sub(last_input.elapsed());
+        if remaining.is_zero() {
+            saver()?;
+            terminal::enable_raw_mode()?;
+            // Leaving the saver restored the normal screen; a full-screen program needs its screen back
+            if screen.alternate {
+                out.write_all(b"\x1b[?1049h")?;
+                out.flush()?;
+            }
+            // Resizing signals the shell if the size changed while the saver ran
+            let (cols, rows) = terminal::size()?;
+            set_size(&master, cols, rows)?;
+            if screen.alternate {
+                redraw(&master);
+            }
+            last_input = Instant::now();
+            continue;
+        }
+
+        let mut fds = [
+            libc::pollfd { fd: input, events: libc::POLLIN, revents: 0 },
+            libc::pollfd { fd: master.as_raw_fd(), events: libc::POLLIN, revents: 0 },
+        ];
+        let timeout = remaining.as_millis().saturating_add(1).min(i32::MAX as u128) as i32;
+        // SAFETY: fds is a valid array of the length passed
+        if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, timeout) } < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(err);
+        }
+
+        if fds[0].revents & (libc::POLLIN | libc::POLLHUP) != 0 {
+            // Read the descriptor directly; stdin's buffer would hide input from poll
+            // SAFETY: the length passed is the buffer's size
+            let count = unsafe { libc::read(input, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len()) };
+            if count <= 0 {
+                // The terminal went away
+                return Ok(());
+            }
+            master.write_all(&buffer[..count as usize])?;
+            last_input = Instant::now();
+        }
+        if fds[1].revents & (libc::POLLIN | libc::POLLHUP | libc::POLLERR) != 0 {
+            match master.read(&mut buffer) {
+                Ok(count) if count > 0 => {
+                    screen.scan(&buffer[..count]);
+                    out.write_all(&buffer[..count])?;
+                    out.flush()?;
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                // EIO once the shell and everything it started have closed the pty
+                _ => return Ok(()),
+            }
+        }
+    }
+}
+
+fn open_pty(termios: &libc::termios, cols: u16, rows: u16) -> io::Result<(OwnedFd, OwnedFd)> {
+    let size = libc::winsize { ws_row: rows, ws_col: cols, ws_xpixel: 0, ws_ypixel: 0 };
+    let (mut master, mut slave) = (0, 0);
+    // SAFETY: openpty writes the two descriptors and reads the settings and size we pass
+    unsafe {
+        if libc::openpty(&mut master, &mut slave, std::ptr::null_mut(), termios, &size) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // Neither end should leak into the shell beyond its stdin, stdout and stderr
+        libc::fcntl(master, libc::F_SETFD, libc::FD_CLOEXEC);
+        libc::fcntl(slave, libc::F_SETFD, libc::FD_CLOEXEC);
+        Ok((OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)))
+    }
+}
+
+fn set_size(master: &File, cols: u16, rows: u16) -> io::Result<()> {
+    let size = libc::winsize { ws_row: rows, ws_col: cols, ws_xpixel: 0, ws_ypixel: 0 };
+    // SAFETY: TIOCSWINSZ reads the winsize we pass
+    if unsafe { libc::ioctl(master.as_raw_fd(), libc::TIOCSWINSZ, &size) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Ask whatever runs in the foreground of the shell to repaint, as after a resize.
+fn redraw(master: &File) {
+    // SAFETY: plain syscalls on a descriptor we own
+    unsafe {
+        let group = libc::tcgetpgrp(master.as_raw_fd());
+        if group > 0 {
+            libc::kill(-group, libc::SIGWINCH);
+        }
+    }
+}